@@ -0,0 +1,625 @@
+use crate::circuit::{Circuit, Gate, WireId};
+use crate::error::{error_at, RewriteError, RewriteErrorType};
+use crate::layout::Layout;
+use crate::lowering::{output_len, CircuitOptions};
+use crate::rewriter::RewriterResult;
+use crate::symbol::Function;
+use std::collections::HashMap;
+use std::fmt;
+use zoker_parser::location::Location;
+
+/// Gate of a Bristol Fashion circuit, over single-bit wires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BristolGate {
+    Xor(usize, usize, usize),
+    And(usize, usize, usize),
+    Inv(usize, usize),
+    /// Copies a wire.
+    Eqw(usize, usize),
+    /// Sets a wire to a constant bit.
+    Eq(bool, usize),
+}
+
+impl BristolGate {
+    fn inputs(&self) -> Vec<usize> {
+        match *self {
+            BristolGate::Xor(a, b, _) | BristolGate::And(a, b, _) => vec![a, b],
+            BristolGate::Inv(a, _) | BristolGate::Eqw(a, _) => vec![a],
+            BristolGate::Eq(..) => vec![],
+        }
+    }
+
+    fn output(&self) -> usize {
+        match *self {
+            BristolGate::Xor(_, _, out)
+            | BristolGate::And(_, _, out)
+            | BristolGate::Inv(_, out)
+            | BristolGate::Eqw(_, out)
+            | BristolGate::Eq(_, out) => out,
+        }
+    }
+}
+
+/// Boolean circuit in the Bristol Fashion format.
+///
+/// Input values take the first wires and output values the last ones, each
+/// value starting with its least significant bit. On the Zoker side a value
+/// of `n` bits is `ceil(n / 32)` big-endian words, as any other value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BristolCircuit {
+    pub num_wires: usize,
+    /// Bits of each input value.
+    pub inputs: Vec<usize>,
+    /// Bits of each output value.
+    pub outputs: Vec<usize>,
+    pub gates: Vec<BristolGate>,
+}
+
+fn syntax_error(message: String) -> RewriteError {
+    error_at(RewriteErrorType::SyntaxError(message), Location::default())
+}
+
+fn word_count(bits: usize) -> usize {
+    bits.div_ceil(32)
+}
+
+/// Word and bit position of bit `bit` of a value of `words` words.
+fn bit_position(words: usize, bit: usize) -> (usize, u32) {
+    (words - 1 - bit / 32, (bit % 32) as u32)
+}
+
+fn number(token: &str) -> RewriterResult<usize> {
+    token
+        .parse::<usize>()
+        .map_err(|_| syntax_error(format!("expected a number, found `{}`", token)))
+}
+
+impl BristolCircuit {
+    pub fn parse(source: &str) -> RewriterResult<Self> {
+        let mut tokens = source.split_whitespace();
+        let mut next = || {
+            tokens
+                .next()
+                .ok_or_else(|| syntax_error("unexpected end of circuit".to_string()))
+        };
+
+        let num_gates = number(next()?)?;
+        let mut circuit = BristolCircuit {
+            num_wires: number(next()?)?,
+            ..BristolCircuit::default()
+        };
+        for _ in 0..number(next()?)? {
+            circuit.inputs.push(number(next()?)?);
+        }
+        for _ in 0..number(next()?)? {
+            circuit.outputs.push(number(next()?)?);
+        }
+        for _ in 0..num_gates {
+            let (num_in, num_out) = (number(next()?)?, number(next()?)?);
+            let mut wires = vec![];
+            for _ in 0..num_in + num_out {
+                wires.push(next()?);
+            }
+            let op = next()?;
+            if op == "EQ" && (num_in, num_out) == (1, 1) {
+                let bit = match wires[0] {
+                    "0" => false,
+                    "1" => true,
+                    bit => return Err(syntax_error(format!("`{}` is not a bit", bit))),
+                };
+                circuit.gates.push(BristolGate::Eq(bit, number(wires[1])?));
+                continue;
+            }
+            let wires = wires
+                .into_iter()
+                .map(number)
+                .collect::<RewriterResult<Vec<usize>>>()?;
+            match (op, num_in, num_out) {
+                ("XOR", 2, 1) => circuit
+                    .gates
+                    .push(BristolGate::Xor(wires[0], wires[1], wires[2])),
+                ("AND", 2, 1) => circuit
+                    .gates
+                    .push(BristolGate::And(wires[0], wires[1], wires[2])),
+                ("INV", 1, 1) | ("NOT", 1, 1) => {
+                    circuit.gates.push(BristolGate::Inv(wires[0], wires[1]))
+                }
+                ("EQW", 1, 1) => circuit.gates.push(BristolGate::Eqw(wires[0], wires[1])),
+                ("MAND", _, n) if num_in == 2 * n => {
+                    for i in 0..n {
+                        circuit.gates.push(BristolGate::And(
+                            wires[i],
+                            wires[n + i],
+                            wires[2 * n + i],
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(syntax_error(format!(
+                        "unsupported gate `{}` with {} inputs and {} outputs",
+                        op, num_in, num_out
+                    )))
+                }
+            }
+        }
+        Ok(circuit)
+    }
+
+    /// Exports the circuit of `function`.
+    ///
+    /// Every entry of the layout of the parameters and return values
+    /// becomes a value, private parameters first, then public ones, each
+    /// in declaration order. Each state variable adds its old value as a
+    /// public input and, if written, its new value as an output. Private
+    /// ones add their old value and salts as private inputs and their
+    /// commitments as outputs instead. Each storage access adds the old
+    /// value of its entry as a public input, and its `active` word, key and
+    /// written value as outputs. Each `emit` adds its `active` word and
+    /// arguments as outputs. The `valid` word of functions with
+    /// requirements and, with checked arithmetic, the `ok` word are last
+    /// 32-bit output values.
+    pub fn from_function(function: &Function, options: CircuitOptions) -> RewriterResult<Self> {
+        let circuit = Circuit::from_function(function, options)?;
+        let layout = Layout::of(function);
+        let mut inputs = vec![];
+        for is_private in [true, false].iter() {
+            for param in &layout.params {
+                if param.is_private == *is_private && param.words > 0 {
+                    inputs.push(param.words);
+                }
+            }
+            for state in &layout.state {
+                if state.old.is_private != *is_private {
+                    continue;
+                }
+                inputs.push(state.old.words);
+                if let Some(commitment) = &state.commitment {
+                    inputs.push(commitment.salt.words);
+                    inputs.extend(commitment.new_salt.iter().map(|salt| salt.words));
+                }
+            }
+        }
+        inputs.extend(layout.storage.iter().map(|entry| entry.old.words));
+        let mut outputs = layout
+            .returns
+            .iter()
+            .map(|ret| ret.words)
+            .filter(|&words| words > 0)
+            .collect::<Vec<usize>>();
+        for state in &layout.state {
+            outputs.extend(state.new.iter().map(|new| new.words));
+            if let Some(commitment) = &state.commitment {
+                outputs.push(commitment.old.words);
+                outputs.extend(commitment.new.iter().map(|new| new.words));
+            }
+        }
+        for entry in &layout.storage {
+            outputs.push(1);
+            outputs.push(entry.key.words);
+            if let Some(new) = &entry.new {
+                outputs.push(new.words);
+            }
+        }
+        for entry in &layout.events {
+            outputs.push(1);
+            outputs.extend(entry.args.iter().map(|arg| arg.words));
+        }
+        let len: usize = outputs.iter().sum();
+        outputs.extend(vec![1; output_len(function, options) - len]);
+        Ok(BristolCircuit::from_circuit(&circuit, &inputs, &outputs))
+    }
+
+    /// Expands `circuit` into bits, `inputs` and `outputs` giving the words
+    /// of each value. Private input words come before public ones.
+    pub fn from_circuit(circuit: &Circuit, inputs: &[usize], outputs: &[usize]) -> Self {
+        Exporter::new(inputs, outputs).run(circuit)
+    }
+
+    /// Packs the circuit into words so `ZkBoo` can prove it.
+    ///
+    /// Input values marked in `is_private` are read from the private input,
+    /// the others from the public input. `AND` gates of the same depth are
+    /// computed 32 at a time, as one non-linear gate.
+    pub fn to_circuit(&self, is_private: &[bool]) -> RewriterResult<Circuit> {
+        if is_private.len() != self.inputs.len() {
+            return Err(syntax_error(format!(
+                "circuit has {} input values, {} given",
+                self.inputs.len(),
+                is_private.len()
+            )));
+        }
+        Importer::new(self, is_private).run()
+    }
+}
+
+impl fmt::Display for BristolCircuit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", self.gates.len(), self.num_wires)?;
+        let values = |values: &[usize]| {
+            let mut line = values.len().to_string();
+            for bits in values {
+                line.push_str(&format!(" {}", bits));
+            }
+            line
+        };
+        writeln!(f, "{}", values(&self.inputs))?;
+        writeln!(f, "{}", values(&self.outputs))?;
+        writeln!(f)?;
+        for gate in &self.gates {
+            match *gate {
+                BristolGate::Xor(a, b, out) => writeln!(f, "2 1 {} {} {} XOR", a, b, out)?,
+                BristolGate::And(a, b, out) => writeln!(f, "2 1 {} {} {} AND", a, b, out)?,
+                BristolGate::Inv(a, out) => writeln!(f, "1 1 {} {} INV", a, out)?,
+                BristolGate::Eqw(a, out) => writeln!(f, "1 1 {} {} EQW", a, out)?,
+                BristolGate::Eq(bit, out) => writeln!(f, "1 1 {} {} EQ", bit as u8, out)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bit {
+    Zero,
+    One,
+    Wire(usize),
+}
+
+struct Exporter<'a> {
+    inputs: &'a [usize],
+    outputs: &'a [usize],
+    bristol: BristolCircuit,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(inputs: &'a [usize], outputs: &'a [usize]) -> Self {
+        Exporter {
+            inputs,
+            outputs,
+            bristol: BristolCircuit {
+                num_wires: 32 * inputs.iter().sum::<usize>(),
+                inputs: inputs.iter().map(|words| 32 * words).collect(),
+                outputs: outputs.iter().map(|words| 32 * words).collect(),
+                gates: vec![],
+            },
+        }
+    }
+
+    fn run(mut self, circuit: &Circuit) -> BristolCircuit {
+        // Bits of every input word, private words first.
+        let mut input_bits = vec![];
+        let mut offset = 0;
+        for &words in self.inputs {
+            for word in 0..words {
+                input_bits.push(
+                    (0..32)
+                        .map(|i| {
+                            let bit = 32 * (words - 1 - word) + i;
+                            Bit::Wire(offset + bit)
+                        })
+                        .collect::<Vec<Bit>>(),
+                );
+            }
+            offset += 32 * words;
+        }
+
+        let mut bits: Vec<Vec<Bit>> = Vec::with_capacity(circuit.gates.len());
+        for gate in &circuit.gates {
+            let word = match *gate {
+                Gate::Const(value) => (0..32)
+                    .map(|i| {
+                        if (value >> i) & 1 == 1 {
+                            Bit::One
+                        } else {
+                            Bit::Zero
+                        }
+                    })
+                    .collect(),
+                Gate::Input { index, is_private } => {
+                    let index = if is_private {
+                        index
+                    } else {
+                        circuit.input_len + index
+                    };
+                    input_bits[index].clone()
+                }
+                Gate::Xor(a, b) => (0..32).map(|i| self.xor(bits[a][i], bits[b][i])).collect(),
+                Gate::And(a, b) => (0..32).map(|i| self.and(bits[a][i], bits[b][i])).collect(),
+                Gate::Not(a) => (0..32).map(|i| self.xor(bits[a][i], Bit::One)).collect(),
+                Gate::Add32(a, b) => self.add(&bits[a], &bits[b]),
+                Gate::Shl(a, n) => (0..32)
+                    .map(|i| {
+                        if i >= n {
+                            bits[a][(i - n) as usize]
+                        } else {
+                            Bit::Zero
+                        }
+                    })
+                    .collect(),
+                Gate::Shr(a, n) => (0..32)
+                    .map(|i| {
+                        if i + n < 32 {
+                            bits[a][(i + n) as usize]
+                        } else {
+                            Bit::Zero
+                        }
+                    })
+                    .collect(),
+                Gate::Rotl(a, n) => (0..32)
+                    .map(|i| bits[a][((i + 32 - n % 32) % 32) as usize])
+                    .collect(),
+                Gate::Rotr(a, n) => (0..32).map(|i| bits[a][(i + n as usize) % 32]).collect(),
+                Gate::BitSlice(a, lo, len) => (0..32)
+                    .map(|i| {
+                        if i < len {
+                            bits[a][(lo + i) as usize]
+                        } else {
+                            Bit::Zero
+                        }
+                    })
+                    .collect(),
+                Gate::Mask(a) => vec![bits[a][0]; 32],
+            };
+            bits.push(word);
+        }
+
+        // Output values must be the last wires.
+        let mut outputs = circuit.outputs.iter();
+        for &words in self.outputs {
+            let value = outputs.by_ref().take(words).collect::<Vec<&WireId>>();
+            for bit in 0..32 * words {
+                let (word, i) = bit_position(words, bit);
+                let out = self.bristol.num_wires;
+                self.bristol.num_wires += 1;
+                self.bristol
+                    .gates
+                    .push(match bits[*value[word]][i as usize] {
+                        Bit::Zero => BristolGate::Eq(false, out),
+                        Bit::One => BristolGate::Eq(true, out),
+                        Bit::Wire(wire) => BristolGate::Eqw(wire, out),
+                    });
+            }
+        }
+        self.bristol
+    }
+
+    fn push(&mut self, gate: impl Fn(usize) -> BristolGate) -> Bit {
+        let out = self.bristol.num_wires;
+        self.bristol.num_wires += 1;
+        self.bristol.gates.push(gate(out));
+        Bit::Wire(out)
+    }
+
+    fn xor(&mut self, a: Bit, b: Bit) -> Bit {
+        match (a, b) {
+            (Bit::Zero, x) | (x, Bit::Zero) => x,
+            (Bit::One, Bit::One) => Bit::Zero,
+            (Bit::One, Bit::Wire(x)) | (Bit::Wire(x), Bit::One) => {
+                self.push(|out| BristolGate::Inv(x, out))
+            }
+            (Bit::Wire(x), Bit::Wire(y)) => self.push(|out| BristolGate::Xor(x, y, out)),
+        }
+    }
+
+    fn and(&mut self, a: Bit, b: Bit) -> Bit {
+        match (a, b) {
+            (Bit::Zero, _) | (_, Bit::Zero) => Bit::Zero,
+            (Bit::One, x) | (x, Bit::One) => x,
+            (Bit::Wire(x), Bit::Wire(y)) => self.push(|out| BristolGate::And(x, y, out)),
+        }
+    }
+
+    fn add(&mut self, a: &[Bit], b: &[Bit]) -> Vec<Bit> {
+        let mut carry = Bit::Zero;
+        let mut sum = vec![];
+        for i in 0..32 {
+            let a_carry = self.xor(a[i], carry);
+            let b_carry = self.xor(b[i], carry);
+            let sum_bit = self.xor(a_carry, b[i]);
+            sum.push(sum_bit);
+            if i < 31 {
+                let both = self.and(a_carry, b_carry);
+                carry = self.xor(both, carry);
+            }
+        }
+        sum
+    }
+}
+
+struct Importer<'a> {
+    bristol: &'a BristolCircuit,
+    is_private: &'a [bool],
+    circuit: Circuit,
+    /// Circuit wire holding each Bristol wire in its lowest bit.
+    wires: Vec<Option<WireId>>,
+    one: Option<WireId>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(bristol: &'a BristolCircuit, is_private: &'a [bool]) -> Self {
+        Importer {
+            bristol,
+            is_private,
+            circuit: Circuit::default(),
+            wires: vec![None; bristol.num_wires],
+            one: None,
+        }
+    }
+
+    fn run(mut self) -> RewriterResult<Circuit> {
+        let mut wire = 0;
+        for (&bits, &is_private) in self.bristol.inputs.iter().zip(self.is_private) {
+            let words = word_count(bits);
+            let first = if is_private {
+                self.circuit.input_len
+            } else {
+                self.circuit.input_pub_len
+            };
+            let value = (0..words)
+                .map(|word| {
+                    self.circuit.push(Gate::Input {
+                        index: first + word,
+                        is_private,
+                    })
+                })
+                .collect::<Vec<WireId>>();
+            if is_private {
+                self.circuit.input_len += words;
+            } else {
+                self.circuit.input_pub_len += words;
+            }
+            for bit in 0..bits {
+                let (word, i) = bit_position(words, bit);
+                let id = self.circuit.push(Gate::BitSlice(value[word], i, 1));
+                self.define(wire, id)?;
+                wire += 1;
+            }
+        }
+
+        let depths = self.depths()?;
+        let max_depth = depths.iter().copied().max().unwrap_or(0);
+        for depth in 0..=max_depth {
+            let ands = self
+                .bristol
+                .gates
+                .iter()
+                .zip(&depths)
+                .filter(|&(gate, &d)| d == depth && matches!(gate, BristolGate::And(..)))
+                .map(|(gate, _)| *gate)
+                .collect::<Vec<BristolGate>>();
+            for chunk in ands.chunks(32) {
+                self.packed_and(chunk)?;
+            }
+            for (gate, &d) in self.bristol.gates.iter().zip(&depths) {
+                if d == depth && !matches!(gate, BristolGate::And(..)) {
+                    self.linear(gate)?;
+                }
+            }
+        }
+
+        let total: usize = self.bristol.outputs.iter().sum();
+        let mut wire = self
+            .bristol
+            .num_wires
+            .checked_sub(total)
+            .ok_or_else(|| syntax_error("outputs exceed the wires".to_string()))?;
+        for &bits in &self.bristol.outputs {
+            let words = word_count(bits);
+            let mut value = vec![None; words];
+            for bit in 0..bits {
+                let (word, i) = bit_position(words, bit);
+                let shifted = self.circuit.push(Gate::Shl(self.wire(wire)?, i));
+                value[word] = Some(match value[word] {
+                    Some(acc) => self.circuit.push(Gate::Xor(acc, shifted)),
+                    None => shifted,
+                });
+                wire += 1;
+            }
+            for word in value {
+                let id = match word {
+                    Some(id) => id,
+                    None => self.circuit.push(Gate::Const(0)),
+                };
+                self.circuit.outputs.push(id);
+            }
+        }
+        Ok(self.circuit)
+    }
+
+    /// Number of `AND` gates on the longest path to each gate.
+    fn depths(&self) -> RewriterResult<Vec<usize>> {
+        let mut wire_depth: HashMap<usize, usize> = HashMap::new();
+        let mut depths = vec![];
+        for gate in &self.bristol.gates {
+            let mut depth = 0;
+            for input in gate.inputs() {
+                if self.wires.get(input).copied().flatten().is_some() {
+                    continue;
+                }
+                depth = depth.max(*wire_depth.get(&input).ok_or_else(|| {
+                    syntax_error(format!("wire {} is read before it is set", input))
+                })?);
+            }
+            if let BristolGate::And(..) = gate {
+                depth += 1;
+            }
+            wire_depth.insert(gate.output(), depth);
+            depths.push(depth);
+        }
+        Ok(depths)
+    }
+
+    fn define(&mut self, wire: usize, id: WireId) -> RewriterResult<()> {
+        match self.wires.get_mut(wire) {
+            Some(slot) => {
+                *slot = Some(id);
+                Ok(())
+            }
+            None => Err(syntax_error(format!("wire {} is out of range", wire))),
+        }
+    }
+
+    fn wire(&self, wire: usize) -> RewriterResult<WireId> {
+        self.wires
+            .get(wire)
+            .copied()
+            .flatten()
+            .ok_or_else(|| syntax_error(format!("wire {} is read before it is set", wire)))
+    }
+
+    fn one(&mut self) -> WireId {
+        match self.one {
+            Some(id) => id,
+            None => {
+                let id = self.circuit.push(Gate::Const(1));
+                self.one = Some(id);
+                id
+            }
+        }
+    }
+
+    fn linear(&mut self, gate: &BristolGate) -> RewriterResult<()> {
+        let id = match *gate {
+            BristolGate::Xor(a, b, _) => {
+                let (a, b) = (self.wire(a)?, self.wire(b)?);
+                self.circuit.push(Gate::Xor(a, b))
+            }
+            BristolGate::Inv(a, _) => {
+                let (a, one) = (self.wire(a)?, self.one());
+                self.circuit.push(Gate::Xor(a, one))
+            }
+            BristolGate::Eqw(a, _) => self.wire(a)?,
+            BristolGate::Eq(bit, _) => self.circuit.push(Gate::Const(bit as u32)),
+            BristolGate::And(..) => unreachable!(),
+        };
+        self.define(gate.output(), id)
+    }
+
+    /// Computes up to 32 independent `AND` gates with one word `And`.
+    fn packed_and(&mut self, gates: &[BristolGate]) -> RewriterResult<()> {
+        let mut packed = (None, None);
+        for (i, gate) in gates.iter().enumerate() {
+            if let BristolGate::And(a, b, _) = *gate {
+                let (a, b) = (self.wire(a)?, self.wire(b)?);
+                packed.0 = Some(self.pack(packed.0, a, i as u32));
+                packed.1 = Some(self.pack(packed.1, b, i as u32));
+            }
+        }
+        if let (Some(a), Some(b)) = packed {
+            let and = self.circuit.push(Gate::And(a, b));
+            for (i, gate) in gates.iter().enumerate() {
+                let id = self.circuit.push(Gate::BitSlice(and, i as u32, 1));
+                self.define(gate.output(), id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn pack(&mut self, acc: Option<WireId>, bit: WireId, i: u32) -> WireId {
+        let shifted = self.circuit.push(Gate::Shl(bit, i));
+        match acc {
+            Some(acc) => self.circuit.push(Gate::Xor(acc, shifted)),
+            None => shifted,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::lowering::{input_len, lower_function, CircuitOptions};
+use crate::optimizer::optimize;
+use crate::rewriter::RewriterResult;
+use crate::symbol::Function;
+use std::cell::RefCell;
+use std::rc::Rc;
+use zoker_prover::ikos::{IKosResult, IKosVariable};
+use zoker_prover::utils::{check_bit_range, slice_bits};
+
+/// Index of the gate defining a wire.
+pub type WireId = usize;
+
+/// Gate of a circuit over 32-bit words.
+///
+/// Every gate defines exactly one wire and only reads wires defined before
+/// it. `And` and `Add32` are the non-linear gates, the others are computed
+/// locally on each share.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Gate {
+    Const(u32),
+    /// Word `index` of the private `input` or of the public `input_pub`.
+    Input {
+        index: usize,
+        is_private: bool,
+    },
+    Xor(WireId, WireId),
+    And(WireId, WireId),
+    Not(WireId),
+    Add32(WireId, WireId),
+    Shl(WireId, u32),
+    Shr(WireId, u32),
+    Rotl(WireId, u32),
+    Rotr(WireId, u32),
+    /// `len` bits starting at bit `lo`, moved down to bit 0.
+    BitSlice(WireId, u32, u32),
+    /// All ones when bit 0 is set, zero otherwise.
+    Mask(WireId),
+}
+
+impl Gate {
+    /// Wires the gate reads.
+    pub fn operands(&self) -> Vec<WireId> {
+        match *self {
+            Gate::Const(_) | Gate::Input { .. } => vec![],
+            Gate::Xor(a, b) | Gate::And(a, b) | Gate::Add32(a, b) => vec![a, b],
+            Gate::Not(a)
+            | Gate::Shl(a, _)
+            | Gate::Shr(a, _)
+            | Gate::Rotl(a, _)
+            | Gate::Rotr(a, _)
+            | Gate::BitSlice(a, _, _)
+            | Gate::Mask(a) => vec![a],
+        }
+    }
+
+    /// The same gate reading `f(wire)` instead of each `wire`.
+    pub fn map_operands(&self, f: impl Fn(WireId) -> WireId) -> Gate {
+        match *self {
+            Gate::Const(_) | Gate::Input { .. } => self.clone(),
+            Gate::Xor(a, b) => Gate::Xor(f(a), f(b)),
+            Gate::And(a, b) => Gate::And(f(a), f(b)),
+            Gate::Add32(a, b) => Gate::Add32(f(a), f(b)),
+            Gate::Not(a) => Gate::Not(f(a)),
+            Gate::Shl(a, n) => Gate::Shl(f(a), n),
+            Gate::Shr(a, n) => Gate::Shr(f(a), n),
+            Gate::Rotl(a, n) => Gate::Rotl(f(a), n),
+            Gate::Rotr(a, n) => Gate::Rotr(f(a), n),
+            Gate::BitSlice(a, lo, len) => Gate::BitSlice(f(a), lo, len),
+            Gate::Mask(a) => Gate::Mask(f(a)),
+        }
+    }
+}
+
+/// Number of gates of a circuit, with its non-linear `And` and `Add32`
+/// gates counted apart since each of them adds a word to every view.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GateCount {
+    pub gates: usize,
+    pub and: usize,
+    pub add: usize,
+}
+
+/// Flat, SSA-style circuit: the representation every backend consumes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Circuit {
+    pub gates: Vec<Gate>,
+    pub outputs: Vec<WireId>,
+    pub input_len: usize,
+    pub input_pub_len: usize,
+}
+
+impl Circuit {
+    /// Compiles `function` into a circuit, optimized if `options` ask so.
+    pub fn from_function(function: &Function, options: CircuitOptions) -> RewriterResult<Self> {
+        let (private_len, public_len) = input_len(function);
+        let circuit = Rc::new(RefCell::new(Circuit {
+            input_len: private_len,
+            input_pub_len: public_len,
+            ..Circuit::default()
+        }));
+        let input = (0..private_len)
+            .map(|index| {
+                Wire::new_gate(
+                    &circuit,
+                    Gate::Input {
+                        index,
+                        is_private: true,
+                    },
+                )
+            })
+            .collect::<Vec<Wire>>();
+        let input_pub = (0..public_len)
+            .map(|index| {
+                Wire::new_gate(
+                    &circuit,
+                    Gate::Input {
+                        index,
+                        is_private: false,
+                    },
+                )
+            })
+            .collect::<Vec<Wire>>();
+        let output = lower_function(function, options, &input, &input_pub)?;
+        for word in output {
+            let id = word.id(&circuit);
+            circuit.borrow_mut().outputs.push(id);
+        }
+        let circuit = circuit.borrow().clone();
+        if options.optimize {
+            return Ok(optimize(&circuit).0);
+        }
+        Ok(circuit)
+    }
+
+    pub fn gate_count(&self) -> GateCount {
+        let mut count = GateCount {
+            gates: self.gates.len(),
+            ..GateCount::default()
+        };
+        for gate in &self.gates {
+            match gate {
+                Gate::And(..) => count.and += 1,
+                Gate::Add32(..) => count.add += 1,
+                _ => {}
+            }
+        }
+        count
+    }
+
+    pub fn push(&mut self, gate: Gate) -> WireId {
+        self.gates.push(gate);
+        self.gates.len() - 1
+    }
+
+    /// Runs the circuit on shared or constant words.
+    pub fn evaluate<T: IKosVariable>(&self, input: &[T], input_pub: &[T]) -> IKosResult<Vec<T>> {
+        let mut wires: Vec<T> = Vec::with_capacity(self.gates.len());
+        for gate in &self.gates {
+            let wire = match *gate {
+                Gate::Const(value) => T::new_value(value),
+                Gate::Input { index, is_private } => {
+                    if is_private {
+                        input[index].clone()
+                    } else {
+                        input_pub[index].clone()
+                    }
+                }
+                Gate::Xor(a, b) => wires[a].clone().xor(&wires[b]),
+                Gate::And(a, b) => wires[a].clone().bit_and(&wires[b])?,
+                Gate::Not(a) => wires[a].clone().negate(),
+                Gate::Add32(a, b) => wires[a].clone().add_op(&wires[b])?,
+                Gate::Shl(a, n) => wires[a].clone().lshift(n),
+                Gate::Shr(a, n) => wires[a].clone().rshift(n),
+                Gate::Rotl(a, n) => wires[a].clone().rotl(n),
+                Gate::Rotr(a, n) => wires[a].clone().rotr(n),
+                Gate::BitSlice(a, lo, len) => wires[a].clone().bit_slice(lo, len),
+                Gate::Mask(a) => wires[a].clone().mask(),
+            };
+            wires.push(wire);
+        }
+        Ok(self.outputs.iter().map(|&id| wires[id].clone()).collect())
+    }
+}
+
+/// Word of a circuit under construction.
+///
+/// Gadgets run on wires like on shared words, recording gates instead of
+/// computing. Operations on constants only are folded right away, as the
+/// IKOS variables do.
+#[derive(Clone)]
+pub enum Wire {
+    Const(u32),
+    Gate(Rc<RefCell<Circuit>>, WireId),
+}
+
+impl Wire {
+    fn new_gate(circuit: &Rc<RefCell<Circuit>>, gate: Gate) -> Self {
+        let id = circuit.borrow_mut().push(gate);
+        Wire::Gate(Rc::clone(circuit), id)
+    }
+
+    fn id(&self, circuit: &Rc<RefCell<Circuit>>) -> WireId {
+        match self {
+            Wire::Const(value) => circuit.borrow_mut().push(Gate::Const(*value)),
+            Wire::Gate(_, id) => *id,
+        }
+    }
+
+    fn unary(self, gate: impl Fn(WireId) -> Gate, fold: impl Fn(u32) -> u32) -> Self {
+        match self {
+            Wire::Const(value) => Wire::Const(fold(value)),
+            Wire::Gate(circuit, id) => Wire::new_gate(&circuit, gate(id)),
+        }
+    }
+
+    fn binary(
+        self,
+        rhs: &Wire,
+        gate: impl Fn(WireId, WireId) -> Gate,
+        fold: impl Fn(u32, u32) -> u32,
+    ) -> Self {
+        let circuit = match (&self, rhs) {
+            (Wire::Const(a), Wire::Const(b)) => return Wire::Const(fold(*a, *b)),
+            (Wire::Gate(circuit, _), _) | (_, Wire::Gate(circuit, _)) => Rc::clone(circuit),
+        };
+        let (a, b) = (self.id(&circuit), rhs.id(&circuit));
+        Wire::new_gate(&circuit, gate(a, b))
+    }
+}
+
+impl IKosVariable for Wire {
+    fn new_value(value: u32) -> Self {
+        Wire::Const(value)
+    }
+
+    fn negate(self) -> Self {
+        self.unary(Gate::Not, |x| !x)
+    }
+
+    fn xor(self, rhs: &Self) -> Self {
+        self.binary(rhs, Gate::Xor, |x, y| x ^ y)
+    }
+
+    fn rshift(self, n: u32) -> Self {
+        self.unary(|a| Gate::Shr(a, n), |x| x >> n)
+    }
+
+    fn lshift(self, n: u32) -> Self {
+        self.unary(|a| Gate::Shl(a, n), |x| x << n)
+    }
+
+    fn rotr(self, n: u32) -> Self {
+        self.unary(|a| Gate::Rotr(a, n), |x| x.rotate_right(n))
+    }
+
+    fn rotl(self, n: u32) -> Self {
+        self.unary(|a| Gate::Rotl(a, n), |x| x.rotate_left(n))
+    }
+
+    fn byte(self, n: u32) -> Self {
+        assert!(n < 4, "byte {} is out of a 32-bit word", n);
+        self.bit_slice(24 - 8 * n, 8)
+    }
+
+    fn swap_bytes(self) -> Self {
+        let mut res = self.clone().byte(0);
+        for n in 1..4 {
+            res = res.xor(&self.clone().byte(n).lshift(8 * n));
+        }
+        res
+    }
+
+    fn bit_slice(self, lo: u32, len: u32) -> Self {
+        check_bit_range(lo, len);
+        self.unary(|a| Gate::BitSlice(a, lo, len), |x| slice_bits(x, lo, len))
+    }
+
+    fn mask(self) -> Self {
+        self.unary(Gate::Mask, |x| 0u32.wrapping_sub(x & 1))
+    }
+
+    fn bit_and(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(self.binary(rhs, Gate::And, |x, y| x & y))
+    }
+
+    fn add_op(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(self.binary(rhs, Gate::Add32, |x, y| x.wrapping_add(y)))
+    }
+
+    fn mux(self, a: &Self, b: &Self) -> IKosResult<Self> {
+        let mask = self.mask();
+        Ok(a.clone().xor(b).bit_and(&mask)?.xor(b))
+    }
+}
@@ -0,0 +1,248 @@
+use crate::error::type_error;
+use crate::interpreter::Value;
+use crate::layout::Layout;
+use crate::rewriter::RewriterResult;
+use crate::symbol::{Function, SymbolType};
+use num_bigint::BigUint;
+use zoker_parser::location::Location;
+
+// Canonical encodings of values as circuit words, shared with the
+// `encode_*` and `decode_*` functions of `zkboo.sol`.
+//
+// A fixed-size value is `word_count` big-endian words: 8 for `uint`,
+// `int` (two's complement) and `bytes32`, 5 for `address` and a single
+// 0 or 1 word for `bool`. Arrays are their elements one after the
+// other, the first one in the most significant words, and structs their
+// fields in declaration order. Strings and bytes are their length in
+// bytes followed by the bytes, four to a big-endian word, zero padded to
+// a multiple of 32 bytes. Bounded ones are zero padded further, to the
+// words of the longest value they hold.
+
+// Values are not source code, so their errors have no location.
+fn encoding_error<V>(message: String) -> RewriterResult<V> {
+    type_error(&message, Location::default())
+}
+
+/// Bits a value of a fixed-size scalar type holds.
+pub fn value_bits(symbol_type: &SymbolType) -> Option<usize> {
+    match symbol_type {
+        SymbolType::Bool => Some(1),
+        SymbolType::Address => Some(160),
+        SymbolType::Uint256 | SymbolType::Int256 | SymbolType::Bytes32 => Some(256),
+        SymbolType::Array(..)
+        | SymbolType::Mapping(..)
+        | SymbolType::Struct(..)
+        | SymbolType::String
+        | SymbolType::BoundedBytes(..)
+        | SymbolType::BoundedString(..)
+        | SymbolType::None => None,
+    }
+}
+
+/// Whether values of `symbol_type` are a fixed number of words.
+pub fn is_fixed_size(symbol_type: &SymbolType) -> bool {
+    match symbol_type {
+        SymbolType::Array(element, _) => is_fixed_size(element),
+        SymbolType::Struct(structure) => structure
+            .fields
+            .iter()
+            .all(|field| is_fixed_size(&field.symbol_type)),
+        _ => value_bits(symbol_type).is_some() || symbol_type.max_len().is_some(),
+    }
+}
+
+// Scalar values making up a `symbol_type` value, with the offset of
+// their first word.
+fn leaves(symbol_type: &SymbolType) -> Vec<(usize, SymbolType)> {
+    match symbol_type {
+        SymbolType::Array(element, size) => {
+            let count = element.word_count();
+            let leaves = leaves(element);
+            (0..*size)
+                .flat_map(|i| {
+                    leaves
+                        .iter()
+                        .map(move |(offset, leaf)| (i * count + offset, leaf.clone()))
+                })
+                .collect()
+        }
+        SymbolType::Struct(structure) => {
+            let mut words = vec![];
+            let mut offset = 0;
+            for field in &structure.fields {
+                words.extend(
+                    leaves(&field.symbol_type)
+                        .into_iter()
+                        .map(|(o, leaf)| (offset + o, leaf)),
+                );
+                offset += field.symbol_type.word_count();
+            }
+            words
+        }
+        _ => vec![(0, symbol_type.clone())],
+    }
+}
+
+/// Offsets of the words of a `symbol_type` value that hold a `bool`.
+pub fn bool_words(symbol_type: &SymbolType) -> Vec<usize> {
+    leaves(symbol_type)
+        .into_iter()
+        .filter(|(_, leaf)| *leaf == SymbolType::Bool)
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+/// Offsets of the length words of the bounded `bytes` and `string` values
+/// in the words of a `symbol_type` value, with their bounds.
+pub fn length_words(symbol_type: &SymbolType) -> Vec<(usize, usize)> {
+    leaves(symbol_type)
+        .into_iter()
+        .filter_map(|(offset, leaf)| Some((offset, leaf.max_len()?)))
+        .collect()
+}
+
+/// The lowest `count` words of `value`, most significant first.
+pub fn to_words(value: &BigUint, count: usize) -> Vec<u32> {
+    let mut words = value.to_u32_digits();
+    words.resize(count, 0);
+    words.reverse();
+    words
+}
+
+pub fn from_words(words: &[u32]) -> BigUint {
+    let mut digits = words.to_vec();
+    digits.reverse();
+    BigUint::new(digits)
+}
+
+/// Words of `value`, which must fit in `symbol_type`.
+///
+/// An array or struct value is the number its words make up.
+pub fn encode(symbol_type: &SymbolType, value: &BigUint) -> RewriterResult<Vec<u32>> {
+    if value_bits(symbol_type).is_none() && is_fixed_size(symbol_type) {
+        let words = to_words(value, symbol_type.word_count());
+        if from_words(&words) != *value {
+            return encoding_error(format!("value does not fit in {}", symbol_type));
+        }
+        decode(symbol_type, &words)?;
+        return Ok(words);
+    }
+    match value_bits(symbol_type) {
+        Some(bits) if value.bits() <= bits => Ok(to_words(value, symbol_type.word_count())),
+        Some(_) => encoding_error(format!("value does not fit in {}", symbol_type)),
+        None => encoding_error(format!("{} has no fixed-size encoding", symbol_type)),
+    }
+}
+
+/// Value of the words of a `symbol_type` value, checking they are canonical.
+pub fn decode(symbol_type: &SymbolType, words: &[u32]) -> RewriterResult<BigUint> {
+    if !is_fixed_size(symbol_type) {
+        return encoding_error(format!("{} has no fixed-size encoding", symbol_type));
+    }
+    if words.len() != symbol_type.word_count() {
+        return encoding_error(format!(
+            "{} takes {} words, found {}",
+            symbol_type,
+            symbol_type.word_count(),
+            words.len()
+        ));
+    }
+    for offset in bool_words(symbol_type) {
+        if words[offset] > 1 {
+            return encoding_error(format!("{} is not a bool", words[offset]));
+        }
+    }
+    for (offset, leaf) in leaves(symbol_type) {
+        if let Some(max_len) = leaf.max_len() {
+            decode_bounded(&words[offset..offset + leaf.word_count()], max_len)?;
+        }
+    }
+    Ok(from_words(words))
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u32> {
+    let mut padded = bytes.to_vec();
+    padded.resize(bytes.len().div_ceil(32) * 32, 0);
+    let mut words = vec![bytes.len() as u32];
+    words.extend(
+        padded
+            .chunks(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+    );
+    words
+}
+
+pub fn decode_bytes(words: &[u32]) -> RewriterResult<Vec<u8>> {
+    let len = match words.first() {
+        Some(&len) => len as usize,
+        None => return encoding_error("missing length word".to_string()),
+    };
+    if words.len() != 1 + len.div_ceil(32) * 8 {
+        return encoding_error(format!(
+            "{} bytes take {} words, found {}",
+            len,
+            1 + len.div_ceil(32) * 8,
+            words.len()
+        ));
+    }
+    let mut bytes = words[1..]
+        .iter()
+        .flat_map(|word| word.to_be_bytes().to_vec())
+        .collect::<Vec<u8>>();
+    if bytes[len..].iter().any(|&b| b != 0) {
+        return encoding_error("padding is not zero".to_string());
+    }
+    bytes.truncate(len);
+    Ok(bytes)
+}
+
+/// Words of `bytes` as a value of a type holding at most `max_len` bytes.
+pub fn encode_bounded(bytes: &[u8], max_len: usize) -> RewriterResult<Vec<u32>> {
+    if bytes.len() > max_len {
+        return encoding_error(format!("{} bytes do not fit in {}", bytes.len(), max_len));
+    }
+    let mut words = encode_bytes(bytes);
+    words.resize(1 + max_len.div_ceil(32) * 8, 0);
+    Ok(words)
+}
+
+/// Bytes of the words of a value of a type holding at most `max_len`
+/// bytes, checking they are canonical.
+pub fn decode_bounded(words: &[u32], max_len: usize) -> RewriterResult<Vec<u8>> {
+    let len = words.first().map_or(0, |&len| len as usize);
+    if len > max_len {
+        return encoding_error(format!("{} bytes do not fit in {}", len, max_len));
+    }
+    let count = 1 + len.div_ceil(32) * 8;
+    if words.len() != 1 + max_len.div_ceil(32) * 8 || words[count..].iter().any(|&w| w != 0) {
+        return encoding_error("padding is not zero".to_string());
+    }
+    decode_bytes(&words[..count])
+}
+
+pub fn encode_string(s: &str) -> Vec<u32> {
+    encode_bytes(s.as_bytes())
+}
+
+pub fn decode_string(words: &[u32]) -> RewriterResult<String> {
+    match String::from_utf8(decode_bytes(words)?) {
+        Ok(s) => Ok(s),
+        Err(_) => encoding_error("string is not valid UTF-8".to_string()),
+    }
+}
+
+/// Return values of `function` from the output words of its proof.
+///
+/// Words after the return values, like the `ok` word, are ignored.
+pub fn decode_outputs(function: &Function, output: &[u32]) -> RewriterResult<Vec<Value>> {
+    let mut values = vec![];
+    for entry in Layout::of(function).returns {
+        let words = match output.get(entry.offset..entry.offset + entry.words) {
+            Some(words) => words,
+            None => return encoding_error("output is too short".to_string()),
+        };
+        let value = decode(&entry.symbol_type, words)?;
+        values.push(Value::new(entry.symbol_type, value));
+    }
+    Ok(values)
+}
@@ -1,4 +1,6 @@
+use crate::rewriter::RewriterResult;
 use zoker_parser::location::Location;
+use zoker_prover::ikos::IKosError;
 
 #[derive(Debug, PartialEq)]
 pub struct RewriteError {
@@ -10,6 +12,34 @@ pub struct RewriteError {
 pub enum RewriteErrorType {
     SyntaxError(String),
     TypeError(String),
+    CircuitError(String),
+    /// A `require` or `assert` does not hold on the inputs given.
+    RequirementError(String),
     UnsupportedError,
     Unreachable,
 }
+
+/// `error` at `location`, that of the operation it is about.
+///
+/// Operations made by the rewriter keep the location of the code they
+/// come from; others, and errors about no operation, are at the default
+/// location.
+pub fn error_at(error: RewriteErrorType, location: Location) -> RewriteError {
+    RewriteError { error, location }
+}
+
+pub fn type_error<V>(message: &str, location: Location) -> RewriterResult<V> {
+    Err(error_at(
+        RewriteErrorType::TypeError(message.to_string()),
+        location,
+    ))
+}
+
+impl From<IKosError> for RewriteError {
+    fn from(err: IKosError) -> Self {
+        RewriteError {
+            error: RewriteErrorType::CircuitError(err.error),
+            location: Location::default(),
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use crate::error::type_error;
+use crate::rewriter::RewriterResult;
+use crate::symbol::{Function, Operation, OperationType, Symbol, SymbolType};
+use std::collections::HashMap;
+use zoker_parser::location::Location;
+
+/// Checks that private values only reach the output of `function` through
+/// `reveal`.
+///
+/// A value is private if it is computed from private parameters, private
+/// state or private fields, or assigned under a condition that is. Other
+/// variables, private ones included, are as private as what they hold.
+/// Digests made with `sha256` and revealed values are public. The public
+/// parts of the return value, the public state the function writes, the
+/// mapping entries it accesses, keys included, and the events it emits
+/// all end up in the output. A private return value stays in the circuit.
+///
+/// Privacy is tracked per part of a value, so a struct may hold private
+/// values in its private fields only.
+pub fn check_flow(function: &Function) -> RewriterResult<()> {
+    let inputs = function.params.iter().chain(
+        function
+            .state
+            .iter()
+            .map(|state| &state.local)
+            .filter(|local| local.is_private),
+    );
+    let tainted = inputs
+        .map(|input| {
+            let parts = input.symbol_type.parts("", input.is_private);
+            let taint = parts.iter().map(|part| part.is_private).collect();
+            ((input.is_private, input.num), taint)
+        })
+        .collect();
+    let mut flow = Flow {
+        tainted,
+        changed: true,
+        leak: None,
+        origins: HashMap::new(),
+    };
+    let output = function
+        .returns
+        .first()
+        .map(|ret| {
+            ret.symbol_type
+                .parts(&ret.name, ret.is_private)
+                .iter()
+                .map(|part| !part.is_private)
+                .collect::<Vec<bool>>()
+        })
+        .unwrap_or_default();
+    // Variables may be tainted after they were read, in loops and later
+    // branches, so the function is walked until nothing changes.
+    while flow.changed {
+        flow.changed = false;
+        flow.leak = None;
+        flow.block(&function.operations, false, &output);
+    }
+    if let Some((leak, location)) = flow.leak {
+        return type_error(&leak, location);
+    }
+    for state in &function.state {
+        let key = (state.local.is_private, state.local.num);
+        if state.is_write && !state.local.is_private && flow.is_tainted(key) {
+            return type_error(
+                &format!(
+                    "`{}` depends on private values; `reveal` it",
+                    state.variable.name
+                ),
+                flow.origins[&key],
+            );
+        }
+    }
+    Ok(())
+}
+
+// Number of parts of a value of `symbol_type`.
+fn part_count(symbol_type: &SymbolType) -> usize {
+    symbol_type.parts("", false).len()
+}
+
+// `taint` for a value of `len` parts: as is if it has as many, else the
+// whole value is private if any of it is.
+fn spread(taint: Vec<bool>, len: usize) -> Vec<bool> {
+    if taint.len() == len {
+        taint
+    } else {
+        vec![taint.iter().any(|&bit| bit); len]
+    }
+}
+
+fn either(a: Vec<bool>, b: &[bool]) -> Vec<bool> {
+    a.iter().zip(b).map(|(&a, &b)| a || b).collect()
+}
+
+// Parts of a `symbol_type` struct making up `field`, as a range.
+fn field_parts(symbol_type: &SymbolType, field: &str) -> Option<(usize, usize)> {
+    let structure = symbol_type.as_struct()?;
+    let mut start = 0;
+    for candidate in &structure.fields {
+        let count = part_count(&candidate.symbol_type);
+        if candidate.name == field {
+            return Some((start, count));
+        }
+        start += count;
+    }
+    None
+}
+
+struct Flow {
+    // Private parts of variables, by variable.
+    tainted: HashMap<(bool, u32), Vec<bool>>,
+    changed: bool,
+    // First leak found in the last walk, and where.
+    leak: Option<(String, Location)>,
+    // Where each tainted variable was first assigned private values.
+    origins: HashMap<(bool, u32), Location>,
+}
+
+impl Flow {
+    fn is_tainted(&self, key: (bool, u32)) -> bool {
+        self.tainted
+            .get(&key)
+            .is_some_and(|taint| taint.iter().any(|&bit| bit))
+    }
+
+    fn leak(&mut self, message: String, location: Location) {
+        if self.leak.is_none() {
+            self.leak = Some((message, location));
+        }
+    }
+
+    // `path` is set under conditions on private values. Returns whether a
+    // `return` may have been taken under one, which makes whether the rest
+    // of the enclosing blocks runs private as well.
+    fn block(&mut self, stmts: &[Operation], mut path: bool, output: &[bool]) -> bool {
+        let mut returned = false;
+        for stmt in stmts {
+            match &stmt.operation {
+                OperationType::If { cond, stmts } | OperationType::Else { cond, stmts } => {
+                    let cond = self.value(cond, path).contains(&true);
+                    if self.block(stmts, path || cond, output) {
+                        path = true;
+                        returned = true;
+                    }
+                }
+                OperationType::For {
+                    iter,
+                    vector,
+                    stmts,
+                } => {
+                    let taint = self.value(vector, path);
+                    self.taint(iter, taint, path);
+                    if self.block(stmts, path, output) {
+                        path = true;
+                        returned = true;
+                    }
+                }
+                OperationType::Return { ret } => {
+                    // Which value is returned depends on the path taken.
+                    let taint = spread(self.value(ret, path), output.len())
+                        .into_iter()
+                        .map(|bit| bit || path);
+                    returned |= path;
+                    if taint.zip(output).any(|(bit, &out)| bit && out) {
+                        self.leak(
+                            "return value depends on private values; `reveal` it".to_string(),
+                            stmt.location,
+                        );
+                    }
+                }
+                _ => {
+                    self.value(stmt, path);
+                }
+            }
+        }
+        returned
+    }
+
+    // Private parts of the value of `operation`, assigning as it goes.
+    fn value(&mut self, operation: &Operation, path: bool) -> Vec<bool> {
+        match &operation.operation {
+            OperationType::Symbol { symbol } => {
+                let parts = symbol.symbol_type.parts("", false);
+                let taint = parts.iter().map(|part| part.is_private).collect();
+                match self.tainted.get(&(symbol.is_private, symbol.num)) {
+                    Some(tainted) => either(taint, tainted),
+                    None => taint,
+                }
+            }
+            OperationType::Constant { .. } | OperationType::Nop => vec![false],
+            OperationType::Reveal { value } => {
+                self.value(value, path);
+                vec![false]
+            }
+            OperationType::Sha256 { args } => {
+                for arg in args {
+                    self.value(arg, path);
+                }
+                vec![false]
+            }
+            OperationType::Assign { left, right } => {
+                let taint = self.value(right, path);
+                self.taint(left, taint.clone(), path);
+                taint
+            }
+            OperationType::Member { value, field } => {
+                let taint = self.value(value, path);
+                let range = value
+                    .variable_type()
+                    .and_then(|(symbol_type, _)| field_parts(&symbol_type, field));
+                match range {
+                    Some((start, count)) if taint.len() >= start + count => {
+                        taint[start..start + count].to_vec()
+                    }
+                    _ => spread(taint, 1),
+                }
+            }
+            OperationType::Index { array, index } => {
+                let taint = self.value(array, path);
+                let index = self.value(index, path).contains(&true);
+                let count = operation
+                    .variable_type()
+                    .map_or(1, |(element, _)| part_count(&element));
+                let mut element = vec![index; count];
+                if taint.len().is_multiple_of(count) {
+                    for chunk in taint.chunks(count) {
+                        element = either(element, chunk);
+                    }
+                } else {
+                    element = either(element, &spread(taint, count));
+                }
+                element
+            }
+            OperationType::Emit { event, args, .. } => {
+                let mut taint = false;
+                for arg in args {
+                    taint |= self.value(arg, path).contains(&true);
+                }
+                if path {
+                    self.leak(
+                        format!(
+                            "`{}` is emitted under a condition on private values",
+                            event.name
+                        ),
+                        operation.location,
+                    );
+                } else if taint {
+                    self.leak(
+                        format!(
+                            "arguments of `{}` depend on private values; `reveal` them",
+                            event.name
+                        ),
+                        operation.location,
+                    );
+                }
+                vec![false]
+            }
+            OperationType::Load { mapping, key, .. } => {
+                self.key(mapping, key, path);
+                if path {
+                    self.leak(
+                        format!(
+                            "`{}` is accessed under a condition on private values",
+                            mapping.name
+                        ),
+                        operation.location,
+                    );
+                }
+                vec![false]
+            }
+            OperationType::Store {
+                mapping,
+                key,
+                value,
+                ..
+            } => {
+                self.key(mapping, key, path);
+                let taint = self.value(value, path);
+                if path {
+                    self.leak(
+                        format!(
+                            "`{}` is accessed under a condition on private values",
+                            mapping.name
+                        ),
+                        operation.location,
+                    );
+                } else if taint.contains(&true) {
+                    self.leak(
+                        format!(
+                            "value written to `{}` depends on private values; `reveal` it",
+                            mapping.name
+                        ),
+                        operation.location,
+                    );
+                }
+                taint
+            }
+            _ => {
+                let mut taint = false;
+                for child in operation.children() {
+                    taint |= self.value(child, path).contains(&true);
+                }
+                vec![taint]
+            }
+        }
+    }
+
+    // Keys of mapping accesses are output as they are.
+    fn key(&mut self, mapping: &Symbol, key: &Operation, path: bool) {
+        if self.value(key, path).contains(&true) {
+            self.leak(
+                format!(
+                    "key of `{}` depends on private values; hash it with `sha256` or `reveal` it",
+                    mapping.name
+                ),
+                key.location,
+            );
+        }
+    }
+
+    // Marks the parts of the variable `target` assigns as private as
+    // `taint` and `path` say.
+    fn taint(&mut self, target: &Operation, taint: Vec<bool>, path: bool) {
+        let (key, symbol_type, start, count, index) = match self.target(target, path) {
+            Some(target) => target,
+            None => return,
+        };
+        let taint = spread(taint, count)
+            .into_iter()
+            .map(|bit| bit || path || index)
+            .collect::<Vec<bool>>();
+        if !taint.contains(&true) {
+            return;
+        }
+        let len = part_count(&symbol_type);
+        let tainted = self.tainted.entry(key).or_insert_with(|| vec![false; len]);
+        for (i, bit) in taint.into_iter().enumerate() {
+            if bit && !tainted[start + i] {
+                tainted[start + i] = true;
+                self.changed = true;
+                self.origins.entry(key).or_insert(target.location);
+            }
+        }
+    }
+
+    // Variable `target` assigns, its type, the range of its parts assigned
+    // and whether an index choosing them is private.
+    #[allow(clippy::type_complexity)]
+    fn target(
+        &mut self,
+        target: &Operation,
+        path: bool,
+    ) -> Option<((bool, u32), SymbolType, usize, usize, bool)> {
+        match &target.operation {
+            OperationType::Symbol { symbol } => Some((
+                (symbol.is_private, symbol.num),
+                symbol.symbol_type.clone(),
+                0,
+                part_count(&symbol.symbol_type),
+                false,
+            )),
+            OperationType::Member { value, field } => {
+                let (key, symbol_type, start, count, index) = self.target(value, path)?;
+                let (value_type, _) = value.variable_type()?;
+                match field_parts(&value_type, field) {
+                    Some((offset, field_count)) if field_count <= count => {
+                        Some((key, symbol_type, start + offset, field_count, index))
+                    }
+                    _ => Some((key, symbol_type, start, count, index)),
+                }
+            }
+            // Any element may be the one assigned.
+            OperationType::Index { array, index } => {
+                let private_index = self.value(index, path).contains(&true);
+                let (key, symbol_type, start, count, index) = self.target(array, path)?;
+                Some((key, symbol_type, start, count, index || private_index))
+            }
+            _ => None,
+        }
+    }
+}
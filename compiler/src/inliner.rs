@@ -0,0 +1,322 @@
+use crate::error::{error_at, type_error, RewriteErrorType};
+use crate::rewriter::RewriterResult;
+use crate::symbol::{
+    Contract, Function, Operation, OperationType, Symbol, SymbolLocation, SymbolType,
+};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use zoker_parser::location::Location;
+
+/// Replaces every call in the functions of `contract` with the body of
+/// the function it calls, since circuits cannot call.
+///
+/// Each call site gets its own copy of the callee's variables, numbered
+/// after the caller's. The arguments are assigned to the copied
+/// parameters, and the callee's `return`s assign its result variable;
+/// statements after a `return` taken in a branch only run while the
+/// call's `done` variable is unset. Recursion is rejected.
+pub fn inline_calls(contract: &mut Contract) -> RewriterResult<()> {
+    let functions = contract.functions.clone();
+    let mut inliner = Inliner {
+        functions: &functions,
+        inlined: HashMap::new(),
+        stack: vec![],
+    };
+    for function in contract.functions.iter_mut() {
+        *function = inliner.function(&function.name, Location::default())?;
+    }
+    Ok(())
+}
+
+fn constant(value: u32) -> Operation {
+    Operation::new(OperationType::Constant {
+        value: BigUint::from(value),
+    })
+}
+
+fn assign(symbol: &Symbol, right: Operation) -> Operation {
+    Operation::new(OperationType::Assign {
+        left: Box::new(Operation::new_symbol(symbol.clone())),
+        right: Box::new(right),
+    })
+}
+
+/// A new private variable of `function`.
+fn fresh_variable(function: &mut Function, name: String, symbol_type: SymbolType) -> Symbol {
+    let num = function.private_num;
+    function.private_num += 1;
+    Symbol {
+        name,
+        num,
+        symbol_type,
+        data_location: SymbolLocation::Memory,
+        is_private: true,
+    }
+}
+
+fn rename_symbols(operation: &mut Operation, rename: &dyn Fn(&Symbol) -> Symbol) {
+    if let OperationType::Symbol { symbol } = &mut operation.operation {
+        *symbol = rename(symbol);
+    }
+    for child in operation.children_mut() {
+        rename_symbols(child, rename);
+    }
+}
+
+fn has_return(operation: &Operation) -> bool {
+    match &operation.operation {
+        OperationType::Return { .. } => true,
+        OperationType::If { stmts, .. }
+        | OperationType::Else { stmts, .. }
+        | OperationType::For { stmts, .. } => stmts.iter().any(has_return),
+        _ => false,
+    }
+}
+
+/// `stmts`, run only while `done` is unset.
+fn unless_done(stmts: Vec<Operation>, done: &Symbol) -> Operation {
+    let not_done = Operation::new(OperationType::Eq {
+        left: Box::new(Operation::new_symbol(done.clone())),
+        right: Box::new(constant(0)),
+    });
+    Operation::new(OperationType::If {
+        cond: Box::new(not_done),
+        stmts,
+    })
+}
+
+/// `stmts` with every `return` turned into assignments of `result` and
+/// `done`.
+fn lower_returns(stmts: &[Operation], result: Option<&Symbol>, done: &Symbol) -> Vec<Operation> {
+    let mut operations = vec![];
+    let mut i = 0;
+    while i < stmts.len() {
+        if let OperationType::Return { ret } = &stmts[i].operation {
+            operations.push(match result {
+                Some(result) => assign(result, ret.as_ref().clone()).at(stmts[i].location),
+                None => ret.as_ref().clone(),
+            });
+            operations.push(assign(done, constant(1)));
+            return operations;
+        }
+        // An `if` and its `else` stay next to each other.
+        let mut end = i + 1;
+        if let (OperationType::If { .. }, Some(OperationType::Else { .. })) = (
+            &stmts[i].operation,
+            stmts.get(end).map(|stmt| &stmt.operation),
+        ) {
+            end += 1;
+        }
+        let returns = stmts[i..end].iter().any(has_return);
+        for stmt in &stmts[i..end] {
+            operations.push(match &stmt.operation {
+                OperationType::If { cond, stmts } => Operation::new(OperationType::If {
+                    cond: cond.clone(),
+                    stmts: lower_returns(stmts, result, done),
+                })
+                .at(stmt.location),
+                OperationType::Else { cond, stmts } => Operation::new(OperationType::Else {
+                    cond: cond.clone(),
+                    stmts: lower_returns(stmts, result, done),
+                })
+                .at(stmt.location),
+                // Later iterations are skipped too.
+                OperationType::For {
+                    iter,
+                    vector,
+                    stmts,
+                } if has_return(stmt) => Operation::new(OperationType::For {
+                    iter: iter.clone(),
+                    vector: vector.clone(),
+                    stmts: vec![unless_done(lower_returns(stmts, result, done), done)],
+                })
+                .at(stmt.location),
+                _ => stmt.clone(),
+            });
+        }
+        if returns && end < stmts.len() {
+            let rest = lower_returns(&stmts[end..], result, done);
+            operations.push(unless_done(rest, done));
+            return operations;
+        }
+        i = end;
+    }
+    operations
+}
+
+struct Inliner<'a> {
+    functions: &'a [Function],
+    // Functions whose calls are already inlined.
+    inlined: HashMap<String, Function>,
+    // Functions being inlined, outermost first.
+    stack: Vec<String>,
+}
+
+impl<'a> Inliner<'a> {
+    // Errors are reported at `location`, that of the call.
+    fn function(&mut self, name: &str, location: Location) -> RewriterResult<Function> {
+        if let Some(function) = self.inlined.get(name) {
+            return Ok(function.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|caller| caller == name) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(name.to_string());
+            return type_error(&format!("recursive call: {}", cycle.join(" -> ")), location);
+        }
+        let mut function = match self.functions.iter().find(|f| f.name == name) {
+            Some(function) => function.clone(),
+            None => return type_error(&format!("unknown function `{}`", name), location),
+        };
+        self.stack.push(name.to_string());
+        let operations = std::mem::take(&mut function.operations);
+        function.operations = self.block(&operations, &mut function)?;
+        self.stack.pop();
+        self.inlined.insert(name.to_string(), function.clone());
+        Ok(function)
+    }
+
+    fn block(
+        &mut self,
+        stmts: &[Operation],
+        caller: &mut Function,
+    ) -> RewriterResult<Vec<Operation>> {
+        let mut operations = vec![];
+        // Condition of the last `if`, which its `else` reuses.
+        let mut last_condition = None;
+        for stmt in stmts {
+            let operation = match &stmt.operation {
+                OperationType::If { cond, stmts } => {
+                    let cond = self.expression(cond, caller, &mut operations)?;
+                    last_condition = Some(cond.clone());
+                    OperationType::If {
+                        cond: Box::new(cond),
+                        stmts: self.block(stmts, caller)?,
+                    }
+                }
+                OperationType::Else { cond, stmts } => {
+                    let cond = match last_condition.take() {
+                        Some(cond) => cond,
+                        None => self.expression(cond, caller, &mut operations)?,
+                    };
+                    OperationType::Else {
+                        cond: Box::new(cond),
+                        stmts: self.block(stmts, caller)?,
+                    }
+                }
+                OperationType::For {
+                    iter,
+                    vector,
+                    stmts,
+                } => OperationType::For {
+                    iter: iter.clone(),
+                    vector: Box::new(self.expression(vector, caller, &mut operations)?),
+                    stmts: self.block(stmts, caller)?,
+                },
+                OperationType::Call { func, args } => {
+                    self.call(func, args, stmt.location, caller, &mut operations)?;
+                    continue;
+                }
+                _ => self.expression(stmt, caller, &mut operations)?.operation,
+            };
+            operations.push(Operation::new(operation).at(stmt.location));
+        }
+        Ok(operations)
+    }
+
+    /// `operation` without calls; the inlined bodies go to `prelude`.
+    fn expression(
+        &mut self,
+        operation: &Operation,
+        caller: &mut Function,
+        prelude: &mut Vec<Operation>,
+    ) -> RewriterResult<Operation> {
+        if let OperationType::Call { func, args } = &operation.operation {
+            return match self.call(func, args, operation.location, caller, prelude)? {
+                Some(result) => Ok(Operation::new_symbol(result).at(operation.location)),
+                None => type_error(
+                    &format!("`{}` does not return a value", func),
+                    operation.location,
+                ),
+            };
+        }
+        let mut operation = operation.clone();
+        for child in operation.children_mut() {
+            *child = self.expression(child, caller, prelude)?;
+        }
+        Ok(operation)
+    }
+
+    /// Appends the body of `func` to `prelude` and returns the variable
+    /// holding its result.
+    fn call(
+        &mut self,
+        func: &str,
+        args: &[Operation],
+        location: Location,
+        caller: &mut Function,
+        prelude: &mut Vec<Operation>,
+    ) -> RewriterResult<Option<Symbol>> {
+        let callee = self.function(func, location)?;
+        if args.len() != callee.params.len() {
+            return type_error(
+                &format!(
+                    "`{}` takes {} arguments, found {}",
+                    func,
+                    callee.params.len(),
+                    args.len()
+                ),
+                location,
+            );
+        }
+        if callee.returns.len() > 1 {
+            return Err(error_at(RewriteErrorType::UnsupportedError, location));
+        }
+        let mut values = vec![];
+        for arg in args {
+            values.push(self.expression(arg, caller, prelude)?);
+        }
+
+        let offsets = (caller.private_num, caller.public_num);
+        caller.private_num += callee.private_num;
+        caller.public_num += callee.public_num;
+        let rename = |symbol: &Symbol| match symbol.data_location {
+            // State variables are shared by every function.
+            SymbolLocation::Storage => symbol.clone(),
+            _ => Symbol {
+                name: format!("{}.{}", func, symbol.name),
+                num: symbol.num
+                    + if symbol.is_private {
+                        offsets.0
+                    } else {
+                        offsets.1
+                    },
+                ..symbol.clone()
+            },
+        };
+        for (param, value) in callee.params.iter().zip(values) {
+            prelude.push(assign(&rename(param), value));
+        }
+
+        let result = callee.returns.first().map(|ret| {
+            let name = format!("{}.return", func);
+            fresh_variable(caller, name, ret.symbol_type.clone())
+        });
+        let done = fresh_variable(caller, format!("{}.done", func), SymbolType::Bool);
+        match &result {
+            // Arrays and structs start out zeroed when declared.
+            Some(result) if result.symbol_type.compound_kind().is_some() => {
+                prelude.push(Operation::new_symbol(result.clone()));
+            }
+            Some(result) => prelude.push(assign(result, constant(0))),
+            None => {}
+        }
+        prelude.push(assign(&done, constant(0)));
+
+        let mut body = callee.operations;
+        for operation in body.iter_mut() {
+            rename_symbols(operation, &rename);
+        }
+        prelude.extend(lower_returns(&body, result.as_ref(), &done));
+        Ok(result)
+    }
+}
@@ -0,0 +1,1048 @@
+use crate::encoding::{decode_bounded, encode, from_words, to_words};
+use crate::error::{self, error_at, RewriteError, RewriteErrorType};
+use crate::lowering::{entry_types, CircuitOptions};
+use crate::rewriter::RewriterResult;
+use crate::symbol::{Function, Operation, OperationType, Requirement, Symbol, SymbolType};
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use num_bigint::{BigInt, BigUint};
+use std::collections::HashMap;
+use std::fmt;
+use zoker_parser::location::Location;
+
+const BITS: usize = 256;
+
+/// Concrete value of a variable, reduced modulo 2^256.
+///
+/// `int` values are kept in two's complement, as in circuits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    pub symbol_type: SymbolType,
+    pub value: BigUint,
+}
+
+impl Value {
+    pub fn new(symbol_type: SymbolType, value: BigUint) -> Self {
+        Value { symbol_type, value }
+    }
+
+    /// Big-endian words of the value, as circuits output it.
+    pub fn words(&self) -> Vec<u32> {
+        to_words(&self.value, self.symbol_type.word_count())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.symbol_type.as_array().is_some() {
+            let elements = elements(self)
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<String>>();
+            return write!(f, "[{}]", elements.join(", "));
+        }
+        if let Some(structure) = self.symbol_type.as_struct() {
+            let fields = structure
+                .fields
+                .iter()
+                .zip(fields(self))
+                .map(|(field, value)| format!("{}: {}", field.name, value))
+                .collect::<Vec<String>>();
+            return write!(f, "{{{}}}", fields.join(", "));
+        }
+        match self.symbol_type {
+            SymbolType::Bool => write!(f, "{}", self.value != BigUint::from(0u32)),
+            SymbolType::Int256 => write!(f, "{}", to_signed(&self.value)),
+            SymbolType::Address | SymbolType::Bytes32 => write!(f, "0x{:x}", self.value),
+            SymbolType::BoundedBytes(max_len) | SymbolType::BoundedString(max_len) => {
+                let bytes = match decode_bounded(&self.words(), max_len) {
+                    Ok(bytes) => bytes,
+                    Err(_) => return write!(f, "{}", self.value),
+                };
+                match self.symbol_type {
+                    SymbolType::BoundedString(_) => {
+                        write!(f, "{:?}", String::from_utf8_lossy(&bytes))
+                    }
+                    _ => {
+                        write!(f, "0x")?;
+                        bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+                    }
+                }
+            }
+            _ => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// What happened while interpreting a function, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Assign {
+        name: String,
+        value: Value,
+    },
+    /// An `if` or `else` branch was entered or skipped.
+    Branch {
+        taken: bool,
+    },
+    Return {
+        value: Value,
+    },
+    /// A mapping entry was written.
+    Store {
+        mapping: String,
+        key: Value,
+        value: Value,
+    },
+    /// A checked operation overflowed.
+    Overflow,
+    /// A `require` or `assert` did not hold.
+    Fail {
+        requirement: Requirement,
+    },
+    Emit {
+        event: String,
+        args: Vec<Value>,
+    },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Assign { name, value } => write!(f, "{} = {}", name, value),
+            Step::Branch { taken: true } => write!(f, "branch taken"),
+            Step::Branch { taken: false } => write!(f, "branch skipped"),
+            Step::Return { value } => write!(f, "return {}", value),
+            Step::Store {
+                mapping,
+                key,
+                value,
+            } => write!(f, "{}[{}] = {}", mapping, key, value),
+            Step::Overflow => write!(f, "overflow"),
+            Step::Fail { requirement } => write!(f, "{} failed", requirement),
+            Step::Emit { event, args } => {
+                let args = args.iter().map(Value::to_string).collect::<Vec<String>>();
+                write!(f, "emit {}({})", event, args.join(", "))
+            }
+        }
+    }
+}
+
+/// Contract state a function runs on. Missing values are 0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Storage {
+    /// Values of the state variables other than mappings, by name.
+    pub variables: HashMap<String, BigUint>,
+    /// Entries of each mapping by mapping name and key.
+    pub mappings: HashMap<String, HashMap<BigUint, BigUint>>,
+    /// Salts of the commitments of private state variables before and
+    /// after the call, by name.
+    pub salts: HashMap<String, (BigUint, BigUint)>,
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// A state variable used by a function, once it has run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateEntry {
+    pub name: String,
+    /// Value in storage before the function ran.
+    pub old: Value,
+    /// Value to store back.
+    pub value: Value,
+    pub is_write: bool,
+    /// Salt of the stored commitment, for private state.
+    pub salt: Option<Value>,
+    /// Salt of the commitment to store back, for private state written.
+    pub new_salt: Option<Value>,
+}
+
+impl StateEntry {
+    /// Commitments of private state before and, if written, after the call.
+    pub fn commitments(&self) -> Vec<Value> {
+        let mut commitments = vec![];
+        if let Some(salt) = &self.salt {
+            commitments.push(commitment(&self.old, salt));
+        }
+        if let Some(new_salt) = &self.new_salt {
+            commitments.push(commitment(&self.value, new_salt));
+        }
+        commitments
+    }
+}
+
+/// Commitment to `value` with `salt`, as private state is stored.
+pub fn commitment(value: &Value, salt: &Value) -> Value {
+    digest(&[value, salt])
+}
+
+// SHA-256 digest of the words of `values`.
+fn digest(values: &[&Value]) -> Value {
+    let mut hasher = Sha256::new();
+    for value in values {
+        for word in value.words() {
+            hasher.input(&word.to_be_bytes());
+        }
+    }
+    let mut digest = [0; 32];
+    hasher.result(&mut digest);
+    Value::new(SymbolType::Bytes32, BigUint::from_bytes_be(&digest))
+}
+
+/// One storage access of a function, once it has run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageEntry {
+    /// Whether the access ran; the values are 0 if not.
+    pub active: bool,
+    pub key: Value,
+    /// Value of the entry in storage before the function ran.
+    pub old: Value,
+    /// Value of the entry after the access.
+    pub value: Value,
+    pub is_write: bool,
+}
+
+/// One `emit` of a function, once it has run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventEntry {
+    pub name: String,
+    /// Whether the `emit` ran; the arguments are 0 if not.
+    pub active: bool,
+    pub args: Vec<Value>,
+}
+
+/// Result of running a function on concrete inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    pub outputs: Vec<Value>,
+    /// Whether the return value is private, so that it is not output.
+    pub is_private_return: bool,
+    /// One per state variable the function uses, in order.
+    pub state: Vec<StateEntry>,
+    /// One per storage access of the function, in order.
+    pub storage: Vec<StorageEntry>,
+    /// One per `emit` of the function, in order.
+    pub events: Vec<EventEntry>,
+    /// Whether every `require` and `assert` that ran held, for functions
+    /// with a `valid` word. The inputs are canonical already.
+    pub valid: Option<bool>,
+    /// The first `require` or `assert` that did not hold.
+    pub failed: Option<Requirement>,
+    /// Whether no operation overflowed, with checked arithmetic.
+    pub ok: Option<bool>,
+    pub trace: Vec<Step>,
+}
+
+impl Execution {
+    /// The words the circuit of the function outputs for the same inputs.
+    pub fn output_words(&self) -> Vec<u32> {
+        let mut words = self
+            .outputs
+            .iter()
+            .flat_map(|value| {
+                value
+                    .symbol_type
+                    .public_words(self.is_private_return, &value.words())
+            })
+            .collect::<Vec<u32>>();
+        for entry in &self.state {
+            if entry.salt.is_some() {
+                for commitment in entry.commitments() {
+                    words.extend(commitment.words());
+                }
+            } else if entry.is_write {
+                words.extend(entry.value.words());
+            }
+        }
+        for entry in &self.storage {
+            words.push(entry.active as u32);
+            words.extend(entry.key.words());
+            if entry.is_write {
+                words.extend(entry.value.words());
+            }
+        }
+        for entry in &self.events {
+            words.push(entry.active as u32);
+            for arg in &entry.args {
+                words.extend(arg.words());
+            }
+        }
+        if let Some(valid) = self.valid {
+            words.push(valid as u32);
+        }
+        if let Some(ok) = self.ok {
+            words.push(ok as u32);
+        }
+        words
+    }
+}
+
+/// Runs `function` on `args`, one per parameter in declaration order.
+///
+/// Values follow the same semantics as the circuit `lower_function` builds,
+/// division by zero included, so the result is the output a proof of the
+/// function has to show. Branches are really taken or skipped. A `require`
+/// or `assert` that fails does not stop the run, which the proof shows in
+/// its validity word.
+pub fn interpret(
+    function: &Function,
+    options: CircuitOptions,
+    args: &[BigUint],
+) -> RewriterResult<Execution> {
+    execute(function, options, args, &Storage::new())
+}
+
+/// Same as `interpret`, with state variables and mapping entries read
+/// from `storage`.
+pub fn execute(
+    function: &Function,
+    options: CircuitOptions,
+    args: &[BigUint],
+    storage: &Storage,
+) -> RewriterResult<Execution> {
+    let mut interpreter = Interpreter::new(options, storage);
+    for access in &function.storage {
+        let (key_type, value_type) = entry_types(access);
+        interpreter.storage.push(StorageEntry {
+            active: false,
+            key: Value::new(key_type.clone(), BigUint::from(0u32)),
+            old: Value::new(value_type.clone(), BigUint::from(0u32)),
+            value: Value::new(value_type.clone(), BigUint::from(0u32)),
+            is_write: access.is_write,
+        });
+    }
+    for event in &function.emits {
+        interpreter.events.push(EventEntry {
+            name: event.name.clone(),
+            active: false,
+            args: event
+                .params
+                .iter()
+                .map(|param| Value::new(param.symbol_type.clone(), BigUint::from(0u32)))
+                .collect(),
+        });
+    }
+    if args.len() != function.params.len() {
+        return type_error("wrong number of arguments");
+    }
+    for (param, arg) in function.params.iter().zip(args) {
+        if param.symbol_type.word_count() == 0 {
+            return type_error("parameter type has no circuit encoding");
+        }
+        if encode(&param.symbol_type, arg).is_err() {
+            return type_error("argument does not fit its type");
+        }
+        interpreter.variables.insert(
+            (param.is_private, param.num),
+            Value::new(param.symbol_type.clone(), arg.clone()),
+        );
+    }
+    let mut state = vec![];
+    for variable in &function.state {
+        let name = &variable.variable.name;
+        let zero = BigUint::from(0u32);
+        let old = storage.variables.get(name).unwrap_or(&zero);
+        if encode(&variable.variable.symbol_type, old).is_err() {
+            return type_error("storage value does not fit its type");
+        }
+        let old = Value::new(variable.variable.symbol_type.clone(), old.clone());
+        let key = (variable.local.is_private, variable.local.num);
+        interpreter.variables.insert(key, old.clone());
+        let mut entry = StateEntry {
+            name: name.clone(),
+            value: old.clone(),
+            old,
+            is_write: variable.is_write,
+            salt: None,
+            new_salt: None,
+        };
+        if variable.variable.is_private {
+            let (salt, new_salt) = storage.salts.get(name).cloned().unwrap_or_default();
+            if salt.bits() > 256 || new_salt.bits() > 256 {
+                return type_error("salt does not fit in 256 bits");
+            }
+            entry.salt = Some(Value::new(SymbolType::Bytes32, salt));
+            if variable.is_write {
+                entry.new_salt = Some(Value::new(SymbolType::Bytes32, new_salt));
+            }
+        }
+        state.push(entry);
+    }
+    interpreter.run(&function.operations)?;
+    for (entry, variable) in state.iter_mut().zip(&function.state) {
+        entry.value =
+            interpreter.variables[&(variable.local.is_private, variable.local.num)].clone();
+    }
+
+    let outputs = if function.returns.is_empty() {
+        vec![]
+    } else if function.returns.len() > 1 {
+        return Err(interpreter_error(RewriteErrorType::UnsupportedError));
+    } else {
+        match &interpreter.ret {
+            Some(value) => {
+                check_convertible(&value.symbol_type, &function.returns[0].symbol_type)?;
+                vec![convert(value, &function.returns[0].symbol_type)]
+            }
+            None => return type_error("function does not return a value"),
+        }
+    };
+    Ok(Execution {
+        outputs,
+        is_private_return: function.returns.iter().any(|ret| ret.is_private),
+        state,
+        storage: interpreter.storage,
+        events: interpreter.events,
+        valid: if function.has_valid_word() {
+            Some(interpreter.failed.is_none())
+        } else {
+            None
+        },
+        failed: interpreter.failed,
+        ok: if options.checked_arithmetic {
+            Some(!interpreter.overflow)
+        } else {
+            None
+        },
+        trace: interpreter.trace,
+    })
+}
+
+// Errors are put at the innermost operation they come from by `located`.
+fn interpreter_error(error: RewriteErrorType) -> RewriteError {
+    error_at(error, Location::default())
+}
+
+fn type_error<V>(message: &str) -> RewriterResult<V> {
+    error::type_error(message, Location::default())
+}
+
+// `error` at `operation` unless an operation inside it placed it already.
+fn located(mut error: RewriteError, operation: &Operation) -> RewriteError {
+    if error.location == Location::default() {
+        error.location = operation.location;
+    }
+    error
+}
+
+fn pow2(n: usize) -> BigUint {
+    BigUint::from(1u32) << n
+}
+
+fn truncate(value: &BigUint, bits: usize) -> BigUint {
+    value % pow2(bits)
+}
+
+fn to_signed(value: &BigUint) -> BigInt {
+    if value.bits() == BITS {
+        BigInt::from(value.clone()) - BigInt::from(pow2(BITS))
+    } else {
+        BigInt::from(value.clone())
+    }
+}
+
+fn from_signed(value: &BigInt) -> BigUint {
+    let modulus = BigInt::from(pow2(BITS));
+    let value = ((value % &modulus) + &modulus) % &modulus;
+    value.to_biguint().unwrap()
+}
+
+fn fits_signed(value: &BigInt) -> bool {
+    let half = BigInt::from(pow2(BITS - 1));
+    *value >= -half.clone() && *value < half
+}
+
+// Arrays and structs only convert to the same type.
+fn check_convertible(from: &SymbolType, to: &SymbolType) -> RewriterResult<()> {
+    if (from.compound_kind().is_some() || to.compound_kind().is_some()) && from != to {
+        return type_error(&format!("cannot convert {} to {}", from, to));
+    }
+    Ok(())
+}
+
+fn elements(array: &Value) -> Vec<Value> {
+    let (element, size) = match array.symbol_type.as_array() {
+        Some(array) => array,
+        None => return vec![],
+    };
+    let count = element.word_count();
+    let words = array.words();
+    (0..size)
+        .map(|i| {
+            Value::new(
+                element.clone(),
+                from_words(&words[i * count..(i + 1) * count]),
+            )
+        })
+        .collect()
+}
+
+fn fields(structure: &Value) -> Vec<Value> {
+    let fields = match structure.symbol_type.as_struct() {
+        Some(structure) => &structure.fields,
+        None => return vec![],
+    };
+    let words = structure.words();
+    let mut offset = 0;
+    fields
+        .iter()
+        .map(|field| {
+            let count = field.symbol_type.word_count();
+            offset += count;
+            Value::new(
+                field.symbol_type.clone(),
+                from_words(&words[offset - count..offset]),
+            )
+        })
+        .collect()
+}
+
+// Position of field `name` of `structure`.
+fn field(structure: &Value, name: &str) -> RewriterResult<usize> {
+    match structure.symbol_type.as_struct() {
+        Some(typ) => match typ.field(name) {
+            Some((i, _)) => Ok(i),
+            None => type_error(&format!(
+                "{} has no field `{}`",
+                structure.symbol_type, name
+            )),
+        },
+        None => type_error(&format!("{} is not a struct", structure.symbol_type)),
+    }
+}
+
+// Also builds structs from their fields.
+fn array(symbol_type: SymbolType, elements: &[Value]) -> Value {
+    let words = elements.iter().flat_map(Value::words).collect::<Vec<u32>>();
+    Value::new(symbol_type, from_words(&words))
+}
+
+fn condition(value: &Value) -> RewriterResult<bool> {
+    if let Some(kind) = value.symbol_type.compound_kind() {
+        return type_error(&format!("{} is not a condition", kind));
+    }
+    Ok(value.value != BigUint::from(0u32))
+}
+
+fn convert(value: &Value, symbol_type: &SymbolType) -> Value {
+    let number = match (&value.symbol_type, symbol_type) {
+        (SymbolType::Bool, _) => value.value.clone(),
+        (_, SymbolType::Bool) => truncate(&value.value, 1),
+        _ => value.value.clone(),
+    };
+    Value::new(symbol_type.clone(), number)
+}
+
+// `value` as stored in an array element, a field or a mapping entry of
+// type `symbol_type`, which only keep the words they are encoded with.
+fn stored(value: &Value, symbol_type: &SymbolType) -> RewriterResult<Value> {
+    check_convertible(&value.symbol_type, symbol_type)?;
+    let value = convert(value, symbol_type);
+    Ok(Value::new(symbol_type.clone(), from_words(&value.words())))
+}
+
+fn is_signed(left: &Value, right: &Value) -> bool {
+    left.symbol_type == SymbolType::Int256 || right.symbol_type == SymbolType::Int256
+}
+
+// Mixing `int` into an operation makes it signed, as for literals.
+fn result_type(left: &Value, right: &Value) -> SymbolType {
+    if is_signed(left, right) {
+        return SymbolType::Int256;
+    }
+    match left.symbol_type {
+        SymbolType::Bool => SymbolType::Uint256,
+        ref typ => typ.clone(),
+    }
+}
+
+fn boolean(bit: bool) -> Value {
+    Value::new(SymbolType::Bool, BigUint::from(bit as u32))
+}
+
+enum Flow {
+    Next,
+    Return,
+}
+
+struct Interpreter<'a> {
+    options: CircuitOptions,
+    variables: HashMap<(bool, u32), Value>,
+    initial: &'a Storage,
+    // Entries written so far, by mapping name and key.
+    written: HashMap<(String, BigUint), BigUint>,
+    storage: Vec<StorageEntry>,
+    events: Vec<EventEntry>,
+    ret: Option<Value>,
+    // Condition of the `if` just run, for its `else`.
+    last_condition: Option<bool>,
+    overflow: bool,
+    failed: Option<Requirement>,
+    trace: Vec<Step>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(options: CircuitOptions, initial: &'a Storage) -> Self {
+        Interpreter {
+            options,
+            variables: HashMap::new(),
+            initial,
+            written: HashMap::new(),
+            storage: vec![],
+            events: vec![],
+            ret: None,
+            last_condition: None,
+            overflow: false,
+            failed: None,
+            trace: vec![],
+        }
+    }
+
+    fn run(&mut self, operations: &[Operation]) -> RewriterResult<Flow> {
+        for operation in operations {
+            if let Flow::Return = self.run_operation(operation)? {
+                return Ok(Flow::Return);
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn run_operation(&mut self, operation: &Operation) -> RewriterResult<Flow> {
+        self.step(operation)
+            .map_err(|error| located(error, operation))
+    }
+
+    fn step(&mut self, operation: &Operation) -> RewriterResult<Flow> {
+        match &operation.operation {
+            OperationType::Symbol { symbol } => {
+                let zero = Value::new(symbol.symbol_type.clone(), BigUint::from(0u32));
+                self.variables
+                    .entry((symbol.is_private, symbol.num))
+                    .or_insert(zero);
+            }
+            OperationType::Return { ret } => {
+                let value = self.evaluate(ret)?;
+                self.trace.push(Step::Return {
+                    value: value.clone(),
+                });
+                self.ret = Some(value);
+                return Ok(Flow::Return);
+            }
+            OperationType::Require { cond, requirement } => {
+                if !condition(&self.evaluate(cond)?)? {
+                    self.trace.push(Step::Fail {
+                        requirement: *requirement,
+                    });
+                    self.failed = self.failed.or(Some(*requirement));
+                }
+            }
+            OperationType::Emit { event, args, emit } => {
+                let mut values = vec![];
+                for (param, arg) in event.params.iter().zip(args) {
+                    values.push(stored(&self.evaluate(arg)?, &param.symbol_type)?);
+                }
+                self.trace.push(Step::Emit {
+                    event: event.name.clone(),
+                    args: values.clone(),
+                });
+                self.events[*emit].active = true;
+                self.events[*emit].args = values;
+            }
+            OperationType::If { cond, stmts } => {
+                let cond = condition(&self.evaluate(cond)?)?;
+                let flow = self.branch(cond, stmts)?;
+                self.last_condition = Some(cond);
+                return Ok(flow);
+            }
+            OperationType::Else { cond, stmts } => {
+                let cond = match self.last_condition.take() {
+                    Some(cond) => cond,
+                    None => condition(&self.evaluate(cond)?)?,
+                };
+                return self.branch(!cond, stmts);
+            }
+            OperationType::For {
+                iter,
+                vector,
+                stmts,
+            } => {
+                let vector = self.evaluate(vector)?;
+                if vector.symbol_type.as_array().is_none() {
+                    return type_error(&format!("cannot iterate over {}", vector.symbol_type));
+                }
+                for element in elements(&vector) {
+                    self.assign(iter, element)?;
+                    if let Flow::Return = self.run(stmts)? {
+                        return Ok(Flow::Return);
+                    }
+                }
+            }
+            OperationType::Nop => {}
+            _ => {
+                self.evaluate(operation)?;
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn branch(&mut self, taken: bool, stmts: &[Operation]) -> RewriterResult<Flow> {
+        self.trace.push(Step::Branch { taken });
+        if taken {
+            self.run(stmts)
+        } else {
+            Ok(Flow::Next)
+        }
+    }
+
+    // An index out of range picks no element and counts as an overflow,
+    // as a private index does in circuits.
+    fn selected(&mut self, index: &Value, size: usize) -> RewriterResult<Option<usize>> {
+        if let Some(kind) = index.symbol_type.compound_kind() {
+            return type_error(&format!("{} is not an index", kind));
+        }
+        if index.value < BigUint::from(size) {
+            Ok(Some(
+                index.value.to_u32_digits().first().cloned().unwrap_or(0) as usize,
+            ))
+        } else {
+            self.check_overflow(true);
+            Ok(None)
+        }
+    }
+
+    fn assign(&mut self, left: &Operation, value: Value) -> RewriterResult<Value> {
+        let symbol = match left.as_symbol() {
+            Some(symbol) => symbol,
+            None => return type_error("left side of an assignment must be a variable"),
+        };
+        check_convertible(&value.symbol_type, &symbol.symbol_type)?;
+        let value = convert(&value, &symbol.symbol_type);
+        self.variables
+            .insert((symbol.is_private, symbol.num), value.clone());
+        self.trace.push(Step::Assign {
+            name: symbol.name,
+            value: value.clone(),
+        });
+        Ok(value)
+    }
+
+    // Stores `value` in the variable, array element or field `target`
+    // names.
+    fn store(&mut self, target: &Operation, value: Value) -> RewriterResult<Value> {
+        let (array_operation, index) = match &target.operation {
+            OperationType::Index { array, index } => (array, index),
+            OperationType::Member {
+                value: old,
+                field: name,
+            } => {
+                let old_value = self.evaluate(old)?;
+                let i = field(&old_value, name)?;
+                let mut fields = fields(&old_value);
+                let value = stored(&value, &fields[i].symbol_type)?;
+                fields[i] = value.clone();
+                self.store(old, array(old_value.symbol_type, &fields))?;
+                return Ok(value);
+            }
+            _ => return self.assign(target, value),
+        };
+        let old = self.evaluate(array_operation)?;
+        let index = self.evaluate(index)?;
+        let element = match old.symbol_type.as_array() {
+            Some((element, _)) => element.clone(),
+            None => return type_error(&format!("{} is not an array", old.symbol_type)),
+        };
+        let value = stored(&value, &element)?;
+        let mut elements = elements(&old);
+        if let Some(i) = self.selected(&index, elements.len())? {
+            elements[i] = value.clone();
+        }
+        self.store(array_operation, array(old.symbol_type, &elements))?;
+        Ok(value)
+    }
+
+    // Reads, or writes `value` to, the entry `key` of `mapping`. Evaluating
+    // an access again gives the value it had.
+    fn access(
+        &mut self,
+        mapping: &Symbol,
+        key: &Operation,
+        value: Option<&Operation>,
+        access: usize,
+    ) -> RewriterResult<Value> {
+        if self.storage[access].active {
+            return Ok(self.storage[access].value.clone());
+        }
+        let (key_type, value_type) = match mapping.symbol_type.as_mapping() {
+            Some((key, value)) => (key.clone(), value.clone()),
+            None => return type_error(&format!("`{}` is not a mapping", mapping.name)),
+        };
+        let value = match value {
+            Some(value) => Some(self.evaluate(value)?),
+            None => None,
+        };
+        let key = stored(&self.evaluate(key)?, &key_type)?;
+        let old = self
+            .initial
+            .mappings
+            .get(&mapping.name)
+            .and_then(|entries| entries.get(&key.value))
+            .cloned()
+            .unwrap_or_else(|| BigUint::from(0u32));
+        let old = Value::new(value_type.clone(), old);
+        if encode(&value_type, &old.value).is_err() {
+            return type_error("storage value does not fit its type");
+        }
+        let entry = (mapping.name.clone(), key.value.clone());
+        let value = match value {
+            Some(value) => {
+                let value = stored(&value, &value_type)?;
+                self.written.insert(entry, value.value.clone());
+                self.trace.push(Step::Store {
+                    mapping: mapping.name.clone(),
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+                value
+            }
+            None => match self.written.get(&entry) {
+                Some(value) => Value::new(value_type, value.clone()),
+                None => old.clone(),
+            },
+        };
+        self.storage[access] = StorageEntry {
+            active: true,
+            key,
+            old,
+            value: value.clone(),
+            is_write: self.storage[access].is_write,
+        };
+        Ok(value)
+    }
+
+    fn check_overflow(&mut self, overflow: bool) {
+        if self.options.checked_arithmetic && overflow {
+            self.overflow = true;
+            self.trace.push(Step::Overflow);
+        }
+    }
+
+    fn evaluate(&mut self, operation: &Operation) -> RewriterResult<Value> {
+        self.value(operation)
+            .map_err(|error| located(error, operation))
+    }
+
+    fn value(&mut self, operation: &Operation) -> RewriterResult<Value> {
+        let modulus = pow2(BITS);
+        match &operation.operation {
+            OperationType::Add { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let sum = &left.value + &right.value;
+                let overflow = if is_signed(&left, &right) {
+                    !fits_signed(&(to_signed(&left.value) + to_signed(&right.value)))
+                } else {
+                    sum >= modulus
+                };
+                self.check_overflow(overflow);
+                Ok(Value::new(result_type(&left, &right), sum % modulus))
+            }
+            OperationType::Sub { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let overflow = if is_signed(&left, &right) {
+                    !fits_signed(&(to_signed(&left.value) - to_signed(&right.value)))
+                } else {
+                    left.value < right.value
+                };
+                self.check_overflow(overflow);
+                let diff = (&left.value + &modulus - &right.value) % modulus;
+                Ok(Value::new(result_type(&left, &right), diff))
+            }
+            OperationType::Mul { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let product = &left.value * &right.value;
+                let overflow = if is_signed(&left, &right) {
+                    !fits_signed(&(to_signed(&left.value) * to_signed(&right.value)))
+                } else {
+                    product >= modulus
+                };
+                self.check_overflow(overflow);
+                Ok(Value::new(result_type(&left, &right), product % modulus))
+            }
+            OperationType::Div { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let min = is_signed(&left, &right)
+                    && left.value == pow2(BITS - 1)
+                    && right.value == &modulus - 1u32;
+                self.check_overflow(right.value == BigUint::from(0u32) || min);
+                let quotient = self.divrem(&left, &right).0;
+                Ok(Value::new(result_type(&left, &right), quotient))
+            }
+            OperationType::Mod { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                self.check_overflow(right.value == BigUint::from(0u32));
+                let rem = self.divrem(&left, &right).1;
+                Ok(Value::new(result_type(&left, &right), rem))
+            }
+            OperationType::Neg { operand } => {
+                let operand = self.evaluate(operand)?;
+                if let Some(kind) = operand.symbol_type.compound_kind() {
+                    return type_error(&format!("arithmetic on {}", kind));
+                }
+                let value = if operand.symbol_type == SymbolType::Int256 {
+                    to_signed(&operand.value)
+                } else {
+                    BigInt::from(operand.value.clone())
+                };
+                self.check_overflow(!fits_signed(&-value));
+                let negated = (&modulus - &operand.value) % modulus;
+                Ok(Value::new(SymbolType::Int256, negated))
+            }
+            OperationType::Lt { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(self.less_than(&left, &right)))
+            }
+            OperationType::Gt { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(self.less_than(&right, &left)))
+            }
+            OperationType::Le { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(!self.less_than(&right, &left)))
+            }
+            OperationType::Ge { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(!self.less_than(&left, &right)))
+            }
+            OperationType::Eq { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(left.value == right.value))
+            }
+            OperationType::NotEq { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                Ok(boolean(left.value != right.value))
+            }
+            OperationType::LShift { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let n = shift_amount(&right);
+                let shifted = truncate(&(&left.value << n), BITS);
+                Ok(Value::new(result_type(&left, &left), shifted))
+            }
+            OperationType::RShift { left, right } => {
+                let (left, right) = self.operands(left, right)?;
+                let n = shift_amount(&right);
+                let mut shifted = &left.value >> n;
+                if left.symbol_type == SymbolType::Int256 && left.value.bits() == BITS {
+                    // Arithmetic shift: the vacated top bits copy the sign.
+                    shifted |= &modulus - pow2(BITS - n);
+                }
+                Ok(Value::new(result_type(&left, &left), shifted))
+            }
+            OperationType::Assign { left, right } => {
+                let value = self.evaluate(right)?;
+                self.store(left, value)
+            }
+            OperationType::Index { array, index } => {
+                let array = self.evaluate(array)?;
+                let index = self.evaluate(index)?;
+                let element = match array.symbol_type.as_array() {
+                    Some((element, _)) => element.clone(),
+                    None => return type_error(&format!("{} is not an array", array.symbol_type)),
+                };
+                let elements = elements(&array);
+                match self.selected(&index, elements.len())? {
+                    Some(i) => Ok(elements[i].clone()),
+                    None => Ok(Value::new(element, BigUint::from(0u32))),
+                }
+            }
+            OperationType::Member { value, field: name } => {
+                let value = self.evaluate(value)?;
+                let i = field(&value, name)?;
+                Ok(fields(&value).swap_remove(i))
+            }
+            OperationType::Load {
+                mapping,
+                key,
+                access,
+            } => self.access(mapping, key, None, *access),
+            OperationType::Store {
+                mapping,
+                key,
+                value,
+                access,
+            } => self.access(mapping, key, Some(value), *access),
+            OperationType::Sha256 { args } => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.evaluate(arg)?);
+                }
+                Ok(digest(&values.iter().collect::<Vec<_>>()))
+            }
+            OperationType::Reveal { value } => self.evaluate(value),
+            OperationType::Symbol { symbol } => {
+                match self.variables.get(&(symbol.is_private, symbol.num)) {
+                    Some(value) => Ok(value.clone()),
+                    None => type_error("use of an undeclared variable"),
+                }
+            }
+            OperationType::Constant { value } => {
+                if value.bits() > BITS {
+                    return type_error("constant does not fit in 256 bits");
+                }
+                Ok(Value::new(SymbolType::Uint256, value.clone()))
+            }
+            _ => Err(interpreter_error(RewriteErrorType::UnsupportedError)),
+        }
+    }
+
+    fn operands(&mut self, left: &Operation, right: &Operation) -> RewriterResult<(Value, Value)> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        for value in [&left, &right] {
+            if let Some(kind) = value.symbol_type.compound_kind() {
+                return type_error(&format!("arithmetic on {}", kind));
+            }
+        }
+        Ok((left, right))
+    }
+
+    fn less_than(&self, left: &Value, right: &Value) -> bool {
+        if is_signed(left, right) {
+            to_signed(&left.value) < to_signed(&right.value)
+        } else {
+            left.value < right.value
+        }
+    }
+
+    // Division by zero gives an all-ones quotient and the dividend as the
+    // remainder, as the circuit does.
+    fn divrem(&self, left: &Value, right: &Value) -> (BigUint, BigUint) {
+        let zero = BigUint::from(0u32);
+        let signed = is_signed(left, right);
+        if right.value == zero {
+            let negative = signed && left.value.bits() == BITS;
+            let quotient = if negative {
+                BigUint::from(1u32)
+            } else {
+                pow2(BITS) - 1u32
+            };
+            return (quotient, left.value.clone());
+        }
+        if signed {
+            let (a, b) = (to_signed(&left.value), to_signed(&right.value));
+            (from_signed(&(&a / &b)), from_signed(&(&a % &b)))
+        } else {
+            (&left.value / &right.value, &left.value % &right.value)
+        }
+    }
+}
+
+fn shift_amount(value: &Value) -> usize {
+    if value.value >= BigUint::from(BITS) {
+        BITS
+    } else {
+        value.value.to_bytes_le()[0] as usize
+    }
+}
@@ -0,0 +1,349 @@
+use crate::lowering::{entry_types, input_len, output_len, CircuitOptions};
+use crate::symbol::{Function, Part, Symbol, SymbolType};
+use serde_json::{json, Value};
+
+/// Where a value sits among the words of a circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutEntry {
+    pub name: String,
+    pub symbol_type: SymbolType,
+    pub is_private: bool,
+    /// First word of the value in `input`, `input_pub` or the output.
+    pub offset: usize,
+    pub words: usize,
+}
+
+impl LayoutEntry {
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "type": abi_type(&self.symbol_type),
+            "private": self.is_private,
+            "offset": self.offset,
+            "words": self.words,
+        })
+    }
+}
+
+/// Where the words of one state variable used by a function sit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateLayout {
+    pub variable: String,
+    /// Value before the call, in `input_pub`, or in `input` if private.
+    pub old: LayoutEntry,
+    /// Value after the call, in the output, if the function writes it and
+    /// it is public.
+    pub new: Option<LayoutEntry>,
+    pub commitment: Option<CommitmentLayout>,
+}
+
+impl StateLayout {
+    fn to_json(&self) -> Value {
+        let mut json = json!({
+            "variable": self.variable,
+            "old": self.old.to_json(),
+        });
+        if let Some(new) = &self.new {
+            json["new"] = new.to_json();
+        }
+        if let Some(commitment) = &self.commitment {
+            json["commitment"] = commitment.to_json();
+        }
+        json
+    }
+}
+
+/// Where the words of the commitments of a private state variable sit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitmentLayout {
+    /// Salt of the stored commitment, in `input`.
+    pub salt: LayoutEntry,
+    /// Stored commitment, in the output.
+    pub old: LayoutEntry,
+    /// Salt of the commitment to store back, in `input`, if written.
+    pub new_salt: Option<LayoutEntry>,
+    /// Commitment to store back, in the output, if written.
+    pub new: Option<LayoutEntry>,
+}
+
+impl CommitmentLayout {
+    fn to_json(&self) -> Value {
+        let mut json = json!({
+            "salt": self.salt.to_json(),
+            "old": self.old.to_json(),
+        });
+        if let (Some(new_salt), Some(new)) = (&self.new_salt, &self.new) {
+            json["new_salt"] = new_salt.to_json();
+            json["new"] = new.to_json();
+        }
+        json
+    }
+}
+
+/// Where the words of one storage access of a function sit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageLayout {
+    pub mapping: String,
+    /// Value of the entry before the call, in `input_pub`.
+    pub old: LayoutEntry,
+    /// Output word set to 1 if the access ran.
+    pub active: usize,
+    pub key: LayoutEntry,
+    /// Value written, in the output.
+    pub new: Option<LayoutEntry>,
+}
+
+impl StorageLayout {
+    fn to_json(&self) -> Value {
+        let mut json = json!({
+            "mapping": self.mapping,
+            "old": self.old.to_json(),
+            "active": self.active,
+            "key": self.key.to_json(),
+        });
+        if let Some(new) = &self.new {
+            json["new"] = new.to_json();
+        }
+        json
+    }
+}
+
+/// Where the words of one `emit` of a function sit in the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventLayout {
+    pub event: String,
+    /// Output word set to 1 if the `emit` ran.
+    pub active: usize,
+    pub args: Vec<LayoutEntry>,
+}
+
+impl EventLayout {
+    fn to_json(&self) -> Value {
+        json!({
+            "event": self.event,
+            "active": self.active,
+            "args": self.args.iter().map(LayoutEntry::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Word layout of the inputs and outputs of a function.
+///
+/// Private parameters fill `input` and public ones `input_pub`, each class
+/// in declaration order and every value as big-endian words. Return values
+/// follow each other in the output, which is public. State variables and
+/// then storage accesses come after the public parameters and after the
+/// return values. Private state variables come after the private
+/// parameters instead, with the salts of their commitments, and output
+/// those commitments. Events emitted come last in the output.
+///
+/// Values holding structs have an entry per part instead: the fields of a
+/// parameter go to `input` or `input_pub` by their own privacy, and only
+/// the public fields of a return value are output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layout {
+    pub params: Vec<LayoutEntry>,
+    pub returns: Vec<LayoutEntry>,
+    pub state: Vec<StateLayout>,
+    pub storage: Vec<StorageLayout>,
+    pub events: Vec<EventLayout>,
+}
+
+impl Layout {
+    pub fn of(function: &Function) -> Self {
+        let mut offsets = (0, 0);
+        let params = function
+            .params
+            .iter()
+            .flat_map(|param| param.symbol_type.parts(&param.name, param.is_private))
+            .map(|part| {
+                let offset = if part.is_private {
+                    &mut offsets.0
+                } else {
+                    &mut offsets.1
+                };
+                entry(
+                    &named(&part.name, &part.symbol_type),
+                    part.is_private,
+                    offset,
+                )
+            })
+            .collect();
+        let mut offset = 0;
+        let returns = function
+            .returns
+            .iter()
+            .flat_map(public_parts)
+            .map(|part| entry(&named(&part.name, &part.symbol_type), false, &mut offset))
+            .collect();
+        let state = function
+            .state
+            .iter()
+            .map(|state| {
+                let variable = &state.variable;
+                if !variable.is_private {
+                    let old = entry(variable, false, &mut offsets.1);
+                    let new = if state.is_write {
+                        Some(entry(variable, false, &mut offset))
+                    } else {
+                        None
+                    };
+                    return StateLayout {
+                        variable: variable.name.clone(),
+                        old,
+                        new,
+                        commitment: None,
+                    };
+                }
+                let digest = named(&variable.name, &SymbolType::Bytes32);
+                let old = entry(variable, true, &mut offsets.0);
+                let salt = entry(&digest, true, &mut offsets.0);
+                let mut commitment = CommitmentLayout {
+                    salt,
+                    old: entry(&digest, false, &mut offset),
+                    new_salt: None,
+                    new: None,
+                };
+                if state.is_write {
+                    commitment.new_salt = Some(entry(&digest, true, &mut offsets.0));
+                    commitment.new = Some(entry(&digest, false, &mut offset));
+                }
+                StateLayout {
+                    variable: variable.name.clone(),
+                    old,
+                    new: None,
+                    commitment: Some(commitment),
+                }
+            })
+            .collect();
+        let storage = function
+            .storage
+            .iter()
+            .map(|access| {
+                let (key_type, value_type) = entry_types(access);
+                let name = &access.mapping.name;
+                let old = entry(&named(name, value_type), false, &mut offsets.1);
+                offset += 1;
+                let active = offset - 1;
+                let key = entry(&named(name, key_type), false, &mut offset);
+                let new = if access.is_write {
+                    Some(entry(&named(name, value_type), false, &mut offset))
+                } else {
+                    None
+                };
+                StorageLayout {
+                    mapping: name.clone(),
+                    old,
+                    active,
+                    key,
+                    new,
+                }
+            })
+            .collect();
+        let events = function
+            .emits
+            .iter()
+            .map(|event| {
+                offset += 1;
+                EventLayout {
+                    event: event.name.clone(),
+                    active: offset - 1,
+                    args: event
+                        .params
+                        .iter()
+                        .map(|param| entry(param, false, &mut offset))
+                        .collect(),
+                }
+            })
+            .collect();
+        Layout {
+            params,
+            returns,
+            state,
+            storage,
+            events,
+        }
+    }
+
+    pub fn param(&self, name: &str) -> Option<&LayoutEntry> {
+        self.params.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// Parts of a return value that are output, none for a private one.
+fn public_parts(ret: &Symbol) -> Vec<Part> {
+    ret.symbol_type
+        .parts(&ret.name, ret.is_private)
+        .into_iter()
+        .filter(|part| !part.is_private)
+        .collect()
+}
+
+fn entry(symbol: &Symbol, is_private: bool, offset: &mut usize) -> LayoutEntry {
+    let words = symbol.symbol_type.word_count();
+    *offset += words;
+    LayoutEntry {
+        name: symbol.name.clone(),
+        symbol_type: symbol.symbol_type.clone(),
+        is_private,
+        offset: *offset - words,
+        words,
+    }
+}
+
+fn named(name: &str, symbol_type: &SymbolType) -> Symbol {
+    Symbol {
+        name: name.to_string(),
+        ..Symbol::new_type_symbol(symbol_type.clone())
+    }
+}
+
+/// Name of a type in the Solidity ABI.
+pub fn abi_type(symbol_type: &SymbolType) -> String {
+    let name = match symbol_type {
+        SymbolType::Uint256 => "uint256",
+        SymbolType::Int256 => "int256",
+        SymbolType::String | SymbolType::BoundedString(..) => "string",
+        SymbolType::BoundedBytes(..) => "bytes",
+        SymbolType::Address => "address",
+        SymbolType::Bytes32 => "bytes32",
+        SymbolType::Bool => "bool",
+        SymbolType::Array(element, size) => return format!("{}[{}]", abi_type(element), size),
+        SymbolType::Mapping(key, value) => {
+            return format!("mapping({} => {})", abi_type(key), abi_type(value))
+        }
+        SymbolType::Struct(structure) => return structure.name.clone(),
+        SymbolType::None => "null",
+    };
+    name.to_string()
+}
+
+/// JSON manifest of the word layout of `function`.
+///
+/// A function with `require`s or `assert`s, or checking its inputs,
+/// outputs the `valid` word, and with checked arithmetic the output ends
+/// with the `ok` word. Neither is a return value, so each is given as
+/// the output word it is at, or `null` if the function has none.
+pub fn manifest(function: &Function, options: CircuitOptions) -> Value {
+    let layout = Layout::of(function);
+    let output_words = output_len(function, options);
+    let ok = options.checked_arithmetic.then(|| output_words - 1);
+    let valid = function
+        .has_valid_word()
+        .then(|| output_words - 1 - options.checked_arithmetic as usize);
+    let (input_words, input_pub_words) = input_len(function);
+    json!({
+        "function": function.name,
+        "checked_arithmetic": options.checked_arithmetic,
+        "input_words": input_words,
+        "input_pub_words": input_pub_words,
+        "output_words": output_words,
+        "params": layout.params.iter().map(LayoutEntry::to_json).collect::<Vec<_>>(),
+        "returns": layout.returns.iter().map(LayoutEntry::to_json).collect::<Vec<_>>(),
+        "valid": valid,
+        "ok": ok,
+        "state": layout.state.iter().map(StateLayout::to_json).collect::<Vec<_>>(),
+        "storage": layout.storage.iter().map(StorageLayout::to_json).collect::<Vec<_>>(),
+        "events": layout.events.iter().map(EventLayout::to_json).collect::<Vec<_>>(),
+    })
+}
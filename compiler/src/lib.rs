@@ -1,5 +1,17 @@
+pub mod bristol;
+pub mod circuit;
+pub mod encoding;
 pub mod error;
+pub mod flow;
+pub mod inliner;
+pub mod interpreter;
+pub mod layout;
+pub mod lowering;
+pub mod optimizer;
 pub mod prover;
 pub mod rewriter;
+pub mod stats;
+pub mod storage;
 pub mod symbol;
 pub mod verifier;
+pub mod witness;
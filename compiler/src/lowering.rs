@@ -0,0 +1,1328 @@
+use crate::encoding::{bool_words, length_words};
+use crate::error::{error_at, type_error, RewriteErrorType};
+use crate::rewriter::RewriterResult;
+use crate::symbol::{
+    Event, Function, Operation, OperationType, StateVariable, StorageAccess, Symbol, SymbolType,
+};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use zoker_parser::location::Location;
+use zoker_prover::ikos::IKosVariable;
+use zoker_prover::sha256::sha256;
+use zoker_prover::uint256;
+use zoker_prover::uint256::LIMBS;
+
+type VariableKey = (bool, u32);
+
+/// Options changing how functions are lowered to circuits.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CircuitOptions {
+    /// Check every `+`, `-`, `*` and unary `-` for overflow, and `/` and
+    /// `%` for division by zero, like Solidity 0.8.
+    ///
+    /// The circuit then ends with one more public output word, `ok`, which
+    /// is 1 only when no executed operation overflowed.
+    pub checked_arithmetic: bool,
+    /// Run the circuit optimizer on compiled functions.
+    pub optimize: bool,
+}
+
+/// Value of an expression while lowering.
+///
+/// Integers, addresses and bytes are kept as `LIMBS` little-endian limbs,
+/// booleans as a single word holding 0 or 1, arrays as their elements
+/// one after the other and structs as their fields.
+#[derive(Clone)]
+struct Value<T> {
+    symbol_type: SymbolType,
+    words: Vec<T>,
+    constant: Option<BigUint>,
+}
+
+/// A storage access that ran.
+struct Slot<T> {
+    mapping: u32,
+    key: Value<T>,
+    // Set if the access was on the path taken.
+    active: T,
+    // Value of the entry after the access.
+    value: Value<T>,
+}
+
+/// Element an array index picks.
+enum Selector<T> {
+    Constant(usize),
+    /// One bit per element, set for the picked one only.
+    Private(Vec<T>),
+}
+
+/// Words a value of `symbol_type` takes while lowering.
+fn width(symbol_type: &SymbolType) -> usize {
+    match symbol_type {
+        SymbolType::Bool => 1,
+        SymbolType::BoundedBytes(..) | SymbolType::BoundedString(..) => symbol_type.word_count(),
+        SymbolType::Array(element, size) => width(element) * size,
+        SymbolType::Struct(structure) => structure
+            .fields
+            .iter()
+            .map(|field| width(&field.symbol_type))
+            .sum(),
+        _ => LIMBS,
+    }
+}
+
+/// Key and value types of the mapping a storage access is to.
+pub fn entry_types(access: &StorageAccess) -> (&SymbolType, &SymbolType) {
+    access.mapping.symbol_type.as_mapping().unwrap()
+}
+
+/// Number of private and public input words `function` consumes.
+pub fn input_len(function: &Function) -> (usize, usize) {
+    let mut len = (0, 0);
+    for param in &function.params {
+        for part in param.symbol_type.parts(&param.name, param.is_private) {
+            if part.is_private {
+                len.0 += part.symbol_type.word_count();
+            } else {
+                len.1 += part.symbol_type.word_count();
+            }
+        }
+    }
+    for state in &function.state {
+        let count = state.variable.symbol_type.word_count();
+        if state.variable.is_private {
+            len.0 += count + salt_count(state);
+        } else {
+            len.1 += count;
+        }
+    }
+    for access in &function.storage {
+        len.1 += entry_types(access).1.word_count();
+    }
+    len
+}
+
+/// Number of output words `function` produces.
+pub fn output_len(function: &Function, options: CircuitOptions) -> usize {
+    let mut len = function
+        .returns
+        .iter()
+        .map(|ret| ret.symbol_type.public_word_count(ret.is_private))
+        .sum();
+    for state in &function.state {
+        if state.variable.is_private {
+            len += salt_count(state);
+        } else if state.is_write {
+            len += state.variable.symbol_type.word_count();
+        }
+    }
+    for access in &function.storage {
+        let (key, value) = entry_types(access);
+        len += 1 + key.word_count();
+        if access.is_write {
+            len += value.word_count();
+        }
+    }
+    for event in &function.emits {
+        len += 1 + event_words(event);
+    }
+    if function.has_valid_word() {
+        len += 1;
+    }
+    if options.checked_arithmetic {
+        len + 1
+    } else {
+        len
+    }
+}
+
+/// Number of words the arguments of `event` take.
+pub fn event_words(event: &Event) -> usize {
+    event
+        .params
+        .iter()
+        .map(|param| param.symbol_type.word_count())
+        .sum()
+}
+
+// Words of the salts of a private state variable, which are as many as
+// those of its commitments.
+fn salt_count(state: &StateVariable) -> usize {
+    let count = SymbolType::Bytes32.word_count();
+    if state.is_write {
+        2 * count
+    } else {
+        count
+    }
+}
+
+/// Runs `function` as a circuit over shared words.
+///
+/// Private parameters are read from `input` and public parameters from
+/// `input_pub`, in declaration order and each as big-endian 32-bit words.
+/// Public words are usually constants made with `new_value`.
+/// The return value comes back as big-endian words as well. The fields
+/// of a struct are read from `input` or `input_pub` by their own privacy,
+/// and only its public fields are output.
+///
+/// State follows: `input_pub` goes on with the value of each state
+/// variable used before the call and the output with the value of each
+/// one written after it. A private state variable adds instead its value
+/// and the `bytes32` salt of its commitment to `input`, and the commitment
+/// to the output. If written, the salt of the new commitment follows in
+/// `input` and the new commitment in the output.
+///
+/// Then come the storage accesses: `input_pub` ends with the value each
+/// accessed entry had before the call, and the output with, for each
+/// access, a word set to 1 if it ran, its key and, for writes, the value
+/// written. All of them are 0 for accesses that did not run.
+///
+/// Each `emit` then outputs a word set to 1 if it ran and the arguments
+/// of the event, 0 as well if it did not.
+///
+/// A function with `require`s or `assert`s outputs one more word, set to
+/// 1 if every one of them that ran held. So does one with private `bool`s
+/// or bounded `bytes` or strings among its parameters, where the word is
+/// also 0 if their words are not canonical.
+pub fn lower_function<T: IKosVariable>(
+    function: &Function,
+    options: CircuitOptions,
+    input: &[T],
+    input_pub: &[T],
+) -> RewriterResult<Vec<T>> {
+    let mut lowering = Lowering::new(options);
+    let (private_offset, mut offset) = lowering.bind_params(function, input, input_pub)?;
+    offset += lowering.bind_state(
+        &function.state,
+        &input[private_offset..],
+        &input_pub[offset..],
+    )?;
+    lowering.bind_storage(&function.storage, &input_pub[offset..])?;
+    lowering.lower_operations(&function.operations)?;
+    let mut output = lowering.outputs(&function.returns)?;
+    output.extend(lowering.state_outputs(&function.state)?);
+    output.extend(lowering.storage_outputs(&function.storage)?);
+    output.extend(lowering.event_outputs(&function.emits)?);
+    if function.has_valid_word() {
+        output.push(match lowering.failed {
+            Some(failed) => failed.xor(&T::new_value(1)),
+            None => T::new_value(1),
+        });
+    }
+    if options.checked_arithmetic {
+        let ok = match lowering.overflow {
+            Some(overflow) => overflow.xor(&T::new_value(1)),
+            None => T::new_value(1),
+        };
+        output.push(ok);
+    }
+    Ok(output)
+}
+
+fn or<T: IKosVariable>(a: T, b: &T) -> RewriterResult<T> {
+    let both = a.clone().bit_and(b)?;
+    Ok(a.xor(b).xor(&both))
+}
+
+// Limbs of the smallest `int`, -2^255.
+fn int_min<T: IKosVariable>() -> Vec<T> {
+    let mut limbs = [0; LIMBS];
+    limbs[LIMBS - 1] = 1 << 31;
+    uint256::constant(&limbs)
+}
+
+// Arrays and structs only convert to the same type.
+fn check_convertible(from: &SymbolType, to: &SymbolType, location: Location) -> RewriterResult<()> {
+    if (from.compound_kind().is_some() || to.compound_kind().is_some()) && from != to {
+        return type_error(&format!("cannot convert {} to {}", from, to), location);
+    }
+    Ok(())
+}
+
+fn constant_limbs(value: &BigUint, location: Location) -> RewriterResult<Vec<u32>> {
+    let bytes = value.to_bytes_le();
+    if bytes.len() > LIMBS * 4 {
+        return type_error("constant does not fit in 256 bits", location);
+    }
+    let mut limbs = vec![0; LIMBS];
+    for (i, &byte) in bytes.iter().enumerate() {
+        limbs[i / 4] |= (byte as u32) << (8 * (i % 4));
+    }
+    Ok(limbs)
+}
+
+struct Lowering<T> {
+    options: CircuitOptions,
+    // Location of the operation being lowered, where errors are reported.
+    location: Location,
+    variables: HashMap<VariableKey, Value<T>>,
+    // Keys of the state variables among them.
+    state: HashSet<VariableKey>,
+    // Variables assigned inside each enclosing branch.
+    assigned: Vec<HashSet<VariableKey>>,
+    ret: Option<Value<T>>,
+    // Set once a `return` has been taken; `None` while none can have been.
+    done: Option<T>,
+    // Condition of the `if` just lowered, for its `else`.
+    last_condition: Option<T>,
+    // Overflows in the operations executed so far, when checking them.
+    overflow: Option<T>,
+    // Set if a requirement failed on the path taken or a private input
+    // is not canonical.
+    failed: Option<T>,
+    // Conditions of the enclosing branches.
+    path: Vec<T>,
+    // Commitment before the call and salt of the one after of each private
+    // state variable.
+    commitments: Vec<(Value<T>, Option<Value<T>>)>,
+    // Values of the accessed entries before the call.
+    loaded: Vec<Value<T>>,
+    slots: Vec<Option<Slot<T>>>,
+    // Accesses that ran, in order.
+    ran: Vec<usize>,
+    // Whether each `emit` ran and its arguments, once lowered.
+    emitted: Vec<Option<(T, Vec<Value<T>>)>>,
+}
+
+impl<T: IKosVariable> Lowering<T> {
+    fn new(options: CircuitOptions) -> Self {
+        Lowering {
+            options,
+            location: Location::default(),
+            variables: HashMap::new(),
+            state: HashSet::new(),
+            assigned: vec![],
+            ret: None,
+            done: None,
+            last_condition: None,
+            overflow: None,
+            failed: None,
+            path: vec![],
+            commitments: vec![],
+            loaded: vec![],
+            slots: vec![],
+            ran: vec![],
+            emitted: vec![],
+        }
+    }
+
+    // Returns the number of public words read.
+    fn bind_params(
+        &mut self,
+        function: &Function,
+        input: &[T],
+        input_pub: &[T],
+    ) -> RewriterResult<(usize, usize)> {
+        let mut private_offset = 0;
+        let mut public_offset = 0;
+        for (i, param) in function.params.iter().enumerate() {
+            // Errors are about the parameter the words are bound to.
+            let location = function
+                .param_locations
+                .get(i)
+                .copied()
+                .unwrap_or(self.location);
+            if param.symbol_type.word_count() == 0 {
+                return type_error("parameter type has no circuit encoding", location);
+            }
+            let mut words = vec![];
+            for part in param.symbol_type.parts(&param.name, param.is_private) {
+                let count = part.symbol_type.word_count();
+                if part.is_private {
+                    if input.len() < private_offset + count {
+                        return type_error("not enough private input words", location);
+                    }
+                    private_offset += count;
+                    let part_words = &input[private_offset - count..private_offset];
+                    self.check_canonical(&part.symbol_type, part_words)?;
+                    words.extend_from_slice(part_words);
+                } else {
+                    if input_pub.len() < public_offset + count {
+                        return type_error("not enough public input words", location);
+                    }
+                    public_offset += count;
+                    words.extend_from_slice(&input_pub[public_offset - count..public_offset]);
+                }
+            }
+            let value = self.words_value(&param.symbol_type, &words);
+            self.variables.insert((param.is_private, param.num), value);
+        }
+        Ok((private_offset, public_offset))
+    }
+
+    // Fails the function unless the private `words` of a `symbol_type`
+    // value are canonical: `bool`s at most 1 and bounded `bytes` and
+    // strings no longer than their bound. The verifier checks public ones.
+    fn check_canonical(&mut self, symbol_type: &SymbolType, words: &[T]) -> RewriterResult<()> {
+        let bools = bool_words(symbol_type)
+            .into_iter()
+            .map(|offset| (offset, 1));
+        for (offset, max) in bools.chain(length_words(symbol_type)) {
+            let max = T::new_value(max as u32);
+            self.fail(uint256::lt(&[max], &[words[offset].clone()])?)?;
+        }
+        Ok(())
+    }
+
+    // Sets the `valid` word to 0 if `failed` is set.
+    fn fail(&mut self, failed: T) -> RewriterResult<()> {
+        self.failed = Some(match self.failed.take() {
+            Some(old) => or(old, &failed)?,
+            None => failed,
+        });
+        Ok(())
+    }
+
+    // Returns the number of public words read.
+    fn bind_state(
+        &mut self,
+        state: &[StateVariable],
+        input: &[T],
+        input_pub: &[T],
+    ) -> RewriterResult<usize> {
+        let location = self.location;
+        let mut words = (input.iter(), input_pub.iter());
+        let mut read = |count: usize, is_private: bool| {
+            let (words, class) = if is_private {
+                (&mut words.0, "private")
+            } else {
+                (&mut words.1, "public")
+            };
+            let read = words.by_ref().take(count).cloned().collect::<Vec<T>>();
+            if read.len() < count {
+                return type_error(&format!("not enough {} input words", class), location);
+            }
+            Ok(read)
+        };
+        let salt_words = SymbolType::Bytes32.word_count();
+        let mut offset = 0;
+        for variable in state {
+            let symbol_type = &variable.variable.symbol_type;
+            let is_private = variable.variable.is_private;
+            let words = read(symbol_type.word_count(), is_private)?;
+            let value = self.words_value(symbol_type, &words);
+            if is_private {
+                let salt = self.words_value(&SymbolType::Bytes32, &read(salt_words, true)?);
+                let new_salt = if variable.is_write {
+                    Some(self.words_value(&SymbolType::Bytes32, &read(salt_words, true)?))
+                } else {
+                    None
+                };
+                let commitment = self.digest(&[&value, &salt])?;
+                self.commitments.push((commitment, new_salt));
+            } else {
+                offset += words.len();
+            }
+            self.state.insert((is_private, variable.local.num));
+            self.variables
+                .insert((is_private, variable.local.num), value);
+        }
+        Ok(offset)
+    }
+
+    fn state_outputs(&self, state: &[StateVariable]) -> RewriterResult<Vec<T>> {
+        let mut output = vec![];
+        let mut commitments = self.commitments.iter();
+        for variable in state {
+            let value = &self.variables[&(variable.variable.is_private, variable.local.num)];
+            if !variable.variable.is_private {
+                if variable.is_write {
+                    output.extend(self.to_words(value));
+                }
+                continue;
+            }
+            let (commitment, new_salt) = commitments.next().unwrap();
+            output.extend(self.to_words(commitment));
+            if let Some(new_salt) = new_salt {
+                output.extend(self.to_words(&self.digest(&[value, new_salt])?));
+            }
+        }
+        Ok(output)
+    }
+
+    fn bind_storage(&mut self, accesses: &[StorageAccess], input_pub: &[T]) -> RewriterResult<()> {
+        let mut offset = 0;
+        for access in accesses {
+            let value_type = entry_types(access).1;
+            let count = value_type.word_count();
+            if input_pub.len() < offset + count {
+                return type_error("not enough public input words", self.location);
+            }
+            let value = self.words_value(value_type, &input_pub[offset..offset + count]);
+            self.loaded.push(value);
+            self.slots.push(None);
+            offset += count;
+        }
+        Ok(())
+    }
+
+    /// Value of the big-endian words of a `symbol_type` value.
+    fn words_value(&self, symbol_type: &SymbolType, words: &[T]) -> Value<T> {
+        if let Some((element, size)) = symbol_type.as_array() {
+            let count = element.word_count();
+            let words = (0..size)
+                .flat_map(|i| {
+                    self.words_value(element, &words[i * count..(i + 1) * count])
+                        .words
+                })
+                .collect();
+            return self.padded_value(symbol_type.clone(), words);
+        }
+        if let Some(structure) = symbol_type.as_struct() {
+            let mut offset = 0;
+            let mut fields = vec![];
+            for field in &structure.fields {
+                let count = field.symbol_type.word_count();
+                fields.push(self.words_value(&field.symbol_type, &words[offset..offset + count]));
+                offset += count;
+            }
+            return self.array(symbol_type.clone(), fields);
+        }
+        let mut words = words.to_vec();
+        words.reverse();
+        self.padded_value(symbol_type.clone(), words)
+    }
+
+    /// Big-endian words of `value`, the inverse of `words_value`.
+    fn to_words(&self, value: &Value<T>) -> Vec<T> {
+        if value.symbol_type.as_array().is_some() {
+            return self
+                .elements(value)
+                .iter()
+                .flat_map(|element| self.to_words(element))
+                .collect();
+        }
+        if value.symbol_type.as_struct().is_some() {
+            return self
+                .fields(value)
+                .iter()
+                .flat_map(|field| self.to_words(field))
+                .collect();
+        }
+        let mut words = value.words.clone();
+        words.truncate(value.symbol_type.word_count());
+        words.reverse();
+        words
+    }
+
+    fn outputs(&self, returns: &[Symbol]) -> RewriterResult<Vec<T>> {
+        if returns.is_empty() {
+            return Ok(vec![]);
+        }
+        if returns.len() > 1 {
+            return Err(error_at(RewriteErrorType::UnsupportedError, self.location));
+        }
+        let value = match &self.ret {
+            Some(value) => value,
+            None => return type_error("function does not return a value", self.location),
+        };
+        let symbol_type = &returns[0].symbol_type;
+        check_convertible(&value.symbol_type, symbol_type, self.location)?;
+        let words = self.to_words(&self.convert(value, symbol_type));
+        Ok(symbol_type.public_words(returns[0].is_private, &words))
+    }
+
+    fn storage_outputs(&self, accesses: &[StorageAccess]) -> RewriterResult<Vec<T>> {
+        let mut output = vec![];
+        for (access, slot) in accesses.iter().zip(&self.slots) {
+            let (key_type, value_type) = entry_types(access);
+            let slot = match slot {
+                Some(slot) => slot,
+                None => {
+                    let mut count = 1 + key_type.word_count();
+                    if access.is_write {
+                        count += value_type.word_count();
+                    }
+                    output.extend((0..count).map(|_| T::new_value(0)));
+                    continue;
+                }
+            };
+            output.push(slot.active.clone());
+            let mut words = self.to_words(&slot.key);
+            if access.is_write {
+                words.extend(self.to_words(&slot.value));
+            }
+            for word in words {
+                output.push(slot.active.clone().mux(&word, &T::new_value(0))?);
+            }
+        }
+        Ok(output)
+    }
+
+    fn event_outputs(&self, emits: &[Event]) -> RewriterResult<Vec<T>> {
+        let mut output = vec![];
+        for (i, event) in emits.iter().enumerate() {
+            let (active, args) = match self.emitted.get(i) {
+                Some(Some(emitted)) => emitted,
+                _ => {
+                    output.extend((0..1 + event_words(event)).map(|_| T::new_value(0)));
+                    continue;
+                }
+            };
+            output.push(active.clone());
+            for word in args.iter().flat_map(|arg| self.to_words(arg)) {
+                output.push(active.clone().mux(&word, &T::new_value(0))?);
+            }
+        }
+        Ok(output)
+    }
+
+    // SHA-256 digest of the words of `values`.
+    fn digest(&self, values: &[&Value<T>]) -> RewriterResult<Value<T>> {
+        let mut message = vec![];
+        for value in values {
+            message.extend(self.to_words(value));
+        }
+        let mut digest = sha256(&message)?;
+        digest.reverse();
+        Ok(self.padded_value(SymbolType::Bytes32, digest))
+    }
+
+    fn padded_value(&self, symbol_type: SymbolType, mut words: Vec<T>) -> Value<T> {
+        let len = width(&symbol_type);
+        while words.len() < len {
+            words.push(T::new_value(0));
+        }
+        Value {
+            symbol_type,
+            words,
+            constant: None,
+        }
+    }
+
+    fn convert(&self, value: &Value<T>, symbol_type: &SymbolType) -> Value<T> {
+        let words = match (&value.symbol_type, symbol_type) {
+            (SymbolType::Bool, SymbolType::Bool) => value.words.clone(),
+            (SymbolType::Bool, _) => {
+                let mut words = uint256::zero();
+                words[0] = value.words[0].clone();
+                words
+            }
+            (_, SymbolType::Bool) => vec![value.words[0].clone().bit_slice(0, 1)],
+            _ => value.words.clone(),
+        };
+        Value {
+            symbol_type: symbol_type.clone(),
+            words,
+            constant: value.constant.clone(),
+        }
+    }
+
+    fn limbs(&self, value: &Value<T>) -> Vec<T> {
+        match value.symbol_type {
+            SymbolType::Bool => self.convert(value, &SymbolType::Uint256).words,
+            _ => value.words.clone(),
+        }
+    }
+
+    fn condition(&self, value: &Value<T>) -> RewriterResult<T> {
+        if let Some(kind) = value.symbol_type.compound_kind() {
+            return type_error(&format!("{} is not a condition", kind), self.location);
+        }
+        match value.symbol_type {
+            SymbolType::Bool => Ok(value.words[0].clone()),
+            _ => Ok(uint256::is_zero(&value.words)?.xor(&T::new_value(1))),
+        }
+    }
+
+    fn elements(&self, array: &Value<T>) -> Vec<Value<T>> {
+        let (element, size) = match array.symbol_type.as_array() {
+            Some(array) => array,
+            None => return vec![],
+        };
+        let len = width(element);
+        (0..size)
+            .map(|i| Value {
+                symbol_type: element.clone(),
+                words: array.words[i * len..(i + 1) * len].to_vec(),
+                constant: None,
+            })
+            .collect()
+    }
+
+    fn fields(&self, structure: &Value<T>) -> Vec<Value<T>> {
+        let fields = match structure.symbol_type.as_struct() {
+            Some(structure) => &structure.fields,
+            None => return vec![],
+        };
+        let mut offset = 0;
+        fields
+            .iter()
+            .map(|field| {
+                let len = width(&field.symbol_type);
+                offset += len;
+                Value {
+                    symbol_type: field.symbol_type.clone(),
+                    words: structure.words[offset - len..offset].to_vec(),
+                    constant: None,
+                }
+            })
+            .collect()
+    }
+
+    // Position of field `name` of `structure`.
+    fn field(&self, structure: &Value<T>, name: &str) -> RewriterResult<usize> {
+        match structure.symbol_type.as_struct() {
+            Some(typ) => match typ.field(name) {
+                Some((i, _)) => Ok(i),
+                None => type_error(
+                    &format!("{} has no field `{}`", structure.symbol_type, name),
+                    self.location,
+                ),
+            },
+            None => type_error(
+                &format!("{} is not a struct", structure.symbol_type),
+                self.location,
+            ),
+        }
+    }
+
+    // Also builds structs from their fields.
+    fn array(&self, symbol_type: SymbolType, elements: Vec<Value<T>>) -> Value<T> {
+        Value {
+            symbol_type,
+            words: elements
+                .into_iter()
+                .flat_map(|element| element.words)
+                .collect(),
+            constant: None,
+        }
+    }
+
+    // A constant index is checked here; a private one out of range picks
+    // no element and counts as an overflow when checking them.
+    fn selector(&mut self, index: &Value<T>, size: usize) -> RewriterResult<Selector<T>> {
+        if let Some(kind) = index.symbol_type.compound_kind() {
+            return type_error(&format!("{} is not an index", kind), self.location);
+        }
+        if let Some(i) = &index.constant {
+            if *i >= BigUint::from(size) {
+                return type_error("array index out of bounds", self.location);
+            }
+            return Ok(Selector::Constant(
+                constant_limbs(i, self.location)?[0] as usize,
+            ));
+        }
+        let limbs = self.limbs(index);
+        let mut bits = vec![];
+        for i in 0..size {
+            let i = uint256::constant(&constant_limbs(&BigUint::from(i), self.location)?);
+            bits.push(uint256::eq(&limbs, &i)?);
+        }
+        if self.options.checked_arithmetic {
+            let size = uint256::constant(&constant_limbs(&BigUint::from(size), self.location)?);
+            let in_range = uint256::lt(&limbs, &size)?;
+            self.check_overflow(in_range.xor(&T::new_value(1)))?;
+        }
+        Ok(Selector::Private(bits))
+    }
+
+    // A private index scans the whole array, so the element read does not
+    // show which one it was.
+    fn index(&mut self, array: &Value<T>, index: &Value<T>) -> RewriterResult<Value<T>> {
+        let element = match array.symbol_type.as_array() {
+            Some((element, _)) => element.clone(),
+            None => {
+                return type_error(
+                    &format!("{} is not an array", array.symbol_type),
+                    self.location,
+                )
+            }
+        };
+        let elements = self.elements(array);
+        match self.selector(index, elements.len())? {
+            Selector::Constant(i) => Ok(elements[i].clone()),
+            Selector::Private(bits) => {
+                let mut value = self.padded_value(element, vec![]);
+                for (bit, element) in bits.iter().zip(elements) {
+                    value.words = uint256::mux(bit, &element.words, &value.words)?;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    // Stores `value` in the variable, array element or field `target`
+    // names.
+    fn store(&mut self, target: &Operation, value: Value<T>) -> RewriterResult<Value<T>> {
+        let (array, index) = match &target.operation {
+            OperationType::Index { array, index } => (array, index),
+            OperationType::Member { value: old, field } => {
+                let old_value = self.lower_expression(old)?;
+                let i = self.field(&old_value, field)?;
+                let mut fields = self.fields(&old_value);
+                let value = self.stored(&value, &fields[i].symbol_type)?;
+                fields[i] = value.clone();
+                let new = self.array(old_value.symbol_type, fields);
+                self.store(old, new)?;
+                return Ok(value);
+            }
+            _ => return self.assign(target, value),
+        };
+        let old = self.lower_expression(array)?;
+        let index = self.lower_expression(index)?;
+        let element = match old.symbol_type.as_array() {
+            Some((element, _)) => element.clone(),
+            None => {
+                return type_error(
+                    &format!("{} is not an array", old.symbol_type),
+                    self.location,
+                )
+            }
+        };
+        let value = self.stored(&value, &element)?;
+        let mut elements = self.elements(&old);
+        match self.selector(&index, elements.len())? {
+            Selector::Constant(i) => elements[i] = value.clone(),
+            Selector::Private(bits) => {
+                for (bit, element) in bits.iter().zip(elements.iter_mut()) {
+                    element.words = uint256::mux(bit, &value.words, &element.words)?;
+                }
+            }
+        }
+        let new = self.array(old.symbol_type, elements);
+        self.store(array, new)?;
+        Ok(value)
+    }
+
+    // `value` as stored in an array element, a field or a mapping entry
+    // of type `symbol_type`, which only keep the bits they are encoded
+    // with.
+    fn stored(&self, value: &Value<T>, symbol_type: &SymbolType) -> RewriterResult<Value<T>> {
+        check_convertible(&value.symbol_type, symbol_type, self.location)?;
+        let mut value = self.convert(value, symbol_type);
+        if *symbol_type == SymbolType::Address {
+            for word in value.words[5..].iter_mut() {
+                *word = T::new_value(0);
+            }
+        }
+        Ok(value)
+    }
+
+    // Set while the operation being lowered is on the path taken.
+    fn active(&self) -> RewriterResult<T> {
+        let mut active = T::new_value(1);
+        for cond in &self.path {
+            active = active.bit_and(cond)?;
+        }
+        if let Some(done) = &self.done {
+            active = active.bit_and(&done.clone().xor(&T::new_value(1)))?;
+        }
+        Ok(active)
+    }
+
+    // Reads, or writes `value` to, the entry `key` of `mapping`.
+    //
+    // The entry holds the value last written by an earlier access to the
+    // same key on the path taken, or else the one loaded from storage.
+    // Evaluating an access again gives the value it had.
+    fn access(
+        &mut self,
+        mapping: &Symbol,
+        key: &Operation,
+        value: Option<&Operation>,
+        access: usize,
+    ) -> RewriterResult<Value<T>> {
+        if let Some(slot) = &self.slots[access] {
+            return Ok(slot.value.clone());
+        }
+        let (key_type, value_type) = match mapping.symbol_type.as_mapping() {
+            Some(types) => types,
+            None => {
+                return type_error(
+                    &format!("`{}` is not a mapping", mapping.name),
+                    self.location,
+                )
+            }
+        };
+        let value = match value {
+            Some(value) => Some(self.lower_expression(value)?),
+            None => None,
+        };
+        let key = self.lower_expression(key)?;
+        let key = self.stored(&key, key_type)?;
+        let mut current = self.loaded[access].clone();
+        for &i in &self.ran {
+            let slot = self.slots[i].as_ref().unwrap();
+            if slot.mapping != mapping.num {
+                continue;
+            }
+            let same = uint256::eq(&self.limbs(&slot.key), &self.limbs(&key))?;
+            let hit = same.bit_and(&slot.active)?;
+            current.words = uint256::mux(&hit, &slot.value.words, &current.words)?;
+        }
+        let active = self.active()?;
+        let value = match value {
+            Some(value) => {
+                let mut value = self.stored(&value, value_type)?;
+                value.words = uint256::mux(&active, &value.words, &current.words)?;
+                value
+            }
+            None => current,
+        };
+        self.slots[access] = Some(Slot {
+            mapping: mapping.num,
+            key,
+            active,
+            value: value.clone(),
+        });
+        self.ran.push(access);
+        Ok(value)
+    }
+
+    fn boolean(&self, bit: T) -> Value<T> {
+        Value {
+            symbol_type: SymbolType::Bool,
+            words: vec![bit],
+            constant: None,
+        }
+    }
+
+    fn lower_operations(&mut self, operations: &[Operation]) -> RewriterResult<()> {
+        for operation in operations {
+            self.lower_operation(operation)?;
+        }
+        Ok(())
+    }
+
+    fn lower_operation(&mut self, operation: &Operation) -> RewriterResult<()> {
+        let outer = std::mem::replace(&mut self.location, operation.location);
+        match &operation.operation {
+            OperationType::Symbol { symbol } => {
+                let zero = self.padded_value(symbol.symbol_type.clone(), vec![]);
+                self.variables
+                    .entry((symbol.is_private, symbol.num))
+                    .or_insert(zero);
+            }
+            OperationType::Return { ret } => {
+                let value = self.lower_expression(ret)?;
+                self.ret = match (&self.done, &self.ret) {
+                    (Some(done), Some(old)) => Some(Value {
+                        symbol_type: value.symbol_type.clone(),
+                        words: uint256::mux(done, &old.words, &self.limbs(&value))?,
+                        constant: None,
+                    }),
+                    _ => Some(value),
+                };
+                self.done = Some(T::new_value(1));
+            }
+            OperationType::Require { cond, .. } => {
+                let cond = self.lower_expression(cond)?;
+                let cond = self.condition(&cond)?;
+                let failed = self.active()?.bit_and(&cond.xor(&T::new_value(1)))?;
+                self.fail(failed)?;
+            }
+            OperationType::Emit { event, args, emit } => {
+                let mut values = vec![];
+                for (param, arg) in event.params.iter().zip(args) {
+                    let value = self.lower_expression(arg)?;
+                    values.push(self.stored(&value, &param.symbol_type)?);
+                }
+                if self.emitted.len() <= *emit {
+                    self.emitted.resize_with(*emit + 1, || None);
+                }
+                self.emitted[*emit] = Some((self.active()?, values));
+            }
+            OperationType::If { cond, stmts } => {
+                let cond = self.lower_expression(cond)?;
+                let cond = self.condition(&cond)?;
+                self.lower_branch(cond.clone(), stmts)?;
+                self.last_condition = Some(cond);
+            }
+            OperationType::Else { cond, stmts } => {
+                // The `if` branch may have changed what `cond` reads, so the
+                // condition it was taken on is reused.
+                let cond = match self.last_condition.take() {
+                    Some(cond) => cond,
+                    None => {
+                        let cond = self.lower_expression(cond)?;
+                        self.condition(&cond)?
+                    }
+                };
+                self.lower_branch(cond.xor(&T::new_value(1)), stmts)?;
+            }
+            OperationType::For {
+                iter,
+                vector,
+                stmts,
+            } => {
+                // Loops are unrolled over a copy of the array.
+                let vector = self.lower_expression(vector)?;
+                if vector.symbol_type.as_array().is_none() {
+                    return type_error(
+                        &format!("cannot iterate over {}", vector.symbol_type),
+                        self.location,
+                    );
+                }
+                for element in self.elements(&vector) {
+                    self.assign(iter, element)?;
+                    self.lower_operations(stmts)?;
+                }
+            }
+            OperationType::Nop => {}
+            _ => {
+                self.lower_expression(operation)?;
+            }
+        }
+        self.location = outer;
+        Ok(())
+    }
+
+    // Both sides of a private branch are executed; afterwards every
+    // variable the branch assigned is selected with `mux`.
+    fn lower_branch(&mut self, cond: T, stmts: &[Operation]) -> RewriterResult<()> {
+        let variables = self.variables.clone();
+        let ret = self.ret.clone();
+        let done = self.done.clone();
+
+        let overflow = self.overflow.take();
+
+        self.assigned.push(HashSet::new());
+        self.path.push(cond.clone());
+        self.lower_operations(stmts)?;
+        self.path.pop();
+        let assigned = self.assigned.pop().unwrap();
+
+        // Overflows only count on the path actually taken.
+        let branch_overflow = std::mem::replace(&mut self.overflow, overflow);
+        if let Some(branch_overflow) = branch_overflow {
+            self.add_overflow(branch_overflow.bit_and(&cond)?)?;
+        }
+
+        let mut merged = variables;
+        for key in assigned {
+            if let Some(old) = merged.get(&key) {
+                let value = self.select(&cond, &self.variables[&key], old)?;
+                merged.insert(key, value);
+                if let Some(outer) = self.assigned.last_mut() {
+                    outer.insert(key);
+                }
+            }
+        }
+        self.variables = merged;
+
+        if let Some(branch_done) = self.done.clone() {
+            let old_done = done.unwrap_or_else(|| T::new_value(0));
+            self.done = Some(cond.clone().mux(&branch_done, &old_done)?);
+            if let (Some(new), Some(old)) = (&self.ret, &ret) {
+                let words = uint256::mux(&cond, &self.limbs(new), &self.limbs(old))?;
+                self.ret = Some(Value {
+                    symbol_type: new.symbol_type.clone(),
+                    words,
+                    constant: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // `new` if `cond` is set and `old` otherwise.
+    fn select(&self, cond: &T, new: &Value<T>, old: &Value<T>) -> RewriterResult<Value<T>> {
+        let words = if old.symbol_type == SymbolType::Bool {
+            vec![cond.clone().mux(&new.words[0], &old.words[0])?]
+        } else {
+            uint256::mux(cond, &new.words, &old.words)?
+        };
+        Ok(Value {
+            symbol_type: old.symbol_type.clone(),
+            words,
+            constant: None,
+        })
+    }
+
+    // Records an overflow unless a `return` was already taken.
+    fn check_overflow(&mut self, overflow: T) -> RewriterResult<()> {
+        let overflow = match &self.done {
+            Some(done) => overflow.bit_and(&done.clone().xor(&T::new_value(1)))?,
+            None => overflow,
+        };
+        self.add_overflow(overflow)
+    }
+
+    fn add_overflow(&mut self, overflow: T) -> RewriterResult<()> {
+        self.overflow = Some(match self.overflow.take() {
+            Some(old) => or(old, &overflow)?,
+            None => overflow,
+        });
+        Ok(())
+    }
+
+    fn assign(&mut self, left: &Operation, value: Value<T>) -> RewriterResult<Value<T>> {
+        let symbol = match left.as_symbol() {
+            Some(symbol) => symbol,
+            None => {
+                return type_error(
+                    "left side of an assignment must be a variable",
+                    self.location,
+                )
+            }
+        };
+        let key = (symbol.is_private, symbol.num);
+        check_convertible(&value.symbol_type, &symbol.symbol_type, self.location)?;
+        let mut value = self.convert(&value, &symbol.symbol_type);
+        // State is output, so it keeps its value once a `return` was taken.
+        if self.done.is_some() && self.state.contains(&key) {
+            value = self.select(&self.active()?, &value, &self.variables[&key])?;
+        }
+        self.variables.insert(key, value.clone());
+        if let Some(assigned) = self.assigned.last_mut() {
+            assigned.insert(key);
+        }
+        Ok(value)
+    }
+
+    fn lower_expression(&mut self, operation: &Operation) -> RewriterResult<Value<T>> {
+        let outer = std::mem::replace(&mut self.location, operation.location);
+        let value = self.lower_value(operation)?;
+        self.location = outer;
+        Ok(value)
+    }
+
+    fn lower_value(&mut self, operation: &Operation) -> RewriterResult<Value<T>> {
+        match &operation.operation {
+            OperationType::Add { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let (a, b) = (self.limbs(&left), self.limbs(&right));
+                let (words, carry) = uint256::add(&a, &b)?;
+                if self.options.checked_arithmetic {
+                    let overflow = if self.is_signed(&left, &right) {
+                        uint256::signed_add_overflow(&a, &b, &words)?
+                    } else {
+                        carry
+                    };
+                    self.check_overflow(overflow)?;
+                }
+                Ok(self.number(self.result_type(&left, &right), words))
+            }
+            OperationType::Sub { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let (a, b) = (self.limbs(&left), self.limbs(&right));
+                let (words, borrow) = uint256::sub(&a, &b)?;
+                if self.options.checked_arithmetic {
+                    let overflow = if self.is_signed(&left, &right) {
+                        uint256::signed_sub_overflow(&a, &b, &words)?
+                    } else {
+                        borrow
+                    };
+                    self.check_overflow(overflow)?;
+                }
+                Ok(self.number(self.result_type(&left, &right), words))
+            }
+            OperationType::Mul { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let (a, b) = (self.limbs(&left), self.limbs(&right));
+                let words = if !self.options.checked_arithmetic {
+                    uint256::mul(&a, &b)?
+                } else {
+                    let (words, overflow) = if self.is_signed(&left, &right) {
+                        uint256::signed_mul_overflow(&a, &b)?
+                    } else {
+                        uint256::mul_overflow(&a, &b)?
+                    };
+                    self.check_overflow(overflow)?;
+                    words
+                };
+                Ok(self.number(self.result_type(&left, &right), words))
+            }
+            OperationType::Div { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let words = self.divrem(&left, &right)?.0;
+                if self.options.checked_arithmetic {
+                    let (a, b) = (self.limbs(&left), self.limbs(&right));
+                    let mut overflow = uint256::is_zero(&b)?;
+                    if self.is_signed(&left, &right) {
+                        let min = uint256::eq(&a, &int_min())?;
+                        let minus_one = uint256::eq(&b, &uint256::constant(&[u32::MAX; LIMBS]))?;
+                        overflow = or(overflow, &min.bit_and(&minus_one)?)?;
+                    }
+                    self.check_overflow(overflow)?;
+                }
+                Ok(self.number(self.result_type(&left, &right), words))
+            }
+            OperationType::Mod { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let words = self.divrem(&left, &right)?.1;
+                if self.options.checked_arithmetic {
+                    self.check_overflow(uint256::is_zero(&self.limbs(&right))?)?;
+                }
+                Ok(self.number(self.result_type(&left, &right), words))
+            }
+            OperationType::Neg { operand } => {
+                let operand = self.lower_expression(operand)?;
+                if let Some(kind) = operand.symbol_type.compound_kind() {
+                    return type_error(&format!("arithmetic on {}", kind), self.location);
+                }
+                let a = self.limbs(&operand);
+                let words = uint256::neg(&a)?;
+                if self.options.checked_arithmetic {
+                    // The result is an `int`, which holds `-a` for `a` up to
+                    // 2^255 but not for `a = -2^255`.
+                    let overflow = if operand.symbol_type == SymbolType::Int256 {
+                        uint256::eq(&a, &int_min())?
+                    } else {
+                        uint256::lt(&int_min(), &a)?
+                    };
+                    self.check_overflow(overflow)?;
+                }
+                Ok(self.number(SymbolType::Int256, words))
+            }
+            OperationType::Lt { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = self.less_than(&left, &right)?;
+                Ok(self.boolean(bit))
+            }
+            OperationType::Gt { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = self.less_than(&right, &left)?;
+                Ok(self.boolean(bit))
+            }
+            OperationType::Le { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = self.less_than(&right, &left)?;
+                Ok(self.boolean(bit.xor(&T::new_value(1))))
+            }
+            OperationType::Ge { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = self.less_than(&left, &right)?;
+                Ok(self.boolean(bit.xor(&T::new_value(1))))
+            }
+            OperationType::Eq { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = uint256::eq(&self.limbs(&left), &self.limbs(&right))?;
+                Ok(self.boolean(bit))
+            }
+            OperationType::NotEq { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let bit = uint256::eq(&self.limbs(&left), &self.limbs(&right))?;
+                Ok(self.boolean(bit.xor(&T::new_value(1))))
+            }
+            OperationType::LShift { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let n = self.shift_amount(&right)?;
+                let words = uint256::shl(&self.limbs(&left), n);
+                Ok(self.number(self.result_type(&left, &left), words))
+            }
+            OperationType::RShift { left, right } => {
+                let (left, right) = self.lower_operands(left, right)?;
+                let n = self.shift_amount(&right)?;
+                let words = if left.symbol_type == SymbolType::Int256 {
+                    uint256::sar(&left.words, n)
+                } else {
+                    uint256::shr(&self.limbs(&left), n)
+                };
+                Ok(self.number(self.result_type(&left, &left), words))
+            }
+            OperationType::Assign { left, right } => {
+                let value = self.lower_expression(right)?;
+                self.store(left, value)
+            }
+            OperationType::Index { array, index } => {
+                let array = self.lower_expression(array)?;
+                let index = self.lower_expression(index)?;
+                self.index(&array, &index)
+            }
+            OperationType::Member { value, field } => {
+                let value = self.lower_expression(value)?;
+                let i = self.field(&value, field)?;
+                Ok(self.fields(&value).swap_remove(i))
+            }
+            OperationType::Load {
+                mapping,
+                key,
+                access,
+            } => self.access(mapping, key, None, *access),
+            OperationType::Store {
+                mapping,
+                key,
+                value,
+                access,
+            } => self.access(mapping, key, Some(value), *access),
+            OperationType::Sha256 { args } => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.lower_expression(arg)?);
+                }
+                self.digest(&values.iter().collect::<Vec<_>>())
+            }
+            OperationType::Reveal { value } => self.lower_expression(value),
+            OperationType::Symbol { symbol } => {
+                match self.variables.get(&(symbol.is_private, symbol.num)) {
+                    Some(value) => Ok(value.clone()),
+                    None => type_error("use of an undeclared variable", self.location),
+                }
+            }
+            OperationType::Constant { value } => Ok(Value {
+                symbol_type: SymbolType::Uint256,
+                words: uint256::constant(&constant_limbs(value, self.location)?),
+                constant: Some(value.clone()),
+            }),
+            _ => Err(error_at(RewriteErrorType::UnsupportedError, self.location)),
+        }
+    }
+
+    fn lower_operands(
+        &mut self,
+        left: &Operation,
+        right: &Operation,
+    ) -> RewriterResult<(Value<T>, Value<T>)> {
+        let left = self.lower_expression(left)?;
+        let right = self.lower_expression(right)?;
+        for value in [&left, &right] {
+            if let Some(kind) = value.symbol_type.compound_kind() {
+                return type_error(&format!("arithmetic on {}", kind), self.location);
+            }
+        }
+        Ok((left, right))
+    }
+
+    // Mixing `int` into an operation makes it signed, as for literals.
+    fn result_type(&self, left: &Value<T>, right: &Value<T>) -> SymbolType {
+        if self.is_signed(left, right) {
+            return SymbolType::Int256;
+        }
+        match left.symbol_type {
+            SymbolType::Bool => SymbolType::Uint256,
+            ref typ => typ.clone(),
+        }
+    }
+
+    fn is_signed(&self, left: &Value<T>, right: &Value<T>) -> bool {
+        left.symbol_type == SymbolType::Int256 || right.symbol_type == SymbolType::Int256
+    }
+
+    fn number(&self, symbol_type: SymbolType, words: Vec<T>) -> Value<T> {
+        Value {
+            symbol_type,
+            words,
+            constant: None,
+        }
+    }
+
+    fn less_than(&self, left: &Value<T>, right: &Value<T>) -> RewriterResult<T> {
+        let (a, b) = (self.limbs(left), self.limbs(right));
+        if self.is_signed(left, right) {
+            Ok(uint256::slt(&a, &b)?)
+        } else {
+            Ok(uint256::lt(&a, &b)?)
+        }
+    }
+
+    fn divrem(&self, left: &Value<T>, right: &Value<T>) -> RewriterResult<(Vec<T>, Vec<T>)> {
+        let (a, b) = (self.limbs(left), self.limbs(right));
+        if self.is_signed(left, right) {
+            Ok(uint256::sdivrem(&a, &b)?)
+        } else {
+            Ok(uint256::divrem(&a, &b)?)
+        }
+    }
+
+    fn shift_amount(&self, value: &Value<T>) -> RewriterResult<usize> {
+        match &value.constant {
+            Some(n) if *n < BigUint::from(uint256::BITS) => {
+                Ok(constant_limbs(n, self.location)?[0] as usize)
+            }
+            Some(_) => Ok(uint256::BITS),
+            None => type_error("shift amount must be a constant", self.location),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::circuit::{Circuit, Gate, GateCount, WireId};
+use std::collections::HashMap;
+use zoker_prover::utils::slice_bits;
+
+/// Gate counts of a circuit before and after optimizing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OptimizeReport {
+    pub before: GateCount,
+    pub after: GateCount,
+}
+
+/// Replaces the public inputs of `circuit` with the words of `input_pub`,
+/// so that `optimize` can fold them.
+pub fn specialize(circuit: &Circuit, input_pub: &[u32]) -> Circuit {
+    let mut circuit = circuit.clone();
+    for gate in circuit.gates.iter_mut() {
+        if let Gate::Input {
+            index,
+            is_private: false,
+        } = *gate
+        {
+            *gate = Gate::Const(input_pub[index]);
+        }
+    }
+    circuit
+}
+
+/// Folds constants, simplifies algebraically, merges common subexpressions
+/// and removes gates no output depends on.
+pub fn optimize(circuit: &Circuit) -> (Circuit, OptimizeReport) {
+    let simplified = Simplifier::new(circuit).run();
+    let optimized = eliminate_dead_gates(&simplified);
+    let report = OptimizeReport {
+        before: circuit.gate_count(),
+        after: optimized.gate_count(),
+    };
+    (optimized, report)
+}
+
+#[derive(Clone, Copy)]
+enum Value {
+    Const(u32),
+    Wire(WireId),
+}
+
+struct Simplifier<'a> {
+    source: &'a Circuit,
+    circuit: Circuit,
+    values: Vec<Value>,
+    consts: HashMap<u32, WireId>,
+    known: HashMap<Gate, WireId>,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(source: &'a Circuit) -> Self {
+        Simplifier {
+            source,
+            circuit: Circuit {
+                input_len: source.input_len,
+                input_pub_len: source.input_pub_len,
+                ..Circuit::default()
+            },
+            values: vec![],
+            consts: HashMap::new(),
+            known: HashMap::new(),
+        }
+    }
+
+    fn run(mut self) -> Circuit {
+        for gate in &self.source.gates {
+            let value = self.simplify(gate);
+            self.values.push(value);
+        }
+        for &output in &self.source.outputs {
+            let id = self.wire(self.values[output]);
+            self.circuit.outputs.push(id);
+        }
+        self.circuit
+    }
+
+    fn wire(&mut self, value: Value) -> WireId {
+        match value {
+            Value::Wire(id) => id,
+            Value::Const(c) => {
+                if let Some(&id) = self.consts.get(&c) {
+                    return id;
+                }
+                let id = self.circuit.push(Gate::Const(c));
+                self.consts.insert(c, id);
+                id
+            }
+        }
+    }
+
+    fn emit(&mut self, gate: Gate) -> Value {
+        if let Some(&id) = self.known.get(&gate) {
+            return Value::Wire(id);
+        }
+        let id = self.circuit.push(gate.clone());
+        self.known.insert(gate, id);
+        Value::Wire(id)
+    }
+
+    fn unary(
+        &mut self,
+        a: WireId,
+        gate: impl Fn(WireId) -> Gate,
+        fold: impl Fn(u32) -> u32,
+    ) -> Value {
+        match self.values[a] {
+            Value::Const(x) => Value::Const(fold(x)),
+            Value::Wire(a) => self.emit(gate(a)),
+        }
+    }
+
+    fn simplify(&mut self, gate: &Gate) -> Value {
+        match *gate {
+            Gate::Const(c) => Value::Const(c),
+            Gate::Input { .. } => self.emit(gate.clone()),
+            Gate::Xor(a, b) => match (self.values[a], self.values[b]) {
+                (Value::Const(x), Value::Const(y)) => Value::Const(x ^ y),
+                (Value::Const(0), other) | (other, Value::Const(0)) => other,
+                (Value::Wire(x), Value::Wire(y)) if x == y => Value::Const(0),
+                (x, y) => self.commutative(x, y, Gate::Xor),
+            },
+            Gate::And(a, b) => match (self.values[a], self.values[b]) {
+                (Value::Const(x), Value::Const(y)) => Value::Const(x & y),
+                (Value::Const(0), _) | (_, Value::Const(0)) => Value::Const(0),
+                (Value::Const(u32::MAX), other) | (other, Value::Const(u32::MAX)) => other,
+                (Value::Wire(x), Value::Wire(y)) if x == y => Value::Wire(x),
+                (x, y) => self.commutative(x, y, Gate::And),
+            },
+            Gate::Add32(a, b) => match (self.values[a], self.values[b]) {
+                (Value::Const(x), Value::Const(y)) => Value::Const(x.wrapping_add(y)),
+                (Value::Const(0), other) | (other, Value::Const(0)) => other,
+                (x, y) => self.commutative(x, y, Gate::Add32),
+            },
+            Gate::Not(a) => {
+                if let Value::Wire(id) = self.values[a] {
+                    if let Gate::Not(inner) = self.circuit.gates[id] {
+                        return Value::Wire(inner);
+                    }
+                }
+                self.unary(a, Gate::Not, |x| !x)
+            }
+            Gate::Shl(a, 0) | Gate::Shr(a, 0) | Gate::BitSlice(a, 0, 32) => self.values[a],
+            Gate::Rotl(a, n) | Gate::Rotr(a, n) if n % 32 == 0 => self.values[a],
+            Gate::Shl(a, n) => self.unary(a, |a| Gate::Shl(a, n), |x| x << n),
+            Gate::Shr(a, n) => self.unary(a, |a| Gate::Shr(a, n), |x| x >> n),
+            Gate::Rotl(a, n) => self.unary(a, |a| Gate::Rotl(a, n), |x| x.rotate_left(n)),
+            Gate::Rotr(a, n) => self.unary(a, |a| Gate::Rotr(a, n), |x| x.rotate_right(n)),
+            Gate::BitSlice(a, lo, len) => self.unary(
+                a,
+                |a| Gate::BitSlice(a, lo, len),
+                |x| slice_bits(x, lo, len),
+            ),
+            Gate::Mask(a) => self.unary(a, Gate::Mask, |x| 0u32.wrapping_sub(x & 1)),
+        }
+    }
+
+    // Operands are ordered so that `a op b` and `b op a` share one gate.
+    fn commutative(&mut self, x: Value, y: Value, gate: impl Fn(WireId, WireId) -> Gate) -> Value {
+        let (x, y) = (self.wire(x), self.wire(y));
+        self.emit(gate(x.min(y), x.max(y)))
+    }
+}
+
+fn eliminate_dead_gates(circuit: &Circuit) -> Circuit {
+    let mut live = vec![false; circuit.gates.len()];
+    for &output in &circuit.outputs {
+        live[output] = true;
+    }
+    for id in (0..circuit.gates.len()).rev() {
+        if !live[id] {
+            continue;
+        }
+        for operand in circuit.gates[id].operands() {
+            live[operand] = true;
+        }
+    }
+
+    let mut renamed = vec![0; circuit.gates.len()];
+    let mut optimized = Circuit {
+        input_len: circuit.input_len,
+        input_pub_len: circuit.input_pub_len,
+        ..Circuit::default()
+    };
+    for (id, gate) in circuit.gates.iter().enumerate() {
+        if live[id] {
+            renamed[id] = optimized.push(gate.map_operands(|operand| renamed[operand]));
+        }
+    }
+    optimized.outputs = circuit.outputs.iter().map(|&id| renamed[id]).collect();
+    optimized
+}
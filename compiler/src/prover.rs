@@ -1,11 +1,61 @@
-// use crate::symbol::{Contract, Function};
+use crate::circuit::Circuit;
+use crate::error::{RewriteError, RewriteErrorType};
+use crate::interpreter::execute;
+use crate::lowering::{output_len, CircuitOptions};
+use crate::rewriter::RewriterResult;
+use crate::symbol::Function;
+use crate::witness::Witness;
+use zoker_prover::ikos::{IKosVariable, IKosVariable4P, IKosVariable4V};
+use zoker_prover::zkboo::{Circuit4P, Circuit4V, Proof, ProvingProof, ZkBoo};
 
-// pub fn rewrite_prover(contracts: Vec<Contract>) -> RewriterResult<Vec<Contract>> {
-//     let mut rewriter = Rewriter::new();
-//     rewriter.compile_program(ast)?;
-//     Ok(rewriter.contracts)
-// }
+fn public_words<T: IKosVariable>(input_pub: &[u32]) -> Vec<T> {
+    input_pub.iter().map(|&word| T::new_value(word)).collect()
+}
 
-// fn rewrite_contract(contract: Contract) {}
+/// Circuit proving `circuit`, to be passed to `ProvingProof::new`.
+pub fn prover_circuit(circuit: &Circuit) -> Circuit4P {
+    let circuit = circuit.clone();
+    Box::new(move |input: &[IKosVariable4P], input_pub: &[u32]| {
+        let input_pub = public_words(input_pub);
+        circuit.evaluate(input, &input_pub)
+    })
+}
 
-// fn rewrite_function(function: Function) {}
+/// Circuit verifying `circuit`, to be passed to `VerifyingProof::new`.
+pub fn verifier_circuit(circuit: &Circuit) -> Circuit4V {
+    let circuit = circuit.clone();
+    Box::new(move |input: &[IKosVariable4V], input_pub: &[u32]| {
+        let input_pub = public_words(input_pub);
+        circuit.evaluate(input, &input_pub)
+    })
+}
+
+/// Proves `function` on `witness` with `zk_boo`.
+///
+/// No proof is made of inputs a `require` or `assert` rejects: the error
+/// names the first one that failed, at its location.
+pub fn prove(
+    zk_boo: &ZkBoo,
+    function: &Function,
+    options: CircuitOptions,
+    witness: &Witness,
+) -> RewriterResult<Proof> {
+    let execution = execute(function, options, &witness.args, &witness.storage)?;
+    if let Some(requirement) = execution.failed {
+        return Err(RewriteError {
+            error: RewriteErrorType::RequirementError(format!(
+                "`{}` failed",
+                requirement.keyword()
+            )),
+            location: requirement.location,
+        });
+    }
+    let circuit = Circuit::from_function(function, options)?;
+    let proof = ProvingProof::new(
+        witness.input.clone(),
+        witness.input_pub.clone(),
+        output_len(function, options),
+        prover_circuit(&circuit),
+    );
+    Ok(zk_boo.prove(proof)?)
+}
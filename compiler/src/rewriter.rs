@@ -1,9 +1,17 @@
-use crate::error::{RewriteError, RewriteErrorType};
+use crate::encoding::{is_fixed_size, value_bits};
+use crate::error::{type_error, RewriteError, RewriteErrorType};
+use crate::flow::check_flow;
+use crate::inliner::inline_calls;
+use crate::interpreter::interpret;
+use crate::lowering::CircuitOptions;
+use crate::storage::{check_mutability, resolve_storage};
 use crate::symbol::{
-    specifier_to_location, token_to_type, Contract, Function, Operation, OperationType, Symbol,
-    SymbolLocation, SymbolType,
+    specifier_to_location, token_to_type, Contract, Event, Function, Mutability, Operation,
+    OperationType, Requirement, StructField, StructType, Symbol, SymbolLocation, SymbolType,
+    Visibility,
 };
 use indexmap::map::IndexMap;
+use num_bigint::BigUint;
 use zoker_parser::ast;
 use zoker_parser::ast::{ExpressionType, Operator, StatementType};
 use zoker_parser::location::Location;
@@ -23,9 +31,118 @@ pub type RewriterResult<T> = Result<T, RewriteError>;
 pub fn rewrite_program(ast: &ast::Program) -> RewriterResult<Vec<Contract>> {
     let mut rewriter = Rewriter::new();
     rewriter.compile_program(ast)?;
+    for contract in rewriter.contracts.iter_mut() {
+        inline_calls(contract)?;
+        for function in contract.functions.iter_mut() {
+            resolve_storage(function)?;
+            check_mutability(function)?;
+            // Only proven functions output anything.
+            if function.is_proven {
+                check_flow(function)?;
+            }
+        }
+        // The others live on in the functions they were inlined into.
+        contract.functions.retain(|function| function.is_proven);
+    }
     Ok(rewriter.contracts)
 }
 
+/// Visibility, state mutability and whether function `name` is proven,
+/// from its specifiers. Functions without a visibility are proven, as
+/// are those marked `proven`; internal ones cannot be.
+fn compile_specifiers(
+    name: &str,
+    specifiers: &[ast::FunctionSpecifier],
+    location: Location,
+) -> RewriterResult<(Visibility, Mutability, bool)> {
+    let mut visibility = None;
+    let mut mutability = None;
+    let mut is_proven = false;
+    for specifier in specifiers {
+        match specifier {
+            ast::FunctionSpecifier::Public
+            | ast::FunctionSpecifier::External
+            | ast::FunctionSpecifier::Internal => {
+                if visibility.is_some() {
+                    return type_error(
+                        &format!("`{}` has more than one visibility", name),
+                        location,
+                    );
+                }
+                visibility = Some(match specifier {
+                    ast::FunctionSpecifier::Public => Visibility::Public,
+                    ast::FunctionSpecifier::External => Visibility::External,
+                    _ => Visibility::Internal,
+                });
+            }
+            ast::FunctionSpecifier::View | ast::FunctionSpecifier::Pure => {
+                if mutability.is_some() {
+                    return type_error(
+                        &format!("`{}` has more than one state mutability", name),
+                        location,
+                    );
+                }
+                mutability = Some(match specifier {
+                    ast::FunctionSpecifier::View => Mutability::View,
+                    _ => Mutability::Pure,
+                });
+            }
+            ast::FunctionSpecifier::Proven => {
+                if is_proven {
+                    return type_error(&format!("`{}` is marked `proven` twice", name), location);
+                }
+                is_proven = true;
+            }
+        }
+    }
+    if visibility == Some(Visibility::Internal) && is_proven {
+        return type_error(
+            &format!("internal function `{}` cannot be `proven`", name),
+            location,
+        );
+    }
+    Ok((
+        visibility.unwrap_or(Visibility::Public),
+        mutability.unwrap_or(Mutability::NonPayable),
+        is_proven || visibility.is_none(),
+    ))
+}
+
+// Whether `operation` only depends on constants.
+fn is_constant(operation: &Operation) -> bool {
+    match &operation.operation {
+        OperationType::Symbol { .. }
+        | OperationType::Call { .. }
+        | OperationType::Assign { .. } => false,
+        _ => operation.children().into_iter().all(is_constant),
+    }
+}
+
+// Names of the structs the fields of struct `member` use.
+fn used_structs(member: &ast::Statement) -> Vec<String> {
+    let mut names = vec![];
+    if let StatementType::StructStatement { fields, .. } = &member.node {
+        for field in fields {
+            if let StatementType::InitializerStatement { variable_type, .. } = &field.node {
+                type_structs(variable_type, &mut names);
+            }
+        }
+    }
+    names
+}
+
+fn type_structs(typ: &ast::Type, names: &mut Vec<String>) {
+    match typ {
+        ast::Type::Array { element, .. } => type_structs(element, names),
+        ast::Type::Mapping { key, value } => {
+            type_structs(key, names);
+            type_structs(value, names);
+        }
+        ast::Type::Struct { name } => names.push(name.clone()),
+        _ => {}
+    }
+}
+
 #[derive(Debug, Clone)]
 struct RewriterContext {
     public_map: IndexMap<String, Symbol>,
@@ -99,23 +216,32 @@ impl Rewriter {
             StatementType::FunctionStatement {
                 function_name,
                 parameters,
+                specifiers,
                 statement,
                 returns,
             } => {
                 self.context = RewriterContext::new();
                 let name = function_name.node.identifier_name().unwrap();
-                let params = self.compile_param_symbols(parameters)?;
-                let ret = if let Some(return_type) = returns {
-                    self.compile_param_symbols(return_type)?
-                } else {
-                    vec![]
+                let (visibility, mutability, is_proven) =
+                    compile_specifiers(&name, specifiers, function_name.location)?;
+                let (params, param_locations) = self.compile_param_symbols(parameters)?;
+                let (ret, ret_locations) = match returns {
+                    Some(return_type) => self.compile_param_symbols(return_type)?,
+                    None => (vec![], vec![]),
                 };
-                let function = Function::new(name, params, ret);
-                self.current_contract().add_function(function);
-
-                self.enter_scope();
-                self.compile_statement(statement)?;
-                self.add_operation_all();
+                // Circuits output a single return value.
+                if ret.len() > 1 {
+                    return type_error(
+                        &format!("`{}` returns more than one value", name),
+                        ret_locations[1],
+                    );
+                }
+                let mut function = Function::new(name, params, ret);
+                function.param_locations = param_locations;
+                function.visibility = visibility;
+                function.mutability = mutability;
+                function.is_proven = is_proven;
+                self.compile_function(function, statement)?;
             }
             StatementType::ContractStatement {
                 contract_name,
@@ -124,7 +250,9 @@ impl Rewriter {
                 let name = contract_name.node.identifier_name().unwrap();
                 let contract = Contract::new(name);
                 self.add_contract(contract);
-                self.compile_statement(members)?;
+                if let StatementType::MemberStatement { statements } = &members.node {
+                    self.compile_members(statements)?;
+                }
             }
             StatementType::InitializerStatement {
                 variable_type,
@@ -133,16 +261,31 @@ impl Rewriter {
                 variable,
                 default,
             } => {
-                let typ = token_to_type(variable_type);
-                let loc = if let Some(location) = data_location {
-                    specifier_to_location(location)
-                } else {
-                    SymbolLocation::Unknown
+                let typ = self.resolve_type(variable_type, statement.location)?;
+                if typ.as_mapping().is_some() {
+                    return Err(RewriteError {
+                        error: RewriteErrorType::TypeError(
+                            "mappings can only be contract state".to_string(),
+                        ),
+                        location: statement.location,
+                    });
+                }
+                let loc = match data_location {
+                    Some(ast::Specifier::Storage) => {
+                        return Err(RewriteError {
+                            error: RewriteErrorType::TypeError(
+                                "only contract state lives in storage".to_string(),
+                            ),
+                            location: statement.location,
+                        });
+                    }
+                    Some(location) => specifier_to_location(location),
+                    None => SymbolLocation::Unknown,
                 };
                 if let Some(identifier) = variable {
                     let name = identifier.node.identifier_name().unwrap();
                     self.init_variable(name.clone(), typ, loc, *is_private);
-                    let symbol = self.get_variable(&name);
+                    let symbol = self.get_variable(&name, identifier.location)?;
                     let left = Operation::new_symbol(symbol);
 
                     let operation = if let Some(var) = default {
@@ -155,9 +298,11 @@ impl Rewriter {
                     } else {
                         left
                     };
-                    self.push_operation(operation);
+                    self.push_operation(operation.at(statement.location));
                 } else {
-                    self.push_operation(Operation::new_symbol(Symbol::new_type_symbol(typ)))
+                    let mut symbol = Symbol::new_type_symbol(typ);
+                    symbol.is_private = *is_private;
+                    self.push_operation(Operation::new_symbol(symbol).at(statement.location))
                 }
             }
             StatementType::CompoundStatement {
@@ -175,14 +320,72 @@ impl Rewriter {
             StatementType::MemberStatement { statements } => {
                 self.compile_statements(statements)?;
             }
+            StatementType::StructStatement { .. }
+            | StatementType::EventStatement { .. }
+            | StatementType::ConstructorStatement { .. } => {
+                return Err(RewriteError {
+                    error: RewriteErrorType::Unreachable,
+                    location: statement.location,
+                });
+            }
             StatementType::ReturnStatement { ret } => {
                 if let Some(returns) = ret {
+                    if let ExpressionType::Tuple { .. } = returns.node {
+                        return type_error("`return` takes one value", statement.location);
+                    }
                     self.compile_expression(returns)?;
                     let ret = self.pop_operation();
                     let operation = Operation::new(OperationType::Return { ret: Box::new(ret) });
-                    self.push_operation(operation);
+                    self.push_operation(operation.at(statement.location));
                 }
             }
+            StatementType::RequireStatement {
+                condition,
+                is_assert,
+            } => {
+                self.compile_expression(condition)?;
+                let cond = self.pop_operation();
+                let requirement = Requirement {
+                    is_assert: *is_assert,
+                    location: statement.location,
+                };
+                let operation = Operation::new(OperationType::Require {
+                    cond: Box::new(cond),
+                    requirement,
+                });
+                self.push_operation(operation.at(statement.location));
+            }
+            StatementType::EmitStatement {
+                event_name,
+                arguments,
+            } => {
+                let name = event_name.node.identifier_name().unwrap();
+                let contract = self.contracts.last().unwrap();
+                let event = match contract.events.iter().find(|event| event.name == name) {
+                    Some(event) => event.clone(),
+                    None => {
+                        return type_error(&format!("unknown event `{}`", name), statement.location)
+                    }
+                };
+                let args = self.compile_params(arguments)?;
+                if args.len() != event.params.len() {
+                    return type_error(
+                        &format!(
+                            "`{}` takes {} arguments, found {}",
+                            name,
+                            event.params.len(),
+                            args.len()
+                        ),
+                        statement.location,
+                    );
+                }
+                let operation = Operation::new(OperationType::Emit {
+                    event,
+                    args,
+                    emit: 0,
+                });
+                self.push_operation(operation.at(statement.location));
+            }
             StatementType::Expression { expression } => {
                 self.compile_expression(expression)?;
             }
@@ -190,6 +393,349 @@ impl Rewriter {
         Ok(())
     }
 
+    // Structs are compiled first, each after the structs its fields use,
+    // then events and state variables, so that members may use those
+    // declared after them.
+    fn compile_members(&mut self, members: &[ast::Statement]) -> RewriterResult<()> {
+        let mut structs = members
+            .iter()
+            .filter(|member| matches!(member.node, StatementType::StructStatement { .. }))
+            .collect::<Vec<_>>();
+        while !structs.is_empty() {
+            let known = &self.contracts.last().unwrap().structs;
+            // Structs using unknown ones are compiled anyway, to report them.
+            let next = structs
+                .iter()
+                .position(|member| {
+                    used_structs(member)
+                        .iter()
+                        .all(|name| known.iter().any(|structure| structure.name == *name))
+                })
+                .unwrap_or(0);
+            self.compile_member(structs.remove(next))?;
+        }
+        let is_declaration = |member: &&ast::Statement| {
+            matches!(
+                member.node,
+                StatementType::EventStatement { .. } | StatementType::InitializerStatement { .. }
+            )
+        };
+        for member in members.iter().filter(is_declaration) {
+            self.compile_member(member)?;
+        }
+        let is_body = |member: &&ast::Statement| {
+            !matches!(
+                member.node,
+                StatementType::StructStatement { .. }
+                    | StatementType::EventStatement { .. }
+                    | StatementType::InitializerStatement { .. }
+            )
+        };
+        for member in members.iter().filter(is_body) {
+            self.compile_member(member)?;
+        }
+        Ok(())
+    }
+
+    // Functions, the constructor, structs, events and state variables of
+    // a contract.
+    fn compile_member(&mut self, member: &ast::Statement) -> RewriterResult<()> {
+        let (variable_type, is_private, data_location, variable, default) = match &member.node {
+            StatementType::StructStatement {
+                struct_name,
+                fields,
+            } => return self.compile_struct(struct_name, fields),
+            StatementType::EventStatement {
+                event_name,
+                parameters,
+            } => return self.compile_event(event_name, parameters),
+            StatementType::ConstructorStatement {
+                parameters,
+                statement,
+            } => return self.compile_constructor(parameters, statement, member.location),
+            StatementType::InitializerStatement {
+                variable_type,
+                is_private,
+                data_location,
+                variable: Some(variable),
+                default,
+            } => (
+                variable_type,
+                *is_private,
+                data_location,
+                variable,
+                default.as_deref(),
+            ),
+            StatementType::InitializerStatement { .. } => {
+                return Err(RewriteError {
+                    error: RewriteErrorType::UnsupportedError,
+                    location: member.location,
+                })
+            }
+            _ => return self.compile_statement(member),
+        };
+        if let Some(ast::Specifier::Memory) = data_location {
+            return type_error("state variables live in storage", member.location);
+        }
+        let typ = self.resolve_type(variable_type, member.location)?;
+        match typ.as_mapping() {
+            Some(_) if is_private => {
+                return Err(RewriteError {
+                    error: RewriteErrorType::UnsupportedError,
+                    location: member.location,
+                })
+            }
+            // Entries are stored and proven word by word.
+            Some((key, value)) if value_bits(key).is_none() || value_bits(value).is_none() => {
+                return type_error(
+                    &format!("mapping keys and values must be value types, found {}", typ),
+                    member.location,
+                )
+            }
+            Some(_) => {}
+            // Other state is read from and written to words whole, or
+            // committed to if private.
+            None if !is_fixed_size(&typ) => {
+                return type_error(
+                    &format!("state variables must have a fixed size, found {}", typ),
+                    member.location,
+                )
+            }
+            None if !is_private && typ.public_word_count(false) < typ.word_count() => {
+                return type_error(&format!("{} has private fields", typ), member.location)
+            }
+            None => {}
+        }
+        let name = variable.node.identifier_name().unwrap();
+        let initial = match default {
+            Some(_) if typ.as_mapping().is_some() => {
+                return type_error(
+                    &format!("mapping `{}` cannot have a default", name),
+                    member.location,
+                )
+            }
+            Some(default) => self.compile_default(&name, &typ, default)?,
+            None => BigUint::from(0u32),
+        };
+        let contract = self.current_contract();
+        contract.initial.push(initial);
+        let symbol = Symbol::new(
+            name,
+            contract.storage.len() as u32,
+            typ,
+            SymbolLocation::Storage,
+            is_private,
+        );
+        contract.storage.push(symbol);
+        Ok(())
+    }
+
+    // Value of the default of state variable `name`, which is worked out
+    // now since it may only use constants.
+    fn compile_default(
+        &mut self,
+        name: &str,
+        typ: &SymbolType,
+        default: &ast::Expression,
+    ) -> RewriterResult<BigUint> {
+        if value_bits(typ).is_none() {
+            return type_error(
+                &format!("only value types have defaults, found {}", typ),
+                default.location,
+            );
+        }
+        self.context = RewriterContext::new();
+        self.enter_scope();
+        self.compile_expression(default)?;
+        let value = self.pop_operation();
+        self.exit_scope();
+        if !is_constant(&value) {
+            return type_error(
+                &format!("default of `{}` must be a constant", name),
+                default.location,
+            );
+        }
+        let ret = Symbol::new(
+            name.to_string(),
+            0,
+            typ.clone(),
+            SymbolLocation::Memory,
+            false,
+        );
+        let mut function = Function::new(name.to_string(), vec![], vec![ret]);
+        function.add_operations(vec![Operation::new(OperationType::Return {
+            ret: Box::new(value),
+        })]);
+        let execution = interpret(&function, CircuitOptions::default(), &[])?;
+        Ok(execution.outputs[0].value.clone())
+    }
+
+    fn compile_constructor(
+        &mut self,
+        parameters: &ast::Expression,
+        statement: &ast::Statement,
+        location: Location,
+    ) -> RewriterResult<()> {
+        if self.contracts.last().unwrap().constructor.is_some() {
+            return type_error("the constructor is declared twice", location);
+        }
+        self.context = RewriterContext::new();
+        let (params, param_locations) = self.compile_param_symbols(parameters)?;
+        // Deployment arguments are public, and passed as Solidity values.
+        for param in &params {
+            if param.is_private {
+                return type_error("constructor parameters are public", location);
+            }
+            if value_bits(&param.symbol_type).is_none() {
+                return type_error(
+                    &format!(
+                        "constructor parameters must be value types, found {}",
+                        param.symbol_type
+                    ),
+                    location,
+                );
+            }
+        }
+        let mut constructor = Function::new("constructor".to_string(), params, vec![]);
+        constructor.param_locations = param_locations;
+        self.compile_function(constructor, statement)?;
+        let contract = self.current_contract();
+        contract.constructor = contract.functions.pop();
+        Ok(())
+    }
+
+    fn compile_struct(
+        &mut self,
+        struct_name: &ast::Expression,
+        fields: &[ast::Statement],
+    ) -> RewriterResult<()> {
+        let name = struct_name.node.identifier_name().unwrap();
+        let contract = self.contracts.last().unwrap();
+        if contract.structs.iter().any(|s| s.name == name) {
+            return type_error(
+                &format!("struct `{}` is declared twice", name),
+                struct_name.location,
+            );
+        }
+        let mut structure = StructType {
+            name,
+            fields: vec![],
+        };
+        for field in fields {
+            let (variable_type, is_private, variable) = match &field.node {
+                StatementType::InitializerStatement {
+                    variable_type,
+                    is_private,
+                    variable: Some(variable),
+                    default: None,
+                    ..
+                } => (variable_type, *is_private, variable),
+                _ => return type_error("struct fields are a type and a name", field.location),
+            };
+            let symbol_type = self.resolve_type(variable_type, field.location)?;
+            // Fields are laid out word by word.
+            if !is_fixed_size(&symbol_type) {
+                return type_error(
+                    &format!(
+                        "struct fields must have a fixed size, found {}",
+                        symbol_type
+                    ),
+                    field.location,
+                );
+            }
+            let field_name = variable.node.identifier_name().unwrap();
+            if structure.field(&field_name).is_some() {
+                return type_error(
+                    &format!("field `{}` is declared twice", field_name),
+                    field.location,
+                );
+            }
+            structure.fields.push(StructField {
+                name: field_name,
+                symbol_type,
+                is_private,
+            });
+        }
+        if structure.fields.is_empty() {
+            return type_error(
+                &format!("struct `{}` has no fields", structure.name),
+                struct_name.location,
+            );
+        }
+        self.current_contract().structs.push(structure);
+        Ok(())
+    }
+
+    fn compile_event(
+        &mut self,
+        event_name: &ast::Expression,
+        parameters: &ast::Expression,
+    ) -> RewriterResult<()> {
+        let name = event_name.node.identifier_name().unwrap();
+        let contract = self.contracts.last().unwrap();
+        if contract.events.iter().any(|event| event.name == name) {
+            return type_error(
+                &format!("event `{}` is declared twice", name),
+                event_name.location,
+            );
+        }
+        let parameters = match &parameters.node {
+            ExpressionType::Parameters { parameters } => parameters,
+            _ => {
+                return Err(RewriteError {
+                    error: RewriteErrorType::Unreachable,
+                    location: parameters.location,
+                })
+            }
+        };
+        let mut event = Event {
+            name,
+            params: vec![],
+        };
+        for param in parameters {
+            let (variable_type, is_private, variable) = match &param.node {
+                StatementType::InitializerStatement {
+                    variable_type,
+                    is_private,
+                    data_location: None,
+                    variable: Some(variable),
+                    default: None,
+                } => (variable_type, *is_private, variable),
+                _ => return type_error("event parameters are a type and a name", param.location),
+            };
+            // Emitted values are output, so anyone can see them.
+            if is_private {
+                return type_error("event parameters are public", param.location);
+            }
+            let symbol_type = self.resolve_type(variable_type, param.location)?;
+            if value_bits(&symbol_type).is_none() {
+                return type_error(
+                    &format!(
+                        "event parameters must be value types, found {}",
+                        symbol_type
+                    ),
+                    param.location,
+                );
+            }
+            let param_name = variable.node.identifier_name().unwrap();
+            if event.params.iter().any(|param| param.name == param_name) {
+                return type_error(
+                    &format!("parameter `{}` is declared twice", param_name),
+                    param.location,
+                );
+            }
+            event.params.push(Symbol::new(
+                param_name,
+                event.params.len() as u32,
+                symbol_type,
+                SymbolLocation::Unknown,
+                false,
+            ));
+        }
+        self.current_contract().events.push(event);
+        Ok(())
+    }
+
     fn compile_expression(&mut self, expression: &ast::Expression) -> RewriterResult<()> {
         match &expression.node {
             ExpressionType::AssignExpression {
@@ -210,12 +756,11 @@ impl Rewriter {
                     _ => {
                         return Err(RewriteError {
                             error: RewriteErrorType::UnsupportedError,
-                            location: Location::new(0, 0),
+                            location: expression.location,
                         })
                     }
                 };
-                let operation = Operation::new(op);
-                self.push_operation(operation);
+                self.push_operation(Operation::new(op).at(expression.location));
             }
             ExpressionType::BinaryExpression {
                 left,
@@ -235,15 +780,58 @@ impl Rewriter {
                         left: Box::new(left),
                         right: Box::new(right),
                     },
+                    Operator::Mul => OperationType::Mul {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Div => OperationType::Div {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Mod => OperationType::Mod {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Lt => OperationType::Lt {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Le => OperationType::Le {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Gt => OperationType::Gt {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Ge => OperationType::Ge {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::Eq => OperationType::Eq {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::NotEq => OperationType::NotEq {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::LShift => OperationType::LShift {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    Operator::RShift => OperationType::RShift {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
                     _ => {
                         return Err(RewriteError {
                             error: RewriteErrorType::UnsupportedError,
-                            location: Location::new(0, 0),
+                            location: expression.location,
                         })
                     }
                 };
-                let operation = Operation::new(op);
-                self.push_operation(operation);
+                self.push_operation(Operation::new(op).at(expression.location));
             }
             ExpressionType::FunctionCallExpression {
                 function_name,
@@ -251,8 +839,63 @@ impl Rewriter {
             } => {
                 let name = function_name.node.identifier_name().unwrap();
                 let args = self.compile_params(arguments)?;
-                let operation = Operation::new_call(name, args);
-                self.push_operation(operation);
+                let operation = match name.as_str() {
+                    "sha256" => Operation::new(OperationType::Sha256 { args }),
+                    "reveal" if args.len() == 1 => Operation::new(OperationType::Reveal {
+                        value: Box::new(args.into_iter().next().unwrap()),
+                    }),
+                    "reveal" => {
+                        return Err(RewriteError {
+                            error: RewriteErrorType::TypeError(
+                                "`reveal` takes one value".to_string(),
+                            ),
+                            location: expression.location,
+                        })
+                    }
+                    _ => Operation::new_call(name, args),
+                };
+                self.push_operation(operation.at(expression.location));
+            }
+            ExpressionType::IndexExpression { array, index } => {
+                self.compile_expression(array)?;
+                let array = self.pop_operation();
+                self.compile_expression(index)?;
+                let index = self.pop_operation();
+                let operation = Operation::new(OperationType::Index {
+                    array: Box::new(array),
+                    index: Box::new(index),
+                });
+                self.push_operation(operation.at(expression.location));
+            }
+            ExpressionType::MemberExpression {
+                expression: value,
+                member,
+            } => {
+                self.compile_expression(value)?;
+                let value = self.pop_operation();
+                let field = member.node.identifier_name().unwrap();
+                // Fields of values only known while lowering are checked
+                // there.
+                if let Some((symbol_type, _)) = value.variable_type() {
+                    let known = match symbol_type.as_struct() {
+                        Some(structure) => structure.field(&field).is_some(),
+                        None => false,
+                    };
+                    if !known {
+                        return Err(RewriteError {
+                            error: RewriteErrorType::TypeError(format!(
+                                "{} has no field `{}`",
+                                symbol_type, field
+                            )),
+                            location: member.location,
+                        });
+                    }
+                }
+                let operation = Operation::new(OperationType::Member {
+                    value: Box::new(value),
+                    field,
+                });
+                self.push_operation(operation.at(expression.location));
             }
             ExpressionType::IfExpression {
                 condition,
@@ -268,15 +911,16 @@ impl Rewriter {
                     cond: Box::new(cond.clone()),
                     stmts,
                 });
-                self.push_operation(operation);
+                self.push_operation(operation.at(expression.location));
                 if let Some(else_stmt) = else_statement {
                     self.enter_scope();
                     self.compile_statement(else_stmt)?;
                     let stmts = self.exit_scope();
-                    self.push_operation(Operation::new(OperationType::Else {
+                    let operation = Operation::new(OperationType::Else {
                         cond: Box::new(cond),
                         stmts,
-                    }));
+                    });
+                    self.push_operation(operation.at(else_stmt.location));
                 };
             }
             ExpressionType::ForEachExpression {
@@ -285,12 +929,24 @@ impl Rewriter {
                 statement,
                 else_statement,
             } => {
-                let iter_name = iterator.node.identifier_name().unwrap();
-                self.iter_variable(iter_name.as_str());
-                let symbol = self.get_variable(iter_name.as_str());
-
                 self.compile_expression(vector)?;
                 let vector_operation = self.pop_operation();
+                // The iterator takes the type and privacy of the elements.
+                let (element, is_private) = match vector_operation.variable_type() {
+                    Some((SymbolType::Array(element, _), is_private)) => (*element, is_private),
+                    _ => {
+                        return Err(RewriteError {
+                            error: RewriteErrorType::TypeError(
+                                "`for` iterates over an array".to_string(),
+                            ),
+                            location: vector.location,
+                        })
+                    }
+                };
+                let iter_name = iterator.node.identifier_name().unwrap();
+                self.iter_variable(iter_name.as_str(), element, is_private);
+                let symbol = self.get_variable(iter_name.as_str(), iterator.location)?;
+
                 self.enter_scope();
                 self.compile_statement(statement)?;
                 let stmts = self.exit_scope();
@@ -299,7 +955,7 @@ impl Rewriter {
                     vector: Box::new(vector_operation),
                     stmts,
                 });
-                self.push_operation(operation);
+                self.push_operation(operation.at(expression.location));
                 if let Some(else_stmt) = else_statement {
                     return Err(RewriteError {
                         error: RewriteErrorType::UnsupportedError,
@@ -308,42 +964,57 @@ impl Rewriter {
                 }
             }
             ExpressionType::Number { value } => {
-                self.push_operation(Operation::new(OperationType::Constant {
+                let operation = Operation::new(OperationType::Constant {
                     value: value.clone(),
-                }));
+                });
+                self.push_operation(operation.at(expression.location));
             }
             ExpressionType::Identifier { value } => {
-                let symbol = self.get_variable(value);
-                self.push_operation(Operation::new_symbol(symbol));
+                let symbol = self.get_variable(value, expression.location)?;
+                self.push_operation(Operation::new_symbol(symbol).at(expression.location));
             }
             ExpressionType::Parameters { .. } => {
                 return Err(RewriteError {
                     error: RewriteErrorType::Unreachable,
-                    location: Location::new(0, 0),
+                    location: expression.location,
                 });
             }
             ExpressionType::Arguments { .. } => {
                 return Err(RewriteError {
                     error: RewriteErrorType::Unreachable,
-                    location: Location::new(0, 0),
+                    location: expression.location,
                 });
             }
-            ExpressionType::UnaryExpression { .. } => {
-                return Err(RewriteError {
-                    error: RewriteErrorType::UnsupportedError,
-                    location: Location::new(0, 0),
-                });
+            ExpressionType::UnaryExpression {
+                operator,
+                expression: operand,
+            } => {
+                self.compile_expression(operand)?;
+                let operand = self.pop_operation();
+                let operation = match operator {
+                    Operator::Plus => operand,
+                    Operator::Minus => Operation::new(OperationType::Neg {
+                        operand: Box::new(operand),
+                    }),
+                    _ => {
+                        return Err(RewriteError {
+                            error: RewriteErrorType::UnsupportedError,
+                            location: expression.location,
+                        })
+                    }
+                };
+                self.push_operation(operation.at(expression.location));
             }
             ExpressionType::Tuple { .. } => {
                 return Err(RewriteError {
                     error: RewriteErrorType::UnsupportedError,
-                    location: Location::new(0, 0),
+                    location: expression.location,
                 });
             }
             ExpressionType::TernaryExpression { .. } => {
                 return Err(RewriteError {
                     error: RewriteErrorType::UnsupportedError,
-                    location: Location::new(0, 0),
+                    location: expression.location,
                 });
             }
         }
@@ -368,17 +1039,38 @@ impl Rewriter {
             }
             _ => Err(RewriteError {
                 error: RewriteErrorType::Unreachable,
-                location: Location::new(0, 0),
+                location: expression.location,
             }),
         }
     }
 
-    fn compile_param_symbols(&mut self, params: &ast::Expression) -> RewriterResult<Vec<Symbol>> {
+    // Adds `function` to the contract with `statement` as its body.
+    fn compile_function(
+        &mut self,
+        function: Function,
+        statement: &ast::Statement,
+    ) -> RewriterResult<()> {
+        self.current_contract().add_function(function);
+        self.enter_scope();
+        self.compile_statement(statement)?;
+        self.add_operation_all();
+        let (private_num, public_num) = (self.context.private_num, self.context.public_num);
+        let function = self.current_contract().functions.last_mut().unwrap();
+        function.private_num = private_num;
+        function.public_num = public_num;
+        Ok(())
+    }
+
+    // Parameter symbols with where each is declared.
+    fn compile_param_symbols(
+        &mut self,
+        params: &ast::Expression,
+    ) -> RewriterResult<(Vec<Symbol>, Vec<Location>)> {
         Ok(self
             .compile_params(params)?
             .iter()
-            .map(|operation| operation.as_symbol().unwrap())
-            .collect::<Vec<Symbol>>())
+            .map(|operation| (operation.as_symbol().unwrap(), operation.location))
+            .unzip())
     }
 
     fn add_contract(&mut self, contract: Contract) {
@@ -435,22 +1127,39 @@ impl Rewriter {
         self.context.add_variable(name, symbol, is_private);
     }
 
-    fn iter_variable(&mut self, name: &str) {
+    fn iter_variable(&mut self, name: &str, typ: SymbolType, is_private: bool) {
         let symbol = Symbol::new(
             name.to_string(),
-            self.context.variable_num(false),
-            SymbolType::Uint256,
+            self.context.variable_num(is_private),
+            typ,
             SymbolLocation::Memory,
-            false,
+            is_private,
         );
-        self.context.add_variable(name.to_string(), symbol, false);
+        self.context
+            .add_variable(name.to_string(), symbol, is_private);
+    }
+
+    fn resolve_type(&self, typ: &ast::Type, location: Location) -> RewriterResult<SymbolType> {
+        let contract = self.contracts.last().unwrap();
+        token_to_type(typ, &contract.structs).map_err(|name| RewriteError {
+            error: RewriteErrorType::TypeError(format!("unknown struct `{}`", name)),
+            location,
+        })
     }
 
-    fn get_variable(&self, name: &str) -> Symbol {
+    // Local variables shadow state variables.
+    fn get_variable(&self, name: &str, location: Location) -> RewriterResult<Symbol> {
         if let Some(symbol) = self.context.private_map.get(name) {
-            symbol.clone()
+            Ok(symbol.clone())
+        } else if let Some(symbol) = self.context.public_map.get(name) {
+            Ok(symbol.clone())
         } else {
-            self.context.public_map.get(name).unwrap().clone()
+            let contract = self.contracts.last().unwrap();
+            let symbol = contract.storage.iter().find(|symbol| symbol.name == name);
+            symbol.cloned().ok_or_else(|| RewriteError {
+                error: RewriteErrorType::TypeError(format!("unknown variable `{}`", name)),
+                location,
+            })
         }
     }
 }
@@ -0,0 +1,138 @@
+use crate::circuit::{Circuit, Gate};
+use crate::lowering::CircuitOptions;
+use crate::rewriter::RewriterResult;
+use crate::symbol::Function;
+use std::fmt;
+
+/// Bytes of the random tape seed of every `IKosView`.
+const RAND_TAPE_SEED_LEN: usize = 16;
+/// Random words produced by one `generate_randomness` call.
+const RANDOMNESS_WORDS: usize = 8;
+
+// Rough gas prices of the `zkboo.sol` primitives, measured on small
+// circuits. They only give the order of magnitude of a verification.
+const GAS_BASE: u64 = 60_000;
+const GAS_CALLDATA_BYTE: u64 = 16;
+const GAS_LINEAR_GATE: u64 = 300;
+const GAS_AND_GATE: u64 = 1_500;
+const GAS_ADD_GATE: u64 = 12_000;
+const GAS_RANDOMNESS: u64 = 500;
+const GAS_SHA256_WORD: u64 = 12;
+
+/// ZKBoo parameters a proof is made with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProofParams {
+    pub num_of_round: usize,
+    pub commit_length: usize,
+}
+
+impl Default for ProofParams {
+    /// The parameters `zkboo.sol` is compiled with.
+    fn default() -> Self {
+        ProofParams {
+            num_of_round: 2,
+            commit_length: 32,
+        }
+    }
+}
+
+/// What proving and verifying a circuit costs.
+///
+/// Counts are per round unless noted. Sizes assume the largest response,
+/// which includes the view holding the input shares.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CircuitStats {
+    pub linear_gates: usize,
+    pub and_gates: usize,
+    pub add_gates: usize,
+    pub input_words: usize,
+    pub input_pub_words: usize,
+    pub output_words: usize,
+    /// Random words the parties sharing the input draw from their tape.
+    pub random_words_per_party: usize,
+    /// Bytes of the largest `IKosView`.
+    pub view_bytes: usize,
+    /// Bytes sent to the verifier over all rounds.
+    pub proof_bytes: usize,
+    /// Estimated gas of `ZKBoo_verify`.
+    pub verify_gas: u64,
+}
+
+impl CircuitStats {
+    pub fn non_linear_gates(&self) -> usize {
+        self.and_gates + self.add_gates
+    }
+}
+
+/// Compiles `function` and reports what proving it costs.
+pub fn function_stats(
+    function: &Function,
+    options: CircuitOptions,
+    params: &ProofParams,
+) -> RewriterResult<CircuitStats> {
+    let circuit = Circuit::from_function(function, options)?;
+    Ok(circuit_stats(&circuit, params))
+}
+
+pub fn circuit_stats(circuit: &Circuit, params: &ProofParams) -> CircuitStats {
+    let mut stats = CircuitStats {
+        input_words: circuit.input_len,
+        input_pub_words: circuit.input_pub_len,
+        output_words: circuit.outputs.len(),
+        ..CircuitStats::default()
+    };
+    for gate in &circuit.gates {
+        match gate {
+            Gate::Const(_) | Gate::Input { .. } => {}
+            Gate::And(..) => stats.and_gates += 1,
+            Gate::Add32(..) => stats.add_gates += 1,
+            _ => stats.linear_gates += 1,
+        }
+    }
+
+    let non_linear = stats.non_linear_gates();
+    stats.random_words_per_party = stats.input_words + non_linear;
+    let out_data = non_linear + stats.output_words;
+    stats.view_bytes = RAND_TAPE_SEED_LEN + 4 * (stats.input_words + out_data);
+
+    // Two views a round, one of them possibly without input shares, the
+    // commitment of the hidden view, the challenge and the output.
+    let response = stats.view_bytes + RAND_TAPE_SEED_LEN + 4 * out_data;
+    stats.proof_bytes =
+        params.num_of_round * (response + params.commit_length) + 32 + 4 * stats.output_words;
+
+    let randomness = stats.random_words_per_party.div_ceil(RANDOMNESS_WORDS);
+    let views_hashed = 3 * stats.view_bytes / 32;
+    let round_gas = 2 * randomness as u64 * GAS_RANDOMNESS
+        + stats.linear_gates as u64 * GAS_LINEAR_GATE
+        + stats.and_gates as u64 * GAS_AND_GATE
+        + stats.add_gates as u64 * GAS_ADD_GATE
+        + views_hashed as u64 * GAS_SHA256_WORD;
+    stats.verify_gas = GAS_BASE
+        + (stats.proof_bytes + 4 * stats.input_pub_words) as u64 * GAS_CALLDATA_BYTE
+        + params.num_of_round as u64 * round_gas;
+    stats
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "inputs: {} private, {} public words",
+            self.input_words, self.input_pub_words
+        )?;
+        writeln!(f, "outputs: {} words", self.output_words)?;
+        writeln!(f, "linear gates: {}", self.linear_gates)?;
+        writeln!(
+            f,
+            "non-linear gates: {} ({} and, {} add)",
+            self.non_linear_gates(),
+            self.and_gates,
+            self.add_gates
+        )?;
+        writeln!(f, "random words per party: {}", self.random_words_per_party)?;
+        writeln!(f, "view size: {} bytes", self.view_bytes)?;
+        writeln!(f, "proof size: {} bytes", self.proof_bytes)?;
+        write!(f, "estimated verify gas: {}", self.verify_gas)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::error::type_error;
+use crate::rewriter::RewriterResult;
+use crate::symbol::{
+    Event, Function, Mutability, Operation, OperationType, StateVariable, StorageAccess, Symbol,
+    SymbolLocation,
+};
+use zoker_parser::location::Location;
+
+/// Turns the mapping reads and writes of `function` into `Load`s and
+/// `Store`s, numbered in the order they run, and lists them in
+/// `function.storage`.
+///
+/// The keys of the entries become public, which `check_flow` checks
+/// along with the other outputs. Loops cannot access mappings, since
+/// every access has its own input and output words.
+///
+/// Other state variables are replaced by variables of the function of the
+/// same privacy, listed in `function.state`, which start out with the
+/// stored value and hold the value to store back at the end.
+///
+/// `emit`s are numbered and listed in `function.emits` the same way, and
+/// cannot be in loops either.
+pub fn resolve_storage(function: &mut Function) -> RewriterResult<()> {
+    let mut resolver = StorageResolver {
+        accesses: vec![],
+        emits: vec![],
+        loops: 0,
+        state: vec![],
+        private_num: function.private_num,
+        public_num: function.public_num,
+    };
+    let operations = std::mem::take(&mut function.operations);
+    function.operations = resolver.block(&operations)?;
+    function.storage = resolver.accesses;
+    function.emits = resolver.emits;
+    function.state = resolver.state;
+    function.private_num = resolver.private_num;
+    function.public_num = resolver.public_num;
+    Ok(())
+}
+
+/// Checks that `function`, once resolved, keeps to its state mutability:
+/// `view` functions only read state, and `pure` ones do not touch it.
+/// Emitting an event counts as a write.
+pub fn check_mutability(function: &Function) -> RewriterResult<()> {
+    let (kind, allows_reads) = match function.mutability {
+        Mutability::NonPayable => return Ok(()),
+        Mutability::View => ("view", true),
+        Mutability::Pure => ("pure", false),
+    };
+    let offence = function
+        .operations
+        .iter()
+        .find_map(|operation| offence(function, operation, allows_reads));
+    match offence {
+        Some((what, location)) => type_error(
+            &format!("{} function `{}` {}", kind, function.name, what),
+            location,
+        ),
+        None => Ok(()),
+    }
+}
+
+// First thing `operation` does that the function may not, as in "writes
+// `x`", and where, if any.
+fn offence(
+    function: &Function,
+    operation: &Operation,
+    allows_reads: bool,
+) -> Option<(String, Location)> {
+    let state = |symbol: &Symbol| {
+        function
+            .state
+            .iter()
+            .find(|state| state.local == *symbol)
+            .map(|state| state.variable.name.clone())
+    };
+    let what = match &operation.operation {
+        OperationType::Assign { left, .. } => assigned(left)
+            .and_then(|symbol| state(&symbol))
+            .map(|name| format!("writes `{}`", name)),
+        OperationType::Store { mapping, .. } => Some(format!("writes `{}`", mapping.name)),
+        OperationType::Load { mapping, .. } if !allows_reads => {
+            Some(format!("reads `{}`", mapping.name))
+        }
+        OperationType::Symbol { symbol } if !allows_reads => {
+            state(symbol).map(|name| format!("reads `{}`", name))
+        }
+        OperationType::Emit { event, .. } => Some(format!("emits `{}`", event.name)),
+        _ => None,
+    };
+    match what {
+        Some(what) => Some((what, operation.location)),
+        None => operation
+            .children()
+            .into_iter()
+            .find_map(|child| offence(function, child, allows_reads)),
+    }
+}
+
+fn as_mapping(operation: &Operation) -> Option<Symbol> {
+    operation
+        .as_symbol()
+        .filter(|symbol| symbol.symbol_type.as_mapping().is_some())
+}
+
+fn is_state(symbol: &Symbol) -> bool {
+    symbol.data_location == SymbolLocation::Storage && symbol.symbol_type.as_mapping().is_none()
+}
+
+// The variable an assignment to `target` changes.
+fn assigned(target: &Operation) -> Option<Symbol> {
+    match &target.operation {
+        OperationType::Symbol { symbol } => Some(symbol.clone()),
+        OperationType::Index { array: value, .. } | OperationType::Member { value, .. } => {
+            assigned(value)
+        }
+        _ => None,
+    }
+}
+
+struct StorageResolver {
+    accesses: Vec<StorageAccess>,
+    emits: Vec<Event>,
+    // Loops around the operation being resolved.
+    loops: usize,
+    state: Vec<StateVariable>,
+    private_num: u32,
+    public_num: u32,
+}
+
+impl StorageResolver {
+    fn block(&mut self, stmts: &[Operation]) -> RewriterResult<Vec<Operation>> {
+        let mut operations = vec![];
+        // Condition of the last `if`, which its `else` reuses.
+        let mut last_condition = None;
+        for stmt in stmts {
+            let operation = match &stmt.operation {
+                OperationType::If { cond, stmts } => {
+                    let cond = self.operation(cond)?;
+                    last_condition = Some(cond.clone());
+                    OperationType::If {
+                        cond: Box::new(cond),
+                        stmts: self.block(stmts)?,
+                    }
+                }
+                OperationType::Else { cond, stmts } => {
+                    let cond = match last_condition.take() {
+                        Some(cond) => cond,
+                        None => self.operation(cond)?,
+                    };
+                    OperationType::Else {
+                        cond: Box::new(cond),
+                        stmts: self.block(stmts)?,
+                    }
+                }
+                OperationType::For {
+                    iter,
+                    vector,
+                    stmts,
+                } => {
+                    let vector = self.operation(vector)?;
+                    self.loops += 1;
+                    let stmts = self.block(stmts)?;
+                    self.loops -= 1;
+                    OperationType::For {
+                        iter: iter.clone(),
+                        vector: Box::new(vector),
+                        stmts,
+                    }
+                }
+                _ => self.operation(stmt)?.operation,
+            };
+            operations.push(Operation::new(operation).at(stmt.location));
+        }
+        Ok(operations)
+    }
+
+    fn operation(&mut self, operation: &Operation) -> RewriterResult<Operation> {
+        Ok(self.resolve(operation)?.at(operation.location))
+    }
+
+    // Operands are resolved in the order they are evaluated, so accesses
+    // are numbered in the order they run.
+    fn resolve(&mut self, operation: &Operation) -> RewriterResult<Operation> {
+        match &operation.operation {
+            OperationType::Assign { left, right } => {
+                let right = self.operation(right)?;
+                if let Some(variable) = assigned(left).filter(is_state) {
+                    self.local(&variable).is_write = true;
+                }
+                if let OperationType::Index { array, index } = &left.operation {
+                    if let Some(mapping) = as_mapping(array) {
+                        let key = self.operation(index)?;
+                        let access = self.access(mapping.clone(), true, operation.location)?;
+                        return Ok(Operation::new(OperationType::Store {
+                            mapping,
+                            key: Box::new(key),
+                            value: Box::new(right),
+                            access,
+                        }));
+                    }
+                }
+                Ok(Operation::new(OperationType::Assign {
+                    left: Box::new(self.operation(left)?),
+                    right: Box::new(right),
+                }))
+            }
+            OperationType::Index { array, index } => match as_mapping(array) {
+                Some(mapping) => {
+                    let key = self.operation(index)?;
+                    let access = self.access(mapping.clone(), false, operation.location)?;
+                    Ok(Operation::new(OperationType::Load {
+                        mapping,
+                        key: Box::new(key),
+                        access,
+                    }))
+                }
+                None => Ok(Operation::new(OperationType::Index {
+                    array: Box::new(self.operation(array)?),
+                    index: Box::new(self.operation(index)?),
+                })),
+            },
+            OperationType::Emit { event, args, .. } => {
+                let mut resolved = vec![];
+                for arg in args {
+                    resolved.push(self.operation(arg)?);
+                }
+                if self.loops > 0 {
+                    return type_error(
+                        &format!("`{}` is emitted in a loop", event.name),
+                        operation.location,
+                    );
+                }
+                self.emits.push(event.clone());
+                Ok(Operation::new(OperationType::Emit {
+                    event: event.clone(),
+                    args: resolved,
+                    emit: self.emits.len() - 1,
+                }))
+            }
+            OperationType::Symbol { symbol } if symbol.symbol_type.as_mapping().is_some() => {
+                type_error(
+                    &format!("mapping `{}` is only accessed by key", symbol.name),
+                    operation.location,
+                )
+            }
+            OperationType::Symbol { symbol } if is_state(symbol) => {
+                Ok(Operation::new_symbol(self.local(symbol).local.clone()))
+            }
+            _ => {
+                let mut operation = operation.clone();
+                for child in operation.children_mut() {
+                    *child = self.operation(child)?;
+                }
+                Ok(operation)
+            }
+        }
+    }
+
+    // The local copy of state variable `variable`.
+    fn local(&mut self, variable: &Symbol) -> &mut StateVariable {
+        let position = self
+            .state
+            .iter()
+            .position(|state| state.variable == *variable);
+        let i = match position {
+            Some(i) => i,
+            None => {
+                let num = if variable.is_private {
+                    &mut self.private_num
+                } else {
+                    &mut self.public_num
+                };
+                let local = Symbol::new(
+                    variable.name.clone(),
+                    *num,
+                    variable.symbol_type.clone(),
+                    SymbolLocation::Memory,
+                    variable.is_private,
+                );
+                *num += 1;
+                self.state.push(StateVariable {
+                    variable: variable.clone(),
+                    local,
+                    is_write: false,
+                });
+                self.state.len() - 1
+            }
+        };
+        &mut self.state[i]
+    }
+
+    fn access(
+        &mut self,
+        mapping: Symbol,
+        is_write: bool,
+        location: Location,
+    ) -> RewriterResult<usize> {
+        if self.loops > 0 {
+            return type_error(
+                &format!("`{}` is accessed in a loop", mapping.name),
+                location,
+            );
+        }
+        self.accesses.push(StorageAccess { mapping, is_write });
+        Ok(self.accesses.len() - 1)
+    }
+}
@@ -1,6 +1,8 @@
+use crate::encoding::{bool_words, length_words};
 use num_bigint::BigUint;
 use std::fmt;
 use zoker_parser::ast::{Specifier, Type};
+use zoker_parser::location::Location;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SymbolType {
@@ -10,19 +12,84 @@ pub enum SymbolType {
     Address,
     Bytes32,
     Bool,
+    /// `bytes` of at most this many bytes.
+    BoundedBytes(usize),
+    /// `string` of at most this many bytes.
+    BoundedString(usize),
+    /// Fixed number of elements of one type.
+    Array(Box<SymbolType>, usize),
+    /// Contract storage from keys of one type to values of another.
+    Mapping(Box<SymbolType>, Box<SymbolType>),
+    /// Fields of a struct declared in the contract.
+    Struct(StructType),
     None,
 }
 
-pub fn token_to_type(typ: &Type) -> SymbolType {
-    match typ {
+/// A struct declared in a contract, with its fields in declaration order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<StructField>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructField {
+    pub name: String,
+    pub symbol_type: SymbolType,
+    /// Private fields are proven from private inputs and never output,
+    /// whatever the privacy of the value holding them.
+    pub is_private: bool,
+}
+
+impl StructType {
+    /// Position of field `name`, and the field.
+    pub fn field(&self, name: &str) -> Option<(usize, &StructField)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.name == name)
+    }
+}
+
+/// A scalar or array inside a value, with the privacy its fields give it.
+///
+/// Values without structs are a single part; a struct is the parts of its
+/// fields and an array holding structs the parts of its elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// Path of the part from the value, like `a.owner` or `a[1].owner`.
+    pub name: String,
+    pub symbol_type: SymbolType,
+    pub is_private: bool,
+    /// First word of the part among the big-endian words of the value.
+    pub offset: usize,
+}
+
+/// The type `typ` names, or the name of the struct it uses which is not
+/// among `structs`.
+pub fn token_to_type(typ: &Type, structs: &[StructType]) -> Result<SymbolType, String> {
+    Ok(match typ {
         Type::Uint256 => SymbolType::Uint256,
         Type::Int256 => SymbolType::Int256,
         Type::Bytes32 => SymbolType::Bytes32,
         Type::Bool => SymbolType::Bool,
         Type::Bytes => SymbolType::Bytes32,
         Type::String => SymbolType::String,
+        Type::BoundedBytes { max_len } => SymbolType::BoundedBytes(*max_len),
+        Type::BoundedString { max_len } => SymbolType::BoundedString(*max_len),
         Type::Address => SymbolType::Address,
-    }
+        Type::Array { element, size } => {
+            SymbolType::Array(Box::new(token_to_type(element, structs)?), *size)
+        }
+        Type::Mapping { key, value } => SymbolType::Mapping(
+            Box::new(token_to_type(key, structs)?),
+            Box::new(token_to_type(value, structs)?),
+        ),
+        Type::Struct { name } => match structs.iter().find(|s| s.name == *name) {
+            Some(structure) => SymbolType::Struct(structure.clone()),
+            None => return Err(name.clone()),
+        },
+    })
 }
 
 pub fn symbol_to_string(typ: &SymbolType) -> &str {
@@ -33,13 +100,160 @@ pub fn symbol_to_string(typ: &SymbolType) -> &str {
         SymbolType::Address => "address",
         SymbolType::Bytes32 => "bytes",
         SymbolType::Bool => "bool",
+        SymbolType::BoundedBytes(..) => "bytes",
+        SymbolType::BoundedString(..) => "string",
+        SymbolType::Array(..) => "array",
+        SymbolType::Mapping(..) => "mapping",
+        SymbolType::Struct(..) => "struct",
         SymbolType::None => "null",
     }
 }
 
+impl SymbolType {
+    /// Number of 32-bit circuit words a value of this type occupies.
+    pub fn word_count(&self) -> usize {
+        match self {
+            SymbolType::Uint256 | SymbolType::Int256 | SymbolType::Bytes32 => 8,
+            SymbolType::Address => 5,
+            SymbolType::Bool => 1,
+            // The length, then the bytes padded to a multiple of 32.
+            SymbolType::BoundedBytes(max_len) | SymbolType::BoundedString(max_len) => {
+                1 + max_len.div_ceil(32) * 8
+            }
+            SymbolType::Array(element, size) => element.word_count() * size,
+            SymbolType::Struct(structure) => structure
+                .fields
+                .iter()
+                .map(|field| field.symbol_type.word_count())
+                .sum(),
+            SymbolType::String | SymbolType::Mapping(..) | SymbolType::None => 0,
+        }
+    }
+
+    /// Most bytes a value of a bounded `bytes` or `string` type holds.
+    pub fn max_len(&self) -> Option<usize> {
+        match self {
+            SymbolType::BoundedBytes(max_len) | SymbolType::BoundedString(max_len) => {
+                Some(*max_len)
+            }
+            _ => None,
+        }
+    }
+
+    /// Element type and length of an array type.
+    pub fn as_array(&self) -> Option<(&SymbolType, usize)> {
+        match self {
+            SymbolType::Array(element, size) => Some((element, *size)),
+            _ => None,
+        }
+    }
+
+    /// Key and value types of a mapping type.
+    pub fn as_mapping(&self) -> Option<(&SymbolType, &SymbolType)> {
+        match self {
+            SymbolType::Mapping(key, value) => Some((key, value)),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&StructType> {
+        match self {
+            SymbolType::Struct(structure) => Some(structure),
+            _ => None,
+        }
+    }
+
+    /// How to call a value of this type in errors if it is an array, a
+    /// struct or bounded bytes or string, which only move around whole.
+    pub fn compound_kind(&self) -> Option<&'static str> {
+        match self {
+            SymbolType::Array(..) => Some("an array"),
+            SymbolType::Struct(..) => Some("a struct"),
+            SymbolType::BoundedBytes(..) => Some("bytes"),
+            SymbolType::BoundedString(..) => Some("a string"),
+            _ => None,
+        }
+    }
+
+    fn has_struct(&self) -> bool {
+        match self {
+            SymbolType::Struct(..) => true,
+            SymbolType::Array(element, _) => element.has_struct(),
+            _ => false,
+        }
+    }
+
+    /// Parts of a value `name` of this type, private if `is_private`.
+    pub fn parts(&self, name: &str, is_private: bool) -> Vec<Part> {
+        let mut parts = vec![];
+        self.add_parts(name.to_string(), is_private, 0, &mut parts);
+        parts
+    }
+
+    fn add_parts(&self, name: String, is_private: bool, offset: usize, parts: &mut Vec<Part>) {
+        match self {
+            SymbolType::Struct(structure) => {
+                let mut offset = offset;
+                for field in &structure.fields {
+                    // Unnamed return values name their fields alone.
+                    let name = match name.as_str() {
+                        "" => field.name.clone(),
+                        _ => format!("{}.{}", name, field.name),
+                    };
+                    let is_private = is_private || field.is_private;
+                    field.symbol_type.add_parts(name, is_private, offset, parts);
+                    offset += field.symbol_type.word_count();
+                }
+            }
+            SymbolType::Array(element, size) if element.has_struct() => {
+                for i in 0..*size {
+                    let name = format!("{}[{}]", name, i);
+                    let offset = offset + i * element.word_count();
+                    element.add_parts(name, is_private, offset, parts);
+                }
+            }
+            _ => parts.push(Part {
+                name,
+                symbol_type: self.clone(),
+                is_private,
+                offset,
+            }),
+        }
+    }
+
+    /// Number of words of the public parts of a value of this type.
+    pub fn public_word_count(&self, is_private: bool) -> usize {
+        self.parts("", is_private)
+            .iter()
+            .filter(|part| !part.is_private)
+            .map(|part| part.symbol_type.word_count())
+            .sum()
+    }
+
+    /// The words of the public parts among the words of a value, which
+    /// is what functions output of it. A private value has none.
+    pub fn public_words<W: Clone>(&self, is_private: bool, words: &[W]) -> Vec<W> {
+        self.parts("", is_private)
+            .iter()
+            .filter(|part| !part.is_private)
+            .flat_map(|part| {
+                words[part.offset..part.offset + part.symbol_type.word_count()].to_vec()
+            })
+            .collect()
+    }
+}
+
 impl fmt::Display for SymbolType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", symbol_to_string(self))
+        match self {
+            SymbolType::Array(element, size) => write!(f, "{}[{}]", element, size),
+            SymbolType::Mapping(key, value) => write!(f, "mapping({} => {})", key, value),
+            SymbolType::Struct(structure) => write!(f, "struct {}", structure.name),
+            SymbolType::BoundedBytes(max_len) | SymbolType::BoundedString(max_len) => {
+                write!(f, "{}<{}>", symbol_to_string(self), max_len)
+            }
+            _ => write!(f, "{}", symbol_to_string(self)),
+        }
     }
 }
 
@@ -47,6 +261,18 @@ impl fmt::Display for SymbolType {
 pub struct Contract {
     pub name: String,
     pub functions: Vec<Function>,
+    /// State variables in declaration order; their `Symbol.num`s index
+    /// this list.
+    pub storage: Vec<Symbol>,
+    /// Structs declared in the contract, in declaration order.
+    pub structs: Vec<StructType>,
+    /// Events declared in the contract, in declaration order.
+    pub events: Vec<Event>,
+    /// Initial values of the state variables, by `Symbol.num`; 0 unless
+    /// declared with a default.
+    pub initial: Vec<BigUint>,
+    /// Code run once when the contract is deployed, on public values only.
+    pub constructor: Option<Function>,
 }
 
 impl Contract {
@@ -54,6 +280,11 @@ impl Contract {
         Contract {
             name,
             functions: vec![],
+            storage: vec![],
+            structs: vec![],
+            events: vec![],
+            initial: vec![],
+            constructor: None,
         }
     }
 
@@ -73,10 +304,28 @@ impl Contract {
 pub struct Function {
     pub name: String,
     pub params: Vec<Symbol>,
+    /// Where each of `params` is declared, if it is in the source.
+    pub param_locations: Vec<Location>,
     pub operations: Vec<Operation>,
     pub returns: Vec<Symbol>,
+    /// Private variables of the function, parameters included; their
+    /// `Symbol.num`s are `0..private_num`.
     pub private_num: u32,
+    /// Public variables of the function, likewise.
     pub public_num: u32,
+    /// Mapping entries the function reads or writes, in execution order;
+    /// `Load`s and `Store`s refer to them by position.
+    pub storage: Vec<StorageAccess>,
+    /// State variables the function uses, in order of first use.
+    pub state: Vec<StateVariable>,
+    /// Events the function emits, in execution order; `Emit`s refer to
+    /// them by position.
+    pub emits: Vec<Event>,
+    pub visibility: Visibility,
+    pub mutability: Mutability,
+    /// Whether the function is an entry point proven on chain, with a
+    /// circuit and a verifier. Other functions are only inlined.
+    pub is_proven: bool,
 }
 
 impl Function {
@@ -84,38 +333,134 @@ impl Function {
         Function {
             name,
             params,
+            param_locations: vec![],
             operations: vec![],
             returns,
             private_num: 0,
             public_num: 0,
+            storage: vec![],
+            state: vec![],
+            emits: vec![],
+            visibility: Visibility::Public,
+            mutability: Mutability::NonPayable,
+            is_proven: true,
         }
     }
 
     pub fn add_operations(&mut self, operations: Vec<Operation>) {
         self.operations.extend(operations);
     }
+
+    /// Whether any `require` or `assert` is left in the function.
+    pub fn has_requirements(&self) -> bool {
+        fn any(stmts: &[Operation]) -> bool {
+            stmts.iter().any(|stmt| match &stmt.operation {
+                OperationType::Require { .. } => true,
+                OperationType::If { stmts, .. }
+                | OperationType::Else { stmts, .. }
+                | OperationType::For { stmts, .. } => any(stmts),
+                _ => false,
+            })
+        }
+        any(&self.operations)
+    }
+
+    /// Whether the circuit checks the words of private parameters are
+    /// canonical, which `bool`s and bounded `bytes` and strings need.
+    pub fn checks_inputs(&self) -> bool {
+        self.params
+            .iter()
+            .flat_map(|param| param.symbol_type.parts("", param.is_private))
+            .filter(|part| part.is_private)
+            .any(|part| {
+                !bool_words(&part.symbol_type).is_empty()
+                    || !length_words(&part.symbol_type).is_empty()
+            })
+    }
+
+    /// Whether the output has a `valid` word, for requirements or checks
+    /// of the inputs.
+    pub fn has_valid_word(&self) -> bool {
+        self.has_requirements() || self.checks_inputs()
+    }
+}
+
+/// A `require` or `assert` statement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Requirement {
+    pub is_assert: bool,
+    pub location: Location,
+}
+
+impl Requirement {
+    pub fn keyword(&self) -> &'static str {
+        if self.is_assert {
+            "assert"
+        } else {
+            "require"
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` at {}", self.keyword(), self.location)
+    }
+}
+
+/// An event declared in a contract, whose parameters are all public.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub params: Vec<Symbol>,
+}
+
+/// One read or write of a mapping entry by a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageAccess {
+    pub mapping: Symbol,
+    pub is_write: bool,
+}
+
+/// A state variable used by a function, which works on a local copy of it
+/// instead.
+///
+/// A private state variable is stored as the commitment `sha256(value,
+/// salt)` with a private `bytes32` salt. Its copy is private, and starts
+/// out from an opening of the stored commitment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateVariable {
+    pub variable: Symbol,
+    pub local: Symbol,
+    pub is_write: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Operation {
     pub operation: OperationType,
+    /// Where the operation was written, for errors found after rewriting.
+    pub location: Location,
 }
 
 impl Operation {
     pub fn new_symbol(symbol: Symbol) -> Self {
-        Operation {
-            operation: OperationType::Symbol { symbol },
-        }
+        Operation::new(OperationType::Symbol { symbol })
     }
 
     pub fn new_call(func: String, args: Vec<Operation>) -> Self {
+        Operation::new(OperationType::Call { func, args })
+    }
+
+    pub fn new(operation: OperationType) -> Self {
         Operation {
-            operation: OperationType::Call { func, args },
+            operation,
+            location: Location::default(),
         }
     }
 
-    pub fn new(operation: OperationType) -> Self {
-        Operation { operation }
+    pub fn at(mut self, location: Location) -> Self {
+        self.location = location;
+        self
     }
 
     pub fn as_symbol(&self) -> Option<Symbol> {
@@ -124,6 +469,121 @@ impl Operation {
             _ => None,
         }
     }
+
+    /// Type and privacy of a variable or of an element or field of one.
+    pub fn variable_type(&self) -> Option<(SymbolType, bool)> {
+        match &self.operation {
+            OperationType::Symbol { symbol } => {
+                Some((symbol.symbol_type.clone(), symbol.is_private))
+            }
+            OperationType::Index { array, .. } => match array.variable_type()? {
+                (SymbolType::Array(element, _), is_private) => Some((*element, is_private)),
+                _ => None,
+            },
+            OperationType::Member { value, field } => {
+                let (symbol_type, is_private) = value.variable_type()?;
+                let (_, field) = symbol_type.as_struct()?.field(field)?;
+                Some((field.symbol_type.clone(), is_private || field.is_private))
+            }
+            _ => None,
+        }
+    }
+
+    /// Operations this one is made of, statements included.
+    pub fn children(&self) -> Vec<&Operation> {
+        match &self.operation {
+            OperationType::Add { left, right }
+            | OperationType::Sub { left, right }
+            | OperationType::Mul { left, right }
+            | OperationType::Div { left, right }
+            | OperationType::Mod { left, right }
+            | OperationType::Lt { left, right }
+            | OperationType::Le { left, right }
+            | OperationType::Gt { left, right }
+            | OperationType::Ge { left, right }
+            | OperationType::Eq { left, right }
+            | OperationType::NotEq { left, right }
+            | OperationType::LShift { left, right }
+            | OperationType::RShift { left, right }
+            | OperationType::Assign { left, right } => vec![left.as_ref(), right.as_ref()],
+            OperationType::Index { array, index } => vec![array.as_ref(), index.as_ref()],
+            OperationType::Member { value, .. } => vec![value.as_ref()],
+            OperationType::Load { key, .. } => vec![key.as_ref()],
+            OperationType::Store { key, value, .. } => vec![key.as_ref(), value.as_ref()],
+            OperationType::Neg { operand } => vec![operand.as_ref()],
+            OperationType::Reveal { value } => vec![value.as_ref()],
+            OperationType::Return { ret } => vec![ret.as_ref()],
+            OperationType::Require { cond, .. } => vec![cond.as_ref()],
+            OperationType::If { cond, stmts } | OperationType::Else { cond, stmts } => {
+                let mut children = vec![cond.as_ref()];
+                children.extend(stmts.iter());
+                children
+            }
+            OperationType::For {
+                iter,
+                vector,
+                stmts,
+            } => {
+                let mut children = vec![iter.as_ref(), vector.as_ref()];
+                children.extend(stmts.iter());
+                children
+            }
+            OperationType::Call { args, .. }
+            | OperationType::Sha256 { args }
+            | OperationType::Emit { args, .. } => args.iter().collect(),
+            OperationType::Symbol { .. } | OperationType::Constant { .. } | OperationType::Nop => {
+                vec![]
+            }
+        }
+    }
+
+    /// `children`, mutably.
+    pub fn children_mut(&mut self) -> Vec<&mut Operation> {
+        match &mut self.operation {
+            OperationType::Add { left, right }
+            | OperationType::Sub { left, right }
+            | OperationType::Mul { left, right }
+            | OperationType::Div { left, right }
+            | OperationType::Mod { left, right }
+            | OperationType::Lt { left, right }
+            | OperationType::Le { left, right }
+            | OperationType::Gt { left, right }
+            | OperationType::Ge { left, right }
+            | OperationType::Eq { left, right }
+            | OperationType::NotEq { left, right }
+            | OperationType::LShift { left, right }
+            | OperationType::RShift { left, right }
+            | OperationType::Assign { left, right } => vec![left.as_mut(), right.as_mut()],
+            OperationType::Index { array, index } => vec![array.as_mut(), index.as_mut()],
+            OperationType::Member { value, .. } => vec![value.as_mut()],
+            OperationType::Load { key, .. } => vec![key.as_mut()],
+            OperationType::Store { key, value, .. } => vec![key.as_mut(), value.as_mut()],
+            OperationType::Neg { operand } => vec![operand.as_mut()],
+            OperationType::Reveal { value } => vec![value.as_mut()],
+            OperationType::Return { ret } => vec![ret.as_mut()],
+            OperationType::Require { cond, .. } => vec![cond.as_mut()],
+            OperationType::If { cond, stmts } | OperationType::Else { cond, stmts } => {
+                let mut children = vec![cond.as_mut()];
+                children.extend(stmts.iter_mut());
+                children
+            }
+            OperationType::For {
+                iter,
+                vector,
+                stmts,
+            } => {
+                let mut children = vec![iter.as_mut(), vector.as_mut()];
+                children.extend(stmts.iter_mut());
+                children
+            }
+            OperationType::Call { args, .. }
+            | OperationType::Sha256 { args }
+            | OperationType::Emit { args, .. } => args.iter_mut().collect(),
+            OperationType::Symbol { .. } | OperationType::Constant { .. } | OperationType::Nop => {
+                vec![]
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -140,6 +600,49 @@ pub enum OperationType {
         left: Box<Operation>,
         right: Box<Operation>,
     },
+    Div {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Mod {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Neg {
+        operand: Box<Operation>,
+    },
+    Lt {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Le {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Gt {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Ge {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    Eq {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    NotEq {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    LShift {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
+    RShift {
+        left: Box<Operation>,
+        right: Box<Operation>,
+    },
     Assign {
         left: Box<Operation>,
         right: Box<Operation>,
@@ -160,10 +663,54 @@ pub enum OperationType {
     Return {
         ret: Box<Operation>,
     },
+    /// Fails the proof unless `cond` holds whenever the statement runs.
+    Require {
+        cond: Box<Operation>,
+        requirement: Requirement,
+    },
     Call {
         func: String,
         args: Vec<Operation>,
     },
+    /// Element `index` of `array`.
+    Index {
+        array: Box<Operation>,
+        index: Box<Operation>,
+    },
+    /// Field `field` of a struct.
+    Member {
+        value: Box<Operation>,
+        field: String,
+    },
+    /// Entry `key` of a mapping, read as the `access`th storage access of
+    /// the function.
+    Load {
+        mapping: Symbol,
+        key: Box<Operation>,
+        access: usize,
+    },
+    /// Writes `value` to entry `key` of a mapping.
+    Store {
+        mapping: Symbol,
+        key: Box<Operation>,
+        value: Box<Operation>,
+        access: usize,
+    },
+    /// Builtin `sha256(...)`: digest of the arguments' words, lowered to
+    /// the SHA-256 gadget instead of a call.
+    Sha256 {
+        args: Vec<Operation>,
+    },
+    /// Emits event `event` with `args`, once the proof is verified.
+    Emit {
+        event: Event,
+        args: Vec<Operation>,
+        emit: usize,
+    },
+    /// Builtin `reveal(...)`: `value` as is, made public for the output.
+    Reveal {
+        value: Box<Operation>,
+    },
     Symbol {
         symbol: Symbol,
     },
@@ -180,6 +727,21 @@ pub enum SymbolLocation {
     Memory,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    Public,
+    External,
+    Internal,
+}
+
+/// What a function may do with the contract state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutability {
+    NonPayable,
+    View,
+    Pure,
+}
+
 pub fn specifier_to_location(loc: &Specifier) -> SymbolLocation {
     match loc {
         Specifier::Memory => SymbolLocation::Memory,
@@ -1,129 +1,864 @@
-/*use crate::symbol::{symbol_to_string, Contract, Function, Operation, OperationType};
+use crate::circuit::Circuit;
+use crate::encoding::{bool_words, length_words, value_bits};
+use crate::error::{error_at, type_error, RewriteErrorType};
+use crate::interpreter::{commitment, Value};
+use crate::layout::{abi_type, Layout, LayoutEntry};
+use crate::lowering::CircuitOptions;
+use crate::rewriter::RewriterResult;
+use crate::symbol::{
+    Contract, Function, Operation, OperationType, StructType, Symbol, SymbolLocation, SymbolType,
+    Visibility,
+};
+use num_bigint::BigUint;
+use std::cell::RefCell;
+use std::rc::Rc;
+use zoker_parser::location::Location;
+use zoker_prover::ikos::{IKosResult, IKosVariable};
 
-// pub fn rewrite_verifier(contracts: Vec<Contract>) -> RewriterResult<()> {
-//     let mut verifier = Verifier::new();
-//     for contract in contracts {
-//         verifier.rewrite_contract(&contract);
-//     }
-//     Ok(())
-// }
+/// Generates the Solidity verifier of `contracts`.
+///
+/// Every proven function becomes an internal `_circuit_<name>` replaying
+/// its circuit on `IKosVariable4V`s, and a public or external `<name>`
+/// checking a ZKBoo proof with it. Contracts inherit the primitives of
+/// `zkboo.sol`.
+/// With checked arithmetic, proofs whose `ok` word is not set are rejected.
+/// Proofs whose `valid` word shows a failed `require` and public `bool`
+/// inputs other than 0 and 1 always are.
+///
+/// Mappings become contract storage. A proof is only accepted if the
+/// entries it read hold the values it was made with, and the entries it
+/// wrote are updated once it is verified. Other state variables work
+/// the same way, each leaf checked against and then set from its words.
+/// Private ones are stored as the commitments the proof opens and makes.
+/// State starts out with its defaults, private state with a commitment
+/// to its default under a zero salt, and then the constructor runs as
+/// Solidity. Private state it sets is committed to under a salt passed
+/// with the deployment arguments.
+/// Events are declared alike and emitted once the proof is verified.
+///
+/// Structs become Solidity structs of their public fields, with a
+/// `decode_<name>` reading them from the words of a value.
+pub fn rewrite_verifier(contracts: &[Contract], options: CircuitOptions) -> RewriterResult<String> {
+    let mut verifier = Verifier::new(options);
+    verifier.write_line("// SPDX-License-Identifier: MIT\n");
+    verifier.write_line("pragma solidity ^0.7.4;\n");
+    verifier.write_line("pragma experimental ABIEncoderV2;\n\n");
+    verifier.write_line("import \"./zkboo.sol\";\n");
+    for contract in contracts {
+        verifier.write("\n");
+        verifier.rewrite_contract(contract)?;
+    }
+    Ok(verifier.code)
+}
+
+#[derive(Default)]
+struct CircuitCode {
+    lines: Vec<String>,
+    wires: usize,
+}
+
+/// Circuit word that writes Solidity instead of computing.
+///
+/// Gadgets run on it like on `IKosVariable4V`; every gate touching an input
+/// appends a statement to the circuit body, while gates over constants stay
+/// inline expressions.
+#[derive(Clone)]
+pub struct SolidityVariable {
+    code: Option<Rc<RefCell<CircuitCode>>>,
+    expr: String,
+}
+
+impl SolidityVariable {
+    fn apply(&self, func: &str, args: &[&SolidityVariable], params: &[u32]) -> Self {
+        let mut call = format!("{}({}", func, self.expr);
+        for arg in args {
+            call.push_str(", ");
+            call.push_str(arg.expr.as_str());
+        }
+        for param in params {
+            call.push_str(&format!(", {}", param));
+        }
+        call.push(')');
+
+        let code = match &self.code {
+            Some(code) => Some(Rc::clone(code)),
+            None => args.iter().find_map(|arg| arg.code.clone()),
+        };
+        match code {
+            Some(code) => {
+                let expr = {
+                    let mut circuit = code.borrow_mut();
+                    let expr = format!("w[{}]", circuit.wires);
+                    circuit.wires += 1;
+                    circuit.lines.push(format!("{} = {};", expr, call));
+                    expr
+                };
+                SolidityVariable {
+                    code: Some(code),
+                    expr,
+                }
+            }
+            None => SolidityVariable { code, expr: call },
+        }
+    }
+}
+
+impl IKosVariable for SolidityVariable {
+    fn new_value(value: u32) -> Self {
+        SolidityVariable {
+            code: None,
+            expr: format!("IKosVariable_new_value({})", value),
+        }
+    }
+
+    fn negate(self) -> Self {
+        self.apply("negate", &[], &[])
+    }
+
+    fn xor(self, rhs: &Self) -> Self {
+        self.apply("_xor", &[rhs], &[])
+    }
+
+    fn rshift(self, n: u32) -> Self {
+        self.apply("rshift", &[], &[n])
+    }
+
+    fn lshift(self, n: u32) -> Self {
+        self.apply("lshift", &[], &[n])
+    }
+
+    fn rotr(self, n: u32) -> Self {
+        self.apply("rotr", &[], &[n])
+    }
+
+    fn rotl(self, n: u32) -> Self {
+        self.apply("rotl", &[], &[n])
+    }
+
+    fn byte(self, n: u32) -> Self {
+        self.apply("_byte", &[], &[n])
+    }
+
+    fn swap_bytes(self) -> Self {
+        self.apply("swap_bytes", &[], &[])
+    }
+
+    fn bit_slice(self, lo: u32, len: u32) -> Self {
+        self.apply("bit_slice", &[], &[lo, len])
+    }
+
+    fn mask(self) -> Self {
+        self.apply("mask", &[], &[])
+    }
+
+    fn bit_and(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(self.apply("bit_and", &[rhs], &[]))
+    }
+
+    fn add_op(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(self.apply("add_op", &[rhs], &[]))
+    }
+
+    fn mux(self, a: &Self, b: &Self) -> IKosResult<Self> {
+        Ok(self.apply("mux", &[a, b], &[]))
+    }
+}
+
+/// Solidity expression decoding the value `entry` places in `words`.
+fn decode(entry: &LayoutEntry, words: &str) -> String {
+    format!(
+        "decode_{}({}, {})",
+        abi_type(&entry.symbol_type),
+        words,
+        entry.offset
+    )
+}
+
+/// Statements setting `target` to the public words of a `symbol_type`
+/// value from `start` in `words`; arrays get a loop per dimension.
+fn decode_public(target: &str, symbol_type: &SymbolType, start: &str, depth: usize) -> Vec<String> {
+    if let SymbolType::Array(element, size) = symbol_type {
+        let i = format!("i{}", depth);
+        let start = format!("{} + {} * {}", start, i, element.public_word_count(false));
+        let mut lines = vec![format!(
+            "for (uint {i} = 0; {i} < {}; {i}++) {{",
+            size,
+            i = i
+        )];
+        for line in decode_public(&format!("{}[{}]", target, i), element, &start, depth + 1) {
+            lines.push(format!("    {}", line));
+        }
+        lines.push("}".to_string());
+        return lines;
+    }
+    vec![format!(
+        "{} = decode_{}(words, {});",
+        target,
+        abi_type(symbol_type),
+        start
+    )]
+}
+
+/// Scalar leaves of a `symbol_type` value stored at `path`, with the
+/// offset of their words from `start`.
+fn state_leaves(
+    path: &str,
+    symbol_type: &SymbolType,
+    start: usize,
+) -> Vec<(String, SymbolType, usize)> {
+    match symbol_type {
+        SymbolType::Array(element, size) => (0..*size)
+            .flat_map(|i| {
+                state_leaves(
+                    &format!("{}[{}]", path, i),
+                    element,
+                    start + i * element.word_count(),
+                )
+            })
+            .collect(),
+        SymbolType::Struct(_) => symbol_type
+            .parts(path, false)
+            .into_iter()
+            .flat_map(|part| state_leaves(&part.name, &part.symbol_type, start + part.offset))
+            .collect(),
+        _ => vec![(path.to_string(), symbol_type.clone(), start)],
+    }
+}
+
+/// Solidity expression comparing two values of a `symbol_type`; `bytes`
+/// and strings are compared by hash.
+fn equal(symbol_type: &SymbolType, left: &str, right: &str) -> String {
+    match symbol_type.max_len() {
+        Some(_) => format!(
+            "keccak256(abi.encodePacked({})) == keccak256(abi.encodePacked({}))",
+            left, right
+        ),
+        None => format!("{} == {}", left, right),
+    }
+}
+
+/// Solidity literal of `value`, of a value type.
+fn literal(symbol_type: &SymbolType, value: &BigUint) -> String {
+    match symbol_type {
+        SymbolType::Address => format!("address(uint160({}))", value),
+        SymbolType::Bytes32 => format!("bytes32(uint256({}))", value),
+        _ => Value::new(symbol_type.clone(), value.clone()).to_string(),
+    }
+}
+
+/// Operands and Solidity operator of a binary operation.
+fn binary(operation: &Operation) -> Option<(&Operation, &'static str, &Operation)> {
+    let (left, op, right) = match &operation.operation {
+        OperationType::Add { left, right } => (left, "+", right),
+        OperationType::Sub { left, right } => (left, "-", right),
+        OperationType::Mul { left, right } => (left, "*", right),
+        OperationType::Div { left, right } => (left, "/", right),
+        OperationType::Mod { left, right } => (left, "%", right),
+        OperationType::Lt { left, right } => (left, "<", right),
+        OperationType::Le { left, right } => (left, "<=", right),
+        OperationType::Gt { left, right } => (left, ">", right),
+        OperationType::Ge { left, right } => (left, ">=", right),
+        OperationType::Eq { left, right } => (left, "==", right),
+        OperationType::NotEq { left, right } => (left, "!=", right),
+        OperationType::LShift { left, right } => (left, "<<", right),
+        OperationType::RShift { left, right } => (left, ">>", right),
+        _ => return None,
+    };
+    Some((left, op, right))
+}
+
+/// Solidity statements of a constructor, which runs on chain as it is,
+/// since it only uses public values. It may assign, check and emit.
+///
+/// Private state it uses is held in a local `_<name>`, starting at its
+/// default. Private state it assigns is committed to once it has run,
+/// with a salt passed as an extra `bytes32 <name>_salt` argument.
+struct ConstructorCode<'a> {
+    constructor: &'a Function,
+    lines: Vec<String>,
+    /// Local variables, declared up front.
+    locals: Vec<Symbol>,
+    /// Private state used, and the private state assigned with where.
+    private: Vec<Symbol>,
+    openings: Vec<(Symbol, Location)>,
+    depth: usize,
+}
+
+impl ConstructorCode<'_> {
+    fn block(&mut self, stmts: &[Operation]) -> RewriterResult<()> {
+        for stmt in stmts {
+            match &stmt.operation {
+                OperationType::If { cond, stmts } => {
+                    let line = format!("if ({}) {{", self.expression(cond)?);
+                    self.line(line);
+                    self.nested(stmts)?;
+                }
+                OperationType::Else { stmts, .. } => {
+                    self.line("else {".to_string());
+                    self.nested(stmts)?;
+                }
+                OperationType::Assign { left, right } => {
+                    if let OperationType::Symbol { symbol } = &left.operation {
+                        let opened = self.openings.iter().any(|(other, _)| other == symbol);
+                        if symbol.is_private
+                            && symbol.data_location == SymbolLocation::Storage
+                            && !opened
+                        {
+                            self.openings.push((symbol.clone(), stmt.location));
+                        }
+                    }
+                    let line = format!("{} = {};", self.expression(left)?, self.expression(right)?);
+                    self.line(line);
+                }
+                OperationType::Require { cond, requirement } => {
+                    let line = format!("{}({});", requirement.keyword(), self.expression(cond)?);
+                    self.line(line);
+                }
+                OperationType::Emit { event, args, .. } => {
+                    let mut values = vec![];
+                    for arg in args {
+                        values.push(self.expression(arg)?);
+                    }
+                    self.line(format!("emit {}({});", event.name, values.join(", ")));
+                }
+                OperationType::Nop => {}
+                _ => return Err(error_at(RewriteErrorType::UnsupportedError, stmt.location)),
+            }
+        }
+        Ok(())
+    }
+
+    fn nested(&mut self, stmts: &[Operation]) -> RewriterResult<()> {
+        self.depth += 1;
+        self.block(stmts)?;
+        self.depth -= 1;
+        self.line("}".to_string());
+        Ok(())
+    }
+
+    fn line(&mut self, line: String) {
+        self.lines
+            .push(format!("{}{}", "    ".repeat(self.depth), line));
+    }
+
+    fn expression(&mut self, operation: &Operation) -> RewriterResult<String> {
+        match binary(operation) {
+            Some((left, op, right)) => Ok(format!(
+                "{} {} {}",
+                self.operand(left)?,
+                op,
+                self.operand(right)?
+            )),
+            None => self.operand(operation),
+        }
+    }
+
+    // Operands of binary operations are in parentheses if binary as well.
+    fn operand(&mut self, operation: &Operation) -> RewriterResult<String> {
+        match &operation.operation {
+            _ if binary(operation).is_some() => Ok(format!("({})", self.expression(operation)?)),
+            OperationType::Neg { operand } => Ok(format!("-{}", self.operand(operand)?)),
+            OperationType::Reveal { value } => self.expression(value),
+            OperationType::Index { array, index } => Ok(format!(
+                "{}[{}]",
+                self.expression(array)?,
+                self.expression(index)?
+            )),
+            OperationType::Member { value, field } => {
+                Ok(format!("{}.{}", self.expression(value)?, field))
+            }
+            OperationType::Constant { value } => Ok(value.to_string()),
+            OperationType::Symbol { symbol } => {
+                if symbol.data_location == SymbolLocation::Storage {
+                    if symbol.is_private {
+                        self.private_state(symbol, operation.location)?;
+                        return Ok(format!("_{}", symbol.name));
+                    }
+                } else if !self.constructor.params.contains(symbol) {
+                    self.local(symbol, operation.location)?;
+                }
+                Ok(symbol.name.clone())
+            }
+            OperationType::Call { func, .. } => type_error(
+                &format!("the constructor cannot call `{}`", func),
+                operation.location,
+            ),
+            _ => Err(error_at(
+                RewriteErrorType::UnsupportedError,
+                operation.location,
+            )),
+        }
+    }
+
+    fn private_state(&mut self, symbol: &Symbol, location: Location) -> RewriterResult<()> {
+        if value_bits(&symbol.symbol_type).is_none() {
+            return type_error(
+                &format!(
+                    "the constructor cannot use private state `{}`; give it a default",
+                    symbol.name
+                ),
+                location,
+            );
+        }
+        if !self.private.contains(symbol) {
+            self.private.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    fn local(&mut self, symbol: &Symbol, location: Location) -> RewriterResult<()> {
+        if symbol.is_private {
+            return type_error(
+                &format!(
+                    "the constructor runs in public; `{}` cannot be private",
+                    symbol.name
+                ),
+                location,
+            );
+        }
+        if self.locals.contains(symbol) {
+            return Ok(());
+        }
+        let declared = self.constructor.params.iter().chain(&self.locals);
+        if declared.clone().any(|other| other.name == symbol.name) {
+            return type_error(
+                &format!("the constructor declares `{}` twice", symbol.name),
+                location,
+            );
+        }
+        if value_bits(&symbol.symbol_type).is_none() {
+            return Err(error_at(RewriteErrorType::UnsupportedError, location));
+        }
+        self.locals.push(symbol.clone());
+        Ok(())
+    }
+}
 
 struct Verifier {
     code: String,
     tab: u32,
+    options: CircuitOptions,
 }
 
 impl Verifier {
-    pub fn new() -> Self {
+    pub fn new(options: CircuitOptions) -> Self {
         Verifier {
             code: String::new(),
             tab: 0,
+            options,
         }
     }
 
-    pub fn rewrite_contract(&mut self, contract: &Contract) {
+    pub fn rewrite_contract(&mut self, contract: &Contract) -> RewriterResult<()> {
         self.write_line("contract ");
-        self.write_line(contract.name.as_str());
-        self.write_line(" {\n");
+        self.write(contract.name.as_str());
+        self.write(" is ZKBoo {\n");
         self.tab += 1;
-        // TODO: Don't consider member variable.
-        for function in &contract.functions {
-            self.rewrite_function(function);
+        for symbol in &contract.storage {
+            let initial = &contract.initial[symbol.num as usize];
+            let declaration = if symbol.is_private {
+                // The initial value is in the source, so it needs no salt.
+                let value = Value::new(symbol.symbol_type.clone(), initial.clone());
+                let salt = Value::new(SymbolType::Bytes32, BigUint::from(0u32));
+                format!(
+                    "bytes32 public {} = 0x{:064x}",
+                    symbol.name,
+                    commitment(&value, &salt).value
+                )
+            } else if *initial == BigUint::from(0u32) {
+                format!("{} public {}", abi_type(&symbol.symbol_type), symbol.name)
+            } else {
+                format!(
+                    "{} public {} = {}",
+                    abi_type(&symbol.symbol_type),
+                    symbol.name,
+                    literal(&symbol.symbol_type, initial)
+                )
+            };
+            self.write_line(&format!("{};\n", declaration));
+        }
+        for event in &contract.events {
+            let params = event
+                .params
+                .iter()
+                .map(|param| format!("{} {}", abi_type(&param.symbol_type), param.name))
+                .collect::<Vec<String>>();
+            self.write_line(&format!("event {}({});\n", event.name, params.join(", ")));
+        }
+        for structure in &contract.structs {
+            self.rewrite_struct(structure);
+        }
+        let declares = !contract.storage.is_empty()
+            || !contract.structs.is_empty()
+            || !contract.events.is_empty();
+        if let Some(constructor) = &contract.constructor {
+            if declares {
+                self.write("\n");
+            }
+            self.rewrite_constructor(constructor, &contract.initial)?;
+        }
+        for (i, function) in contract.functions.iter().enumerate() {
+            if i > 0 || declares || contract.constructor.is_some() {
+                self.write("\n");
+            }
+            let circuit = Circuit::from_function(function, self.options)?;
+            self.rewrite_function(function, &circuit)?;
         }
+        self.tab -= 1;
         self.write_line("}\n");
+        Ok(())
     }
 
-    pub fn rewrite_function(&mut self, function: &Function) {
-        self.write_line("function _");
-        self.write(function.name.as_str());
-        self.write("(");
-        // for param in &function.params {
-        //     TODO: MUST CONSUME ZKP PROOF.
-        // }
-        self.write(") returns (");
-        for ret in &function.returns {
-            self.write(symbol_to_string(&ret.symbol_type));
+    fn rewrite_constructor(
+        &mut self,
+        constructor: &Function,
+        initial: &[BigUint],
+    ) -> RewriterResult<()> {
+        let mut code = ConstructorCode {
+            constructor,
+            lines: vec![],
+            locals: vec![],
+            private: vec![],
+            openings: vec![],
+            depth: 0,
+        };
+        code.block(&constructor.operations)?;
+        code.openings.sort_by_key(|(symbol, _)| symbol.num);
+        let mut params = constructor
+            .params
+            .iter()
+            .map(|param| format!("{} {}", abi_type(&param.symbol_type), param.name))
+            .collect::<Vec<String>>();
+        for (symbol, location) in &code.openings {
+            let salt = format!("{}_salt", symbol.name);
+            let declared = constructor.params.iter().chain(&code.locals);
+            if declared.clone().any(|other| other.name == salt) {
+                return type_error(
+                    &format!("the constructor declares `{}` twice", salt),
+                    *location,
+                );
+            }
+            params.push(format!("bytes32 {}", salt));
+        }
+        self.write_line(&format!("constructor({}) {{\n", params.join(", ")));
+        self.tab += 1;
+        for local in &code.locals {
+            self.write_line(&format!(
+                "{} {};\n",
+                abi_type(&local.symbol_type),
+                local.name
+            ));
+        }
+        for symbol in &code.private {
+            let value = &initial[symbol.num as usize];
+            let declaration = format!("{} _{}", abi_type(&symbol.symbol_type), symbol.name);
+            if *value == BigUint::from(0u32) {
+                self.write_line(&format!("{};\n", declaration));
+            } else {
+                self.write_line(&format!(
+                    "{} = {};\n",
+                    declaration,
+                    literal(&symbol.symbol_type, value)
+                ));
+            }
         }
-        self.write(") {\n");
-        for operation in &function.operations {
-            self.write_line("");
-            self.rewrite_operation(&operation);
-            self.write(";");
+        for line in &code.lines {
+            self.write_line(line);
+            self.write("\n");
         }
-        self.write_line("}");
+        // The packed value is its words, as `commitment` hashes them.
+        for (symbol, _) in &code.openings {
+            let value = match symbol.symbol_type {
+                SymbolType::Bool => format!("uint32(_{} ? 1 : 0)", symbol.name),
+                _ => format!("_{}", symbol.name),
+            };
+            self.write_line(&format!(
+                "{} = sha256(abi.encodePacked({}, {}_salt));\n",
+                symbol.name, value, symbol.name
+            ));
+        }
+        self.tab -= 1;
+        self.write_line("}\n");
+        Ok(())
     }
 
-    fn rewrite_operation(&mut self, operation: &Operation) {
-        match &operation.operation {
-            OperationType::Add { left, right } => {
-                self.write("_add(");
-                self.rewrite_operation(left);
-                self.write(",");
-                self.rewrite_operation(right);
-                self.write(")");
-            }
-            OperationType::Sub { left, right } => {
-                self.write("_sub(");
-                self.rewrite_operation(left);
-                self.write(",");
-                self.rewrite_operation(right);
-                self.write(")");
-            }
-            OperationType::Mul { left, right } => {
-                self.write("_mul(");
-                self.rewrite_operation(left);
-                self.write(",");
-                self.rewrite_operation(right);
-                self.write(")");
-            }
-            OperationType::Assign { left, right } => {
-                // TODO:
-                self.rewrite_operation(left);
-                self.write(" = ");
-                self.rewrite_operation(right);
-            }
-            OperationType::For { stmts, .. } => {
-                // self.write();
-                for stmt in stmts {
-                    self.rewrite_operation(stmt);
-                }
-            }
-            OperationType::If { cond, .. } => {
-                // TODO:
-                self.write("_if(");
-                self.rewrite_operation(cond);
-                self.write(",");
-                // self.rewrite_operation(right);
-                self.write(")");
-            }
-            OperationType::Else { cond, .. } => {
-                self.write("_if(not(");
-                self.rewrite_operation(cond);
-                self.write("),");
-                // self.rewrite_operation(right);
-                self.write(")");
+    // Structs without public fields have nothing to declare.
+    fn rewrite_struct(&mut self, structure: &StructType) {
+        let fields = structure
+            .fields
+            .iter()
+            .filter(|field| !field.is_private && field.symbol_type.public_word_count(false) > 0)
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return;
+        }
+        let name = structure.name.as_str();
+        self.write("\n");
+        self.write_line(&format!("struct {} {{\n", name));
+        self.tab += 1;
+        for field in &fields {
+            self.write_line(&format!(
+                "{} {};\n",
+                abi_type(&field.symbol_type),
+                field.name
+            ));
+        }
+        self.tab -= 1;
+        self.write_line("}\n\n");
+        self.write_line(&format!(
+            "function decode_{}(uint32[] memory words, uint start) internal pure returns ({} memory value) {{\n",
+            name, name
+        ));
+        self.tab += 1;
+        let mut offset = 0;
+        for field in &fields {
+            let target = format!("value.{}", field.name);
+            let start = format!("start + {}", offset);
+            for line in decode_public(&target, &field.symbol_type, &start, 0) {
+                self.write_line(&line);
+                self.write("\n");
             }
-            OperationType::Return { ret } => {
-                self.write("return ");
-                self.rewrite_operation(ret);
+            offset += field.symbol_type.public_word_count(false);
+        }
+        self.tab -= 1;
+        self.write_line("}\n");
+    }
+
+    pub fn rewrite_function(
+        &mut self,
+        function: &Function,
+        circuit: &Circuit,
+    ) -> RewriterResult<()> {
+        let name = function.name.as_str();
+        let (private_len, public_len) = (circuit.input_len, circuit.input_pub_len);
+        let code = Rc::new(RefCell::new(CircuitCode::default()));
+        let input = (0..private_len)
+            .map(|i| SolidityVariable {
+                code: Some(Rc::clone(&code)),
+                expr: format!("input[{}]", i),
+            })
+            .collect::<Vec<SolidityVariable>>();
+        // Gates over public inputs are written out as well, since inline
+        // expressions would repeat every operand they share.
+        let input_pub = (0..public_len)
+            .map(|i| SolidityVariable {
+                code: Some(Rc::clone(&code)),
+                expr: format!("IKosVariable_new_value(input_pub[{}])", i),
+            })
+            .collect::<Vec<SolidityVariable>>();
+        let output = circuit.evaluate(&input, &input_pub)?;
+        let output_len = output.len();
+
+        self.write_line("function _circuit_");
+        self.write(name);
+        self.write("(IKosVariable4V[] memory input, uint32[] memory input_pub) ");
+        self.write("internal pure returns (IKosVariable4V[] memory) {\n");
+        self.tab += 1;
+        let code = code.borrow();
+        self.write_line(&format!(
+            "IKosVariable4V[] memory w = new IKosVariable4V[]({});\n",
+            code.wires
+        ));
+        for line in &code.lines {
+            self.write_line(line);
+            self.write("\n");
+        }
+        self.write_line(&format!(
+            "IKosVariable4V[] memory output = new IKosVariable4V[]({});\n",
+            output_len
+        ));
+        for (i, word) in output.iter().enumerate() {
+            self.write_line(&format!("output[{}] = {};\n", i, word.expr));
+        }
+        self.write_line("return output;\n");
+        self.tab -= 1;
+        self.write_line("}\n\n");
+
+        self.write_line("function ");
+        self.write(name);
+        self.write("(\n");
+        self.tab += 1;
+        self.write_line("uint32[] memory input_pub,\n");
+        self.write_line("uint32[] memory output,\n");
+        self.write_line("bytes32 challenge,\n");
+        self.write_line("bytes memory two_views,\n");
+        self.write_line("IKosView[] memory response\n");
+        self.tab -= 1;
+        let layout = Layout::of(function);
+        let writes = !layout.events.is_empty()
+            || layout.storage.iter().any(|entry| entry.new.is_some())
+            || layout.state.iter().any(|state| {
+                state.new.is_some()
+                    || state
+                        .commitment
+                        .iter()
+                        .any(|commitment| commitment.new.is_some())
+            });
+        let mutability = if writes {
+            ""
+        } else if !layout.storage.is_empty() || !layout.state.is_empty() {
+            " view"
+        } else {
+            " pure"
+        };
+        let visibility = match function.visibility {
+            Visibility::External => "external",
+            _ => "public",
+        };
+        self.write_line(&format!(
+            ") {}{} returns (bool) {{\n",
+            visibility, mutability
+        ));
+        self.tab += 1;
+        self.write_line(&format!("require(input_pub.length == {});\n", public_len));
+        self.write_line(&format!("require(output.length == {});\n", output_len));
+        for entry in &layout.params {
+            if entry.is_private {
+                continue;
             }
-            OperationType::Call { func, .. } => {
-                self.write("_");
-                self.write(func.as_str());
-                self.write("(");
+            self.check_canonical(&entry.symbol_type, entry.offset);
+        }
+        if function.has_valid_word() {
+            self.write_line(&format!(
+                "require(output[{}] == 1, \"requirement failed\");\n",
+                output_len - 1 - self.options.checked_arithmetic as usize
+            ));
+        }
+        if self.options.checked_arithmetic {
+            self.write_line(&format!(
+                "require(output[{}] == 1, \"arithmetic overflow\");\n",
+                output_len - 1
+            ));
+        }
+        // Proofs only hold for the state and entries they read.
+        for state in &layout.state {
+            if let Some(commitment) = &state.commitment {
+                self.write_line(&format!(
+                    "require({} == {}, \"stale storage read\");\n",
+                    decode(&commitment.old, "output"),
+                    state.variable
+                ));
+                continue;
             }
-            OperationType::Symbol { symbol } => {
-                // TODO: Use IKOS Variable
-                self.write(symbol.name.as_str());
+            let old = &state.old;
+            self.check_canonical(&old.symbol_type, old.offset);
+            for (path, symbol_type, offset) in
+                state_leaves(&state.variable, &old.symbol_type, old.offset)
+            {
+                let value = format!("decode_{}(input_pub, {})", abi_type(&symbol_type), offset);
+                self.write_line(&format!(
+                    "require({}, \"stale storage read\");\n",
+                    equal(&symbol_type, &value, &path)
+                ));
             }
-            OperationType::Constant { value } => {
-                // TODO: Use IKOS Variable
-                self.write(value.to_string().as_str());
+        }
+        for entry in &layout.storage {
+            self.write_line(&format!("if (output[{}] == 1) {{\n", entry.active));
+            self.tab += 1;
+            let stored = format!("{}[{}]", entry.mapping, decode(&entry.key, "output"));
+            self.write_line(&format!(
+                "require({}, \"stale storage read\");\n",
+                equal(
+                    &entry.old.symbol_type,
+                    &stored,
+                    &decode(&entry.old, "input_pub")
+                )
+            ));
+            self.tab -= 1;
+            self.write_line("}\n");
+        }
+        let verify = format!(
+            "ZKBoo_verify(VerifyingProof({}, input_pub, output, challenge, two_views, response, _circuit_{}))",
+            private_len, name
+        );
+        if !writes {
+            self.write_line(&format!("return {};\n", verify));
+            self.tab -= 1;
+            self.write_line("}\n");
+            return Ok(());
+        }
+        self.write_line(&format!("if (!{}) {{\n", verify));
+        self.tab += 1;
+        self.write_line("return false;\n");
+        self.tab -= 1;
+        self.write_line("}\n");
+        for state in &layout.state {
+            if let Some(new) = state
+                .commitment
+                .as_ref()
+                .and_then(|commitment| commitment.new.as_ref())
+            {
+                self.write_line(&format!(
+                    "{} = {};\n",
+                    state.variable,
+                    decode(new, "output")
+                ));
             }
-            OperationType::Nop => {
-                // Do nothing
+            let new = match &state.new {
+                Some(new) => new,
+                None => continue,
+            };
+            for (path, symbol_type, offset) in
+                state_leaves(&state.variable, &new.symbol_type, new.offset)
+            {
+                self.write_line(&format!(
+                    "{} = decode_{}(output, {});\n",
+                    path,
+                    abi_type(&symbol_type),
+                    offset
+                ));
             }
         }
+        for entry in &layout.storage {
+            let new = match &entry.new {
+                Some(new) => new,
+                None => continue,
+            };
+            self.write_line(&format!("if (output[{}] == 1) {{\n", entry.active));
+            self.tab += 1;
+            self.write_line(&format!(
+                "{}[{}] = {};\n",
+                entry.mapping,
+                decode(&entry.key, "output"),
+                decode(new, "output")
+            ));
+            self.tab -= 1;
+            self.write_line("}\n");
+        }
+        for entry in &layout.events {
+            let args = entry
+                .args
+                .iter()
+                .map(|arg| decode(arg, "output"))
+                .collect::<Vec<String>>();
+            self.write_line(&format!("if (output[{}] == 1) {{\n", entry.active));
+            self.tab += 1;
+            self.write_line(&format!("emit {}({});\n", entry.event, args.join(", ")));
+            self.tab -= 1;
+            self.write_line("}\n");
+        }
+        self.write_line("return true;\n");
+        self.tab -= 1;
+        self.write_line("}\n");
+        Ok(())
+    }
+
+    // Requires the public words of a `symbol_type` value from `offset` in
+    // `input_pub` to be canonical, as the circuit does for private ones.
+    fn check_canonical(&mut self, symbol_type: &SymbolType, offset: usize) {
+        for word in bool_words(symbol_type) {
+            self.write_line(&format!(
+                "require(input_pub[{}] <= 1, \"bool out of range\");\n",
+                offset + word
+            ));
+        }
+        for (word, max_len) in length_words(symbol_type) {
+            self.write_line(&format!(
+                "require(input_pub[{}] <= {}, \"length out of range\");\n",
+                offset + word,
+                max_len
+            ));
+        }
     }
 
     fn write(&mut self, line: &str) {
@@ -137,4 +872,3 @@ impl Verifier {
         self.code.push_str(line);
     }
 }
-*/
@@ -0,0 +1,347 @@
+use crate::encoding::{encode, encode_bounded, from_words, value_bits};
+use crate::error::{self, error_at, RewriteError, RewriteErrorType};
+use crate::interpreter::{execute, Storage};
+use crate::layout::Layout;
+use crate::lowering::{entry_types, CircuitOptions};
+use crate::rewriter::RewriterResult;
+use crate::symbol::{Function, SymbolType};
+use num_bigint::{BigInt, BigUint, Sign};
+use serde_json::Value;
+use std::collections::HashMap;
+use zoker_parser::location::Location;
+
+/// Value given for a parameter, before it is checked against its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputValue {
+    Number(BigInt),
+    Bool(bool),
+    /// Decimal or `0x` hexadecimal number, text of a `string` or `0x`
+    /// hexadecimal `bytes`.
+    Text(String),
+    Array(Vec<InputValue>),
+    /// Field values by field name.
+    Struct(Vec<(String, InputValue)>),
+}
+
+/// Input words of a proof, built from named arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Witness {
+    pub input: Vec<u32>,
+    pub input_pub: Vec<u32>,
+    /// Arguments in declaration order, as `interpret` takes them.
+    pub args: Vec<BigUint>,
+    /// Mapping entries the function runs on, as `execute` takes them.
+    pub storage: Storage,
+    pub layout: Layout,
+}
+
+impl Witness {
+    /// Checks `values` against the parameters of `function` and encodes them.
+    ///
+    /// Every parameter needs a value and every value a parameter. Mappings
+    /// the function accesses are empty.
+    pub fn from_map(
+        function: &Function,
+        values: &HashMap<String, InputValue>,
+    ) -> RewriterResult<Self> {
+        Witness::from_state(function, values, &Storage::new())
+    }
+
+    /// Same as `from_map`, with the mappings the function accesses holding
+    /// `storage`.
+    ///
+    /// The function is run to find the entries it accesses, whose values
+    /// follow the public arguments. Openings of private state follow the
+    /// private ones.
+    pub fn from_state(
+        function: &Function,
+        values: &HashMap<String, InputValue>,
+        storage: &Storage,
+    ) -> RewriterResult<Self> {
+        if let Some(name) = values
+            .keys()
+            .find(|name| function.params.iter().all(|param| &param.name != *name))
+        {
+            return type_error(name, "no parameter has this name");
+        }
+        let mut witness = Witness {
+            input: vec![],
+            input_pub: vec![],
+            args: vec![],
+            storage: storage.clone(),
+            layout: Layout::of(function),
+        };
+        for param in &function.params {
+            let words = match values.get(&param.name) {
+                Some(value) => to_words(&param.name, &param.symbol_type, value)?,
+                None => return type_error(&param.name, "missing value"),
+            };
+            for part in param.symbol_type.parts(&param.name, param.is_private) {
+                let part_words = &words[part.offset..part.offset + part.symbol_type.word_count()];
+                if part.is_private {
+                    witness.input.extend_from_slice(part_words);
+                } else {
+                    witness.input_pub.extend_from_slice(part_words);
+                }
+            }
+            witness.args.push(from_words(&words));
+        }
+        if !function.state.is_empty() || !function.storage.is_empty() {
+            let options = CircuitOptions::default();
+            let execution = execute(function, options, &witness.args, storage)?;
+            for entry in execution.state {
+                match (entry.salt, entry.new_salt) {
+                    (Some(salt), new_salt) => {
+                        witness.input.extend(entry.old.words());
+                        witness.input.extend(salt.words());
+                        if let Some(new_salt) = new_salt {
+                            witness.input.extend(new_salt.words());
+                        }
+                    }
+                    (None, _) => witness.input_pub.extend(entry.old.words()),
+                }
+            }
+            for entry in execution.storage {
+                witness.input_pub.extend(entry.old.words());
+            }
+        }
+        Ok(witness)
+    }
+
+    /// Same as `from_state`, from a JSON object mapping names to numbers,
+    /// booleans, numeric strings, or arrays and objects of those; an object
+    /// gives the fields of a struct by name.
+    ///
+    /// Its `storage` member, if any, maps state variable names to values
+    /// and mapping names to objects from keys to values, like
+    /// `{"total": 15, "balances": {"0x1f": 10}}`. A private state variable
+    /// maps to its opening and the salt of its next commitment, like
+    /// `{"value": 10, "salt": "0x2a", "new_salt": "0x2b"}`. State the
+    /// function does not use is ignored.
+    pub fn from_json(function: &Function, json: &str) -> RewriterResult<Self> {
+        let members = match serde_json::from_str(json) {
+            Ok(Value::Object(members)) => members,
+            Ok(_) => return Err(witness_error("witness must be a JSON object".to_string())),
+            Err(err) => return Err(witness_error(err.to_string())),
+        };
+        let mut values = HashMap::new();
+        let mut storage = Storage::new();
+        for (name, value) in members {
+            if name == "storage" {
+                storage = storage_values(function, value)?;
+                continue;
+            }
+            let value = input_value(&name, value)?;
+            values.insert(name, value);
+        }
+        Witness::from_state(function, &values, &storage)
+    }
+}
+
+fn storage_values(function: &Function, value: Value) -> RewriterResult<Storage> {
+    let mappings = match value {
+        Value::Object(mappings) => mappings,
+        _ => return type_error("storage", "expected an object"),
+    };
+    let mut storage = Storage::new();
+    for (name, entries) in mappings {
+        let state = function
+            .state
+            .iter()
+            .find(|state| state.variable.name == name);
+        if let Some(state) = state {
+            let symbol_type = &state.variable.symbol_type;
+            if !state.variable.is_private {
+                let words = to_words(&name, symbol_type, &input_value(&name, entries)?)?;
+                storage.variables.insert(name, from_words(&words));
+                continue;
+            }
+            let members = match entries {
+                Value::Object(members) => members,
+                _ => return type_error(&name, "expected an object"),
+            };
+            let mut salts = (BigUint::from(0u32), BigUint::from(0u32));
+            for (member, value) in members {
+                let value = input_value(&name, value)?;
+                match member.as_str() {
+                    "value" => {
+                        let words = to_words(&name, symbol_type, &value)?;
+                        storage.variables.insert(name.clone(), from_words(&words));
+                    }
+                    "salt" => salts.0 = to_number(&name, &SymbolType::Bytes32, &value)?,
+                    "new_salt" => salts.1 = to_number(&name, &SymbolType::Bytes32, &value)?,
+                    _ => return type_error(&name, "expected value, salt and new_salt"),
+                }
+            }
+            storage.salts.insert(name, salts);
+            continue;
+        }
+        let access = function
+            .storage
+            .iter()
+            .find(|access| access.mapping.name == name);
+        let (key_type, value_type) = match access {
+            Some(access) => entry_types(access),
+            None => continue,
+        };
+        let entries = match entries {
+            Value::Object(entries) => entries,
+            _ => return type_error(&name, "expected an object"),
+        };
+        let mut values = HashMap::new();
+        for (key, value) in entries {
+            let key = match key.as_str() {
+                "true" => InputValue::Bool(true),
+                "false" => InputValue::Bool(false),
+                _ => InputValue::Text(key),
+            };
+            let key = to_number(&name, key_type, &key)?;
+            let value = to_number(&name, value_type, &input_value(&name, value)?)?;
+            values.insert(key, value);
+        }
+        storage.mappings.insert(name, values);
+    }
+    Ok(storage)
+}
+
+fn input_value(name: &str, value: Value) -> RewriterResult<InputValue> {
+    match value {
+        Value::Bool(b) => Ok(InputValue::Bool(b)),
+        Value::Number(n) => Ok(InputValue::Text(n.to_string())),
+        Value::String(s) => Ok(InputValue::Text(s)),
+        Value::Array(items) => Ok(InputValue::Array(
+            items
+                .into_iter()
+                .map(|item| input_value(name, item))
+                .collect::<RewriterResult<_>>()?,
+        )),
+        Value::Object(members) => Ok(InputValue::Struct(
+            members
+                .into_iter()
+                .map(|(field, value)| Ok((field, input_value(name, value)?)))
+                .collect::<RewriterResult<_>>()?,
+        )),
+        _ => type_error(
+            name,
+            "expected a number, a boolean, a string, an array or an object",
+        ),
+    }
+}
+
+// Witnesses are not source code, so their errors have no location.
+fn witness_error(message: String) -> RewriteError {
+    error_at(RewriteErrorType::SyntaxError(message), Location::default())
+}
+
+fn type_error<V>(name: &str, message: &str) -> RewriterResult<V> {
+    error::type_error(&format!("`{}`: {}", name, message), Location::default())
+}
+
+fn parse_number(text: &str) -> Option<BigInt> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16)?,
+        None => BigUint::parse_bytes(digits.as_bytes(), 10)?,
+    };
+    let sign = if negative { Sign::Minus } else { Sign::Plus };
+    Some(BigInt::from_biguint(sign, magnitude))
+}
+
+fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    let hex = text.strip_prefix("0x")?;
+    if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Words of a parameter of type `symbol_type` holding `value`.
+fn to_words(name: &str, symbol_type: &SymbolType, value: &InputValue) -> RewriterResult<Vec<u32>> {
+    if let Some(structure) = symbol_type.as_struct() {
+        let fields = match value {
+            InputValue::Struct(fields) => fields,
+            _ => return type_error(name, "expected an object"),
+        };
+        if let Some((field, _)) = fields
+            .iter()
+            .find(|(field, _)| structure.field(field).is_none())
+        {
+            return type_error(&format!("{}.{}", name, field), "no field has this name");
+        }
+        let mut words = vec![];
+        for field in &structure.fields {
+            let path = format!("{}.{}", name, field.name);
+            match fields.iter().find(|(name, _)| *name == field.name) {
+                Some((_, value)) => words.extend(to_words(&path, &field.symbol_type, value)?),
+                None => return type_error(&path, "missing value"),
+            }
+        }
+        return Ok(words);
+    }
+    if let Some(max_len) = symbol_type.max_len() {
+        let bytes = match (symbol_type, value) {
+            (SymbolType::BoundedString(_), InputValue::Text(text)) => text.as_bytes().to_vec(),
+            (_, InputValue::Text(text)) => match parse_bytes(text) {
+                Some(bytes) => bytes,
+                None => return type_error(name, "expected `0x` and pairs of hex digits"),
+            },
+            _ => return type_error(name, "expected a string"),
+        };
+        if bytes.len() > max_len {
+            let message = format!("{} bytes do not fit in {}", bytes.len(), symbol_type);
+            return type_error(name, &message);
+        }
+        return encode_bounded(&bytes, max_len);
+    }
+    let ((element, size), items) = match (symbol_type.as_array(), value) {
+        (Some(array), InputValue::Array(items)) => (array, items),
+        (Some(_), _) => return type_error(name, "expected an array"),
+        (None, _) => return encode(symbol_type, &to_number(name, symbol_type, value)?),
+    };
+    if items.len() != size {
+        let message = format!("expected {} elements, found {}", size, items.len());
+        return type_error(name, &message);
+    }
+    let mut words = vec![];
+    for item in items {
+        words.extend(to_words(name, element, item)?);
+    }
+    Ok(words)
+}
+
+/// The unsigned value a parameter of type `symbol_type` holds for `value`.
+fn to_number(name: &str, symbol_type: &SymbolType, value: &InputValue) -> RewriterResult<BigUint> {
+    let number = match value {
+        InputValue::Bool(b) if *symbol_type == SymbolType::Bool => BigInt::from(*b as u32),
+        InputValue::Bool(_) => return type_error(name, "expected a number"),
+        InputValue::Array(_) | InputValue::Struct(_) => {
+            return type_error(name, "expected a single value")
+        }
+        InputValue::Number(n) => n.clone(),
+        InputValue::Text(text) => match parse_number(text) {
+            Some(n) => n,
+            None => return type_error(name, "expected a number"),
+        },
+    };
+    let bits = match value_bits(symbol_type) {
+        Some(bits) => bits,
+        None => return type_error(name, "type has no circuit encoding"),
+    };
+    let modulus = BigInt::from(1u32) << bits;
+    let (min, max) = match symbol_type {
+        SymbolType::Int256 => (-(&modulus >> 1), &modulus >> 1),
+        _ => (BigInt::from(0u32), modulus.clone()),
+    };
+    if number < min || number >= max {
+        return type_error(name, &format!("value does not fit in {}", symbol_type));
+    }
+    // Negative `int`s in two's complement.
+    let number = (number + &modulus) % &modulus;
+    Ok(number.to_biguint().unwrap())
+}
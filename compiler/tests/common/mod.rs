@@ -0,0 +1,69 @@
+// Helpers shared by the integration tests, each of which uses only some.
+#![allow(dead_code)]
+
+use zoker_compiler::circuit::{Circuit, Wire};
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::execute;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::symbol::{Contract, Function};
+use zoker_compiler::witness::Witness;
+use zoker_parser::parser;
+use zoker_prover::ikos::IKosVariable;
+
+pub fn compile(source: &str) -> Vec<Contract> {
+    let program = parser::parse_program(source).unwrap();
+    rewrite_program(&program).unwrap()
+}
+
+// The first function of the first contract of `source`.
+pub fn compile_function(source: &str) -> Function {
+    compile(source)[0].functions[0].clone()
+}
+
+pub fn error(source: &str) -> RewriteErrorType {
+    let program = parser::parse_program(source).unwrap();
+    rewrite_program(&program).unwrap_err().error
+}
+
+// Words of `value` as a uint.
+pub fn uint(value: u32) -> Vec<u32> {
+    let mut words = vec![0; 7];
+    words.push(value);
+    words
+}
+
+// Output words of `circuit` on constant inputs.
+pub fn evaluate(circuit: &Circuit, input: &[u32], input_pub: &[u32]) -> Vec<u32> {
+    let wires = |words: &[u32]| {
+        words
+            .iter()
+            .cloned()
+            .map(Wire::new_value)
+            .collect::<Vec<_>>()
+    };
+    circuit
+        .evaluate(&wires(input), &wires(input_pub))
+        .unwrap()
+        .into_iter()
+        .map(|word| match word {
+            Wire::Const(value) => value,
+            Wire::Gate(..) => panic!("output depends on no input"),
+        })
+        .collect()
+}
+
+// Runs `function` on a witness both as a circuit and in the interpreter,
+// and returns the output words.
+pub fn run(function: &Function, json: &str) -> Vec<u32> {
+    run_with(function, CircuitOptions::default(), json)
+}
+
+pub fn run_with(function: &Function, options: CircuitOptions, json: &str) -> Vec<u32> {
+    let witness = Witness::from_json(function, json).unwrap();
+    let execution = execute(function, options, &witness.args, &witness.storage).unwrap();
+    let circuit = Circuit::from_function(function, options).unwrap();
+    let output = evaluate(&circuit, &witness.input, &witness.input_pub);
+    assert_eq!(execution.output_words(), output, "{}", json);
+    output
+}
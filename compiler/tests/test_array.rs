@@ -0,0 +1,210 @@
+mod common;
+
+use common::{compile_function, run_with};
+use num_bigint::BigUint;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::encoding::{encode, from_words};
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::layout::abi_type;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::symbol::SymbolType;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+use zoker_parser::parser;
+
+fn number(words: &[u32]) -> u32 {
+    assert!(words[..words.len() - 1].iter().all(|&word| word == 0));
+    words[words.len() - 1]
+}
+
+#[test]
+fn test_array_indexing() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint[4] xs, uint i) returns (uint) {\
+             uint j = 2;\
+             return reveal(xs[i] * 10 + xs[j] + xs[3]);\
+           }\
+        }",
+    );
+    let options = CircuitOptions::default();
+    let xs = "[1, 2, 3, 4]";
+    for i in 0..4 {
+        let output = run_with(
+            &function,
+            options,
+            &format!("{{\"xs\": {}, \"i\": {}}}", xs, i),
+        );
+        assert_eq!(number(&output), (i + 1) * 10 + 3 + 4);
+    }
+    // A private index out of range reads zero, and clears `ok` when checked.
+    let json = format!("{{\"xs\": {}, \"i\": 4}}", xs);
+    assert_eq!(number(&run_with(&function, options, &json)), 7);
+    let checked = CircuitOptions {
+        checked_arithmetic: true,
+        ..options
+    };
+    let output = run_with(&function, checked, &json);
+    assert_eq!(output[8], 0);
+}
+
+#[test]
+fn test_array_set_membership() {
+    let function = compile_function(
+        "contract Test {\
+           function member(private uint[5] set, private uint x) returns (bool) {\
+             uint found = 0;\
+             for s in set {\
+               if s == x {\
+                 found = 1;\
+               };\
+             };\
+             return reveal(found);\
+           }\
+        }",
+    );
+    let set = "[\"0x10\", 7, 42, 0, 99]";
+    for (x, member) in [(42, 1), (16, 1), (8, 0), (99, 1)].iter() {
+        let json = format!("{{\"set\": {}, \"x\": {}}}", set, x);
+        let output = run_with(&function, CircuitOptions::default(), &json);
+        assert_eq!(output, vec![*member]);
+    }
+}
+
+#[test]
+fn test_array_assignment() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint[3] xs, uint i, private address a) returns (uint) {\
+             address[2][2] m;\
+             m[1][i % 2] = a;\
+             xs[i] = xs[i] + m[1][1];\
+             xs[0] = 5;\
+             uint sum = 0;\
+             for x in xs {\
+               sum = sum + x;\
+             };\
+             return reveal(sum);\
+           }\
+        }",
+    );
+    let options = CircuitOptions::default();
+    let json = |i: u32| format!("{{\"xs\": [1, 2, 3], \"i\": {}, \"a\": 100}}", i);
+    assert_eq!(number(&run_with(&function, options, &json(1))), 5 + 102 + 3);
+    assert_eq!(number(&run_with(&function, options, &json(2))), 5 + 2 + 3);
+    assert_eq!(number(&run_with(&function, options, &json(0))), 5 + 2 + 3);
+}
+
+#[test]
+fn test_array_errors() {
+    let error = |source: &str| {
+        let function = compile_function(source);
+        Circuit::from_function(&function, CircuitOptions::default())
+            .unwrap_err()
+            .error
+    };
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint[4] xs) returns (uint) {\
+                 return reveal(xs[4]);\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("array index out of bounds".to_string())
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint[4] xs) returns (uint) {\
+                 return reveal(xs + 1);\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("arithmetic on an array".to_string())
+    );
+    let program = parser::parse_program(
+        "contract Test {\
+           function f(uint a) returns (uint) {\
+             for x in a {\
+               a = x;\
+             };\
+             return a;\
+           }\
+        }",
+    )
+    .unwrap();
+    assert_eq!(
+        rewrite_program(&program).unwrap_err().error,
+        RewriteErrorType::TypeError("`for` iterates over an array".to_string())
+    );
+}
+
+#[test]
+fn test_array_encoding() {
+    let flags = SymbolType::Array(Box::new(SymbolType::Bool), 3);
+    assert_eq!(abi_type(&flags), "bool[3]");
+    assert_eq!(flags.word_count(), 3);
+    assert_eq!(
+        encode(&flags, &from_words(&[1, 0, 1])).unwrap(),
+        vec![1, 0, 1]
+    );
+    assert!(encode(&flags, &from_words(&[1, 2, 1])).is_err());
+
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint[2] xs, bool[2] flags) returns (uint) {\
+             return reveal(xs[1]);\
+           }\
+        }",
+    );
+    let witness =
+        Witness::from_json(&function, "{\"xs\": [1, 2], \"flags\": [true, false]}").unwrap();
+    assert_eq!(
+        witness.input,
+        vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2]
+    );
+    assert_eq!(witness.input_pub, vec![1, 0]);
+    assert_eq!(witness.args[1], BigUint::from(1u32) << 32);
+    assert!(Witness::from_json(&function, "{\"xs\": [1], \"flags\": [true, false]}").is_err());
+
+    let program = parser::parse_program(
+        "contract Test {\
+           function f(private uint[2] xs, bool[2] flags) returns (uint) {\
+             return reveal(xs[1]);\
+           }\
+        }",
+    )
+    .unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("require(input_pub[1] <= 1, \"bool out of range\");"));
+}
+
+#[test]
+fn test_array_call() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint[2] xs, uint i) returns (uint) {\
+             uint[2] ys = swap(xs);\
+             return ys[i];\
+           }\
+           function swap(private uint[2] xs) returns (uint[2]) {\
+             uint t = xs[0];\
+             xs[0] = xs[1];\
+             xs[1] = t;\
+             return reveal(xs);\
+           }\
+        }",
+    );
+    let options = CircuitOptions::default();
+    assert_eq!(
+        number(&run_with(&function, options, "{\"xs\": [3, 4], \"i\": 0}")),
+        4
+    );
+    assert_eq!(
+        number(&run_with(&function, options, "{\"xs\": [3, 4], \"i\": 1}")),
+        3
+    );
+}
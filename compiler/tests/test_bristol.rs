@@ -0,0 +1,113 @@
+mod common;
+
+use common::{compile_function, evaluate};
+use zoker_compiler::bristol::BristolCircuit;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::prover::{prover_circuit, verifier_circuit};
+use zoker_prover::zkboo::{ProvingProof, VerifyingProof, ZkBoo};
+
+fn prove_and_verify(circuit: &Circuit, input: Vec<u32>, input_pub: Vec<u32>) -> Vec<u32> {
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+    let mut res = zk_boo
+        .prove(ProvingProof::new(
+            input.clone(),
+            input_pub.clone(),
+            circuit.outputs.len(),
+            prover_circuit(circuit),
+        ))
+        .unwrap();
+    let output = res.output.clone();
+    let challenge = ZkBoo::query_random_oracle(
+        res.input_len,
+        res.output_len,
+        &res.out_data,
+        &res.three_views,
+    );
+    let response = zk_boo.build_response(&res.views, &challenge);
+    let two_views = zk_boo.rebuild_proof(&mut res, &challenge);
+    let verified = zk_boo.verify(VerifyingProof::new(
+        input.len(),
+        input_pub,
+        output.clone(),
+        challenge,
+        two_views,
+        response,
+        verifier_circuit(circuit),
+    ));
+    assert!(verified.unwrap());
+    output
+}
+
+// (a & b) ^ !c over 2-bit values.
+const CIRCUIT: &str = "6 13
+3 2 2 2
+1 2
+
+4 2 0 1 2 3 6 7 MAND
+1 1 4 8 INV
+1 1 1 9 EQ
+2 1 5 9 10 XOR
+2 1 6 8 11 XOR
+2 1 7 10 12 XOR
+";
+
+#[test]
+fn test_bristol_import_and_prove() {
+    let bristol = BristolCircuit::parse(CIRCUIT).unwrap();
+    assert_eq!(bristol.inputs, vec![2, 2, 2]);
+    assert_eq!(bristol.outputs, vec![2]);
+    let circuit = bristol.to_circuit(&[true, true, false]).unwrap();
+    assert_eq!((circuit.input_len, circuit.input_pub_len), (2, 1));
+    // Both AND gates share one word.
+    assert_eq!(circuit.gate_count().and, 1);
+
+    for &(a, b, c) in [(3, 1, 0), (2, 3, 1), (3, 3, 3), (0, 0, 2)].iter() {
+        let output = prove_and_verify(&circuit, vec![a, b], vec![c]);
+        assert_eq!(output, vec![(a & b) ^ (!c & 3)]);
+    }
+}
+
+#[test]
+fn test_bristol_rejects_unset_wire() {
+    let source = "1 3\n1 1\n1 1\n\n2 1 0 1 2 XOR\n";
+    let bristol = BristolCircuit::parse(source).unwrap();
+    assert!(bristol.to_circuit(&[true]).is_err());
+    assert!(BristolCircuit::parse("1 3\n1 1\n1 1\n\n2 1 0 1 2 OR\n").is_err());
+}
+
+#[test]
+fn test_bristol_export_round_trip() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             uint c = a + (b << 3);\
+             if a < b {\
+               c = b - a;\
+             };\
+             return reveal(c);\
+           }\
+        }",
+    );
+    let options = CircuitOptions {
+        optimize: true,
+        ..CircuitOptions::default()
+    };
+    let circuit = Circuit::from_function(&function, options).unwrap();
+    let bristol = BristolCircuit::from_function(&function, options).unwrap();
+    assert_eq!(bristol.inputs, vec![256, 256]);
+    assert_eq!(bristol.outputs, vec![256]);
+
+    let imported = BristolCircuit::parse(&bristol.to_string())
+        .unwrap()
+        .to_circuit(&[true, false])
+        .unwrap();
+    let cases = [
+        (vec![0, 0, 0, 0, 0, 0, 1, 5], vec![0, 0, 0, 0, 0, 0, 2, 7]),
+        (vec![9, 0, 0, 0, 0, 0, 0, 1], vec![u32::MAX; 8]),
+        (vec![u32::MAX; 8], vec![0, 0, 0, 0, 0, 0, 0, 3]),
+    ];
+    for (a, b) in cases.iter() {
+        assert_eq!(evaluate(&imported, a, b), evaluate(&circuit, a, b));
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::compile_function;
+use zoker_compiler::circuit::{Circuit, Gate};
+use zoker_compiler::lowering::{lower_function, CircuitOptions};
+use zoker_prover::ikos::IKosVariable4P;
+
+fn constants(words: &[u32]) -> Vec<IKosVariable4P> {
+    words
+        .iter()
+        .map(|&w| IKosVariable4P::new_value(w))
+        .collect()
+}
+
+fn values(words: &[IKosVariable4P]) -> Vec<u32> {
+    words.iter().map(|word| word.value[0]).collect()
+}
+
+#[test]
+fn test_circuit_inputs() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, uint b, private bool c) returns (bool) {\
+             return reveal(c);\
+           }\
+        }",
+    );
+    let circuit = Circuit::from_function(&function, CircuitOptions::default()).unwrap();
+    assert_eq!(circuit.input_len, 9);
+    assert_eq!(circuit.input_pub_len, 8);
+    assert_eq!(
+        circuit.gates[8],
+        Gate::Input {
+            index: 8,
+            is_private: true
+        }
+    );
+    assert_eq!(
+        circuit.gates[9],
+        Gate::Input {
+            index: 0,
+            is_private: false
+        }
+    );
+    // The private `bool` word is output as is, followed by the valid
+    // word checking it is 0 or 1.
+    assert_eq!(circuit.outputs.len(), 2);
+    assert_eq!(circuit.outputs[0], 8);
+}
+
+#[test]
+fn test_circuit_is_topologically_ordered() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             return reveal(a * b + (a >> 3));\
+           }\
+        }",
+    );
+    let circuit = Circuit::from_function(&function, CircuitOptions::default()).unwrap();
+    for (id, gate) in circuit.gates.iter().enumerate() {
+        assert!(gate.operands().iter().all(|&operand| operand < id));
+    }
+}
+
+#[test]
+fn test_circuit_matches_direct_lowering() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, int b) returns (bytes32) {\
+             uint c;\
+             if b < 0 {\
+               c = a * 3;\
+             } else {\
+               c = a / 7;\
+             };\
+             return sha256(c, b >> 5);\
+           }\
+        }",
+    );
+    let options = CircuitOptions {
+        checked_arithmetic: true,
+        ..CircuitOptions::default()
+    };
+    let circuit = Circuit::from_function(&function, options).unwrap();
+    let input = constants(&[1, 2, 3, 4, 5, 6, 7, 0x89ab_cdef]);
+    for &sign in [0u32, 0x8000_0000].iter() {
+        let input_pub = constants(&[sign, 0, 0, 0, 0, 0, 0, 77]);
+        let expected = lower_function(&function, options, &input, &input_pub).unwrap();
+        let output = circuit.evaluate(&input, &input_pub).unwrap();
+        assert_eq!(output.len(), 9);
+        assert_eq!(values(&output), values(&expected));
+    }
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use common::{compile, error};
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use num_bigint::BigUint;
+use std::process::{self, Command};
+use std::{env, fs};
+use zoker_compiler::encoding::to_words;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::{commitment, execute, Value};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::symbol::SymbolType;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+
+const TOKEN: &str = "contract Token {\
+       event Mint(address to, uint amount);\
+       uint total = 10 * 3;\
+       int floor = -5;\
+       bool open = 1 < 2;\
+       address admin = 7;\
+       mapping(address => uint) balances;\
+       constructor(address to, uint amount) {\
+         uint minted = amount * 2 + 1;\
+         require(minted > amount);\
+         balances[to] = minted;\
+         total = total + minted;\
+         if amount > 100 {\
+           emit Mint(to, amount);\
+         } else {\
+           admin = to;\
+         };\
+       }\
+       function supply() returns (uint) {\
+         return total;\
+       }\
+    }";
+
+fn verifier_error(source: &str) -> RewriteErrorType {
+    rewrite_verifier(&compile(source), CircuitOptions::default())
+        .unwrap_err()
+        .error
+}
+
+#[test]
+fn test_constructor_verifier() {
+    let contracts = compile(TOKEN);
+    // The constructor is not an entry point.
+    assert_eq!(contracts[0].functions.len(), 1);
+    let constructor = contracts[0].constructor.as_ref().unwrap();
+    assert_eq!(constructor.params.len(), 2);
+
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("    uint256 public total = 30;\n"));
+    assert!(code.contains("    int256 public floor = -5;\n"));
+    assert!(code.contains("    bool public open = true;\n"));
+    assert!(code.contains("    address public admin = address(uint160(7));\n"));
+    assert!(code.contains("    mapping(address => uint256) public balances;\n"));
+    assert!(code.contains(
+        "    event Mint(address to, uint256 amount);\n\n\
+         \x20   constructor(address to, uint256 amount) {\n\
+         \x20       uint256 minted;\n\
+         \x20       minted = (amount * 2) + 1;\n\
+         \x20       require(minted > amount);\n\
+         \x20       balances[to] = minted;\n\
+         \x20       total = total + minted;\n\
+         \x20       if (amount > 100) {\n\
+         \x20           emit Mint(to, amount);\n\
+         \x20       }\n\
+         \x20       else {\n\
+         \x20           admin = to;\n\
+         \x20       }\n\
+         \x20   }\n\n\
+         \x20   function _circuit_supply("
+    ));
+}
+
+#[test]
+fn test_initial_commitment() {
+    let contracts = compile(
+        "contract Vault {\
+           private uint balance = 42;\
+           function check(uint limit) returns (bool) {\
+             return reveal(balance >= limit);\
+           }\
+        }",
+    );
+    assert_eq!(contracts[0].initial[0].to_string(), "42");
+    // The first proof opens the deployed commitment with a zero salt.
+    let check = &contracts[0].functions[0];
+    let json = "{\"limit\": 40, \"storage\": {\"balance\": {\"value\": 42}}}";
+    let witness = Witness::from_json(check, json).unwrap();
+    let execution = execute(
+        check,
+        CircuitOptions::default(),
+        &witness.args,
+        &witness.storage,
+    )
+    .unwrap();
+    let initial = execution.state[0].commitments()[0].value.clone();
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains(&format!("bytes32 public balance = 0x{:064x};", initial)));
+}
+
+#[test]
+fn test_constructor_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    assert_eq!(
+        error("contract Test { constructor(private uint a) { } }"),
+        type_error("constructor parameters are public")
+    );
+    assert_eq!(
+        error("contract Test { constructor(uint[2] a) { } }"),
+        type_error("constructor parameters must be value types, found uint[2]")
+    );
+    assert_eq!(
+        error("contract Test { constructor() { } constructor() { } }"),
+        type_error("the constructor is declared twice")
+    );
+    assert_eq!(
+        error("contract Test { uint a; uint b = a + 1; }"),
+        type_error("default of `b` must be a constant")
+    );
+    assert_eq!(
+        error("contract Test { uint[2] a = 1; }"),
+        type_error("only value types have defaults, found uint[2]")
+    );
+    assert_eq!(
+        error("contract Test { mapping(uint => uint) m = 1; }"),
+        type_error("mapping `m` cannot have a default")
+    );
+    assert_eq!(
+        verifier_error(
+            "contract Test {\
+               private uint[2] secret;\
+               constructor(uint a) {\
+                 secret[0] = a;\
+               }\
+            }"
+        ),
+        type_error("the constructor cannot use private state `secret`; give it a default")
+    );
+    assert_eq!(
+        verifier_error(
+            "contract Test {\
+               constructor(uint a) {\
+                 private uint b = a;\
+               }\
+            }"
+        ),
+        type_error("the constructor runs in public; `b` cannot be private")
+    );
+    assert_eq!(
+        verifier_error(
+            "contract Test {\
+               uint total;\
+               function twice(uint a) returns (uint) {\
+                 return a * 2;\
+               }\
+               constructor(uint a) {\
+                 total = twice(a);\
+               }\
+            }"
+        ),
+        type_error("the constructor cannot call `twice`")
+    );
+    assert_eq!(
+        verifier_error(
+            "contract Test {\
+               bytes32 seed;\
+               constructor(uint a) {\
+                 seed = sha256(a);\
+               }\
+            }"
+        ),
+        RewriteErrorType::UnsupportedError
+    );
+}
+
+const VAULT: &str = "contract Vault {\
+       private uint balance = 42;\
+       private bool open;\
+       private address owner;\
+       constructor(uint deposit, bool flag) {\
+         balance = balance + deposit;\
+         if flag {\
+           open = flag;\
+         };\
+       }\
+       function check(uint limit) returns (bool) {\
+         return reveal(balance >= limit);\
+       }\
+    }";
+
+#[test]
+fn test_constructor_opening() {
+    let contracts = compile(VAULT);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains(
+        "    constructor(uint256 deposit, bool flag, bytes32 balance_salt, bytes32 open_salt) {\n\
+         \x20       uint256 _balance = 42;\n\
+         \x20       bool _open;\n\
+         \x20       _balance = _balance + deposit;\n\
+         \x20       if (flag) {\n\
+         \x20           _open = flag;\n\
+         \x20       }\n\
+         \x20       balance = sha256(abi.encodePacked(_balance, balance_salt));\n\
+         \x20       open = sha256(abi.encodePacked(uint32(_open ? 1 : 0), open_salt));\n\
+         \x20   }\n"
+    ));
+
+    // The packed values are the words the circuit commits to.
+    let salt = Value::new(SymbolType::Bytes32, BigUint::from(7u32));
+    let packed = |value: &[u8]| {
+        let mut hasher = Sha256::new();
+        hasher.input(value);
+        hasher.input(
+            &to_words(&salt.value, 8)
+                .iter()
+                .flat_map(|w| w.to_be_bytes().to_vec())
+                .collect::<Vec<u8>>(),
+        );
+        let mut digest = [0; 32];
+        hasher.result(&mut digest);
+        BigUint::from_bytes_be(&digest)
+    };
+    let mut balance = vec![0; 31];
+    balance.push(50);
+    let value = Value::new(SymbolType::Uint256, BigUint::from(50u32));
+    assert_eq!(commitment(&value, &salt).value, packed(&balance));
+    let value = Value::new(SymbolType::Bool, BigUint::from(1u32));
+    assert_eq!(commitment(&value, &salt).value, packed(&[0, 0, 0, 1]));
+
+    assert_eq!(
+        verifier_error(
+            "contract Test {\
+               private uint secret;\
+               constructor(uint secret_salt) {\
+                 secret = secret_salt;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("the constructor declares `secret_salt` twice".to_string())
+    );
+}
+
+// Compiles the verifiers of the contracts with `solc`, if it is installed.
+#[test]
+fn test_constructor_solc() {
+    if Command::new("solc").arg("--version").output().is_err() {
+        eprintln!("solc is not installed, skipping");
+        return;
+    }
+    let dir = env::temp_dir().join(format!("zoker-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let zkboo = concat!(env!("CARGO_MANIFEST_DIR"), "/src/zkboo.sol");
+    fs::copy(zkboo, dir.join("zkboo.sol")).unwrap();
+    for (name, source) in &[("Token", TOKEN), ("Vault", VAULT)] {
+        let code = rewrite_verifier(&compile(source), CircuitOptions::default()).unwrap();
+        let file = format!("{}.sol", name);
+        fs::write(dir.join(&file), code).unwrap();
+        let output = Command::new("solc")
+            .arg("--bin")
+            .arg(&file)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
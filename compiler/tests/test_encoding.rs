@@ -0,0 +1,149 @@
+mod common;
+
+use common::{compile_function, evaluate, run};
+use num_bigint::BigUint;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::encoding::{
+    decode, decode_bounded, decode_bytes, decode_outputs, decode_string, encode, encode_bounded,
+    encode_bytes, encode_string,
+};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::symbol::SymbolType;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_parser::parser;
+
+#[test]
+fn test_encoding_round_trip() {
+    let max = |bits: usize| (BigUint::from(1u32) << bits) - BigUint::from(1u32);
+    let cases = vec![
+        (SymbolType::Uint256, max(256), 8),
+        (SymbolType::Int256, max(255), 8),
+        (SymbolType::Bytes32, BigUint::from(0xdeadbeefu32), 8),
+        (SymbolType::Address, max(160), 5),
+        (SymbolType::Bool, BigUint::from(1u32), 1),
+    ];
+    for (symbol_type, value, words) in cases {
+        let encoded = encode(&symbol_type, &value).unwrap();
+        assert_eq!(encoded.len(), words);
+        assert_eq!(decode(&symbol_type, &encoded).unwrap(), value);
+    }
+    assert_eq!(
+        encode(&SymbolType::Address, &BigUint::from(0x1234u32)).unwrap(),
+        vec![0, 0, 0, 0, 0x1234]
+    );
+}
+
+#[test]
+fn test_encoding_rejects_non_canonical_values() {
+    let two = BigUint::from(2u32);
+    assert!(encode(&SymbolType::Bool, &two).is_err());
+    assert!(encode(&SymbolType::Address, &(BigUint::from(1u32) << 160)).is_err());
+    assert!(encode(&SymbolType::String, &two).is_err());
+    assert!(decode(&SymbolType::Bool, &[2]).is_err());
+    assert!(decode(&SymbolType::Uint256, &[0; 5]).is_err());
+}
+
+#[test]
+fn test_encoding_bytes() {
+    let words = encode_bytes(&[0x01, 0x02, 0x03, 0x04, 0x05]);
+    let mut expected = vec![5, 0x0102_0304, 0x0500_0000];
+    expected.resize(9, 0);
+    assert_eq!(words, expected);
+    assert_eq!(decode_bytes(&words).unwrap(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(encode_bytes(&[]), vec![0]);
+
+    let mut padded = words.clone();
+    padded[2] |= 1;
+    assert!(decode_bytes(&padded).is_err());
+    assert!(decode_bytes(&words[..8]).is_err());
+
+    let words = encode_string("zoker");
+    assert_eq!(decode_string(&words).unwrap(), "zoker");
+}
+
+#[test]
+fn test_encoding_decode_outputs() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (uint) {\
+             return reveal(a);\
+           }\
+        }",
+    );
+    let mut output = encode(&SymbolType::Uint256, &BigUint::from(7u32)).unwrap();
+    // A trailing `ok` word is ignored.
+    output.push(1);
+    let values = decode_outputs(&function, &output).unwrap();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].value, BigUint::from(7u32));
+    assert!(decode_outputs(&function, &output[..7]).is_err());
+}
+
+#[test]
+fn test_encoding_bool_inputs() {
+    let program = parser::parse_program(
+        "contract Test {\
+           function f(private bool a, bool b) returns (bool) {\
+             return reveal(a);\
+           }\
+        }",
+    )
+    .unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let function = &contracts[0].functions[0];
+    let circuit = Circuit::from_function(function, CircuitOptions::default()).unwrap();
+    // A private `bool` word above 1 makes the proof invalid.
+    assert_eq!(evaluate(&circuit, &[1], &[1]), vec![1, 1]);
+    assert_eq!(evaluate(&circuit, &[3], &[1]), vec![3, 0]);
+
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("require(input_pub[0] <= 1, \"bool out of range\");"));
+}
+
+#[test]
+fn test_encoding_bounded() {
+    let words = encode_bounded(b"zoker", 40).unwrap();
+    assert_eq!(words.len(), 17);
+    assert_eq!(words[..9].to_vec(), encode_bytes(b"zoker"));
+    assert_eq!(decode_bounded(&words, 40).unwrap(), b"zoker".to_vec());
+    assert!(encode_bounded(&[0; 41], 40).is_err());
+
+    let mut long = words.clone();
+    long[0] = 41;
+    assert!(decode_bounded(&long, 40).is_err());
+    let mut padded = words.clone();
+    padded[16] = 1;
+    assert!(decode_bounded(&padded, 40).is_err());
+    assert!(decode_bounded(&words[..9], 40).is_err());
+
+    let symbol_type = SymbolType::BoundedString(40);
+    assert_eq!(symbol_type.word_count(), 17);
+    assert_eq!(symbol_type.to_string(), "string<40>");
+}
+
+#[test]
+fn test_encoding_bounded_inputs() {
+    let source = "contract Test {\
+           function f(private string<40> s, bytes<4> b) returns (string<40>) {\
+             return reveal(s);\
+           }\
+        }";
+    let function = compile_function(source);
+    let output = run(&function, "{\"s\": \"zoker\", \"b\": \"0x0102\"}");
+    let mut expected = encode_bounded(b"zoker", 40).unwrap();
+    expected.push(1);
+    assert_eq!(output, expected);
+
+    // A private length above the bound makes the proof invalid.
+    let circuit = Circuit::from_function(&function, CircuitOptions::default()).unwrap();
+    let mut input = encode_bounded(b"zoker", 40).unwrap();
+    input[0] = 41;
+    let input_pub = encode_bounded(&[1, 2], 4).unwrap();
+    assert_eq!(evaluate(&circuit, &input, &input_pub).last(), Some(&0));
+
+    let program = parser::parse_program(source).unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("require(input_pub[0] <= 4, \"length out of range\");"));
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use common::{compile, error, run, uint};
+use serde_json::json;
+use zoker_compiler::bristol::BristolCircuit;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::{execute, Step};
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+
+const TOKEN: &str = "contract Token {\
+       event Transfer(address to, uint amount);\
+       event Burn(uint amount);\
+       mapping(address => uint) balances;\
+       function transfer(private uint secret, address to, uint amount) returns (uint) {\
+         require(secret != 0);\
+         balances[to] = balances[to] + amount;\
+         emit Transfer(to, amount);\
+         if amount > 10 {\
+           emit Burn(amount - 10);\
+         };\
+         return amount;\
+       }\
+    }";
+
+fn json(amount: u32) -> String {
+    format!(
+        "{{\"secret\": 1, \"to\": \"0x7\", \"amount\": {}, \"storage\": {{\"balances\": {{\"0x7\": 2}}}}}}",
+        amount
+    )
+}
+
+#[test]
+fn test_event_outputs() {
+    let contracts = compile(TOKEN);
+    assert_eq!(contracts[0].events.len(), 2);
+    let transfer = &contracts[0].functions[0];
+    let emits = transfer
+        .emits
+        .iter()
+        .map(|event| event.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(emits, vec!["Transfer", "Burn"]);
+
+    let to = [vec![0; 4], vec![7]].concat();
+    // Each `emit` outputs whether it ran and its arguments, before the
+    // validity word.
+    let read = [vec![1], to.clone()].concat();
+    let storage = [read.clone(), read.clone(), uint(15)].concat();
+    assert_eq!(
+        run(transfer, &json(13)),
+        [
+            uint(13),
+            storage,
+            vec![1],
+            to.clone(),
+            uint(13),
+            vec![1],
+            uint(3),
+            vec![1]
+        ]
+        .concat()
+    );
+    let storage = [read.clone(), read, uint(6)].concat();
+    assert_eq!(
+        run(transfer, &json(4)),
+        [
+            uint(4),
+            storage,
+            vec![1],
+            to,
+            uint(4),
+            vec![0],
+            uint(0),
+            vec![1]
+        ]
+        .concat()
+    );
+
+    let witness = Witness::from_json(transfer, &json(13)).unwrap();
+    let execution = execute(
+        transfer,
+        CircuitOptions::default(),
+        &witness.args,
+        &witness.storage,
+    )
+    .unwrap();
+    let trace = execution
+        .trace
+        .iter()
+        .filter(|step| matches!(step, Step::Emit { .. }))
+        .map(Step::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(trace, vec!["emit Transfer(0x7, 13)", "emit Burn(3)"]);
+    assert!(!execution.events[1].args.is_empty());
+}
+
+#[test]
+fn test_event_declared_after() {
+    let contracts = compile(
+        "contract Test {\
+           function f(uint n) {\
+             emit Ping(n);\
+           }\
+           event Ping(uint n);\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    assert_eq!(f.emits[0].name, "Ping");
+    assert_eq!(run(f, "{\"n\": 4}"), [vec![1], uint(4)].concat());
+}
+
+#[test]
+fn test_event_layout() {
+    let contracts = compile(TOKEN);
+    let transfer = &contracts[0].functions[0];
+    let layout = Layout::of(transfer);
+    assert_eq!(layout.events[0].active, 28);
+    assert_eq!(layout.events[0].args[0].offset, 29);
+    assert_eq!(layout.events[0].args[1].offset, 34);
+    assert_eq!(layout.events[1].active, 42);
+    let json = manifest(transfer, CircuitOptions::default());
+    assert_eq!(json["output_words"], 52);
+    assert_eq!(
+        json["events"][1],
+        json!({
+            "event": "Burn",
+            "active": 42,
+            "args": [{"name": "amount", "type": "uint256", "private": false, "offset": 43, "words": 8}],
+        })
+    );
+    let bristol = BristolCircuit::from_function(transfer, CircuitOptions::default()).unwrap();
+    assert_eq!(
+        bristol.outputs,
+        vec![256, 32, 160, 32, 160, 256, 32, 160, 256, 32, 256, 32]
+    );
+}
+
+#[test]
+fn test_event_verifier() {
+    let contracts = compile(TOKEN);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("event Transfer(address to, uint256 amount);"));
+    assert!(code.contains("event Burn(uint256 amount);"));
+    assert!(code.contains("require(output.length == 52);"));
+    // Events are only emitted once the proof is verified.
+    let verify = code.find("if (!ZKBoo_verify(").unwrap();
+    let emit = code
+        .find("if (output[28] == 1) {\n            emit Transfer(decode_address(output, 29), decode_uint256(output, 34));")
+        .unwrap();
+    assert!(verify < emit);
+    assert!(code.contains("emit Burn(decode_uint256(output, 43));"));
+
+    // Emitting makes a function change the chain.
+    let contracts = compile(
+        "contract Log {\
+           event Ping(uint n);\
+           function ping(uint n) {\
+             emit Ping(n);\
+           }\
+        }",
+    );
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains(") public returns (bool) {"));
+}
+
+#[test]
+fn test_event_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    assert_eq!(
+        error(
+            "contract Test {\
+               event Ping(uint n);\
+               function f(private uint a) {\
+                 emit Ping(a);\
+               }\
+            }"
+        ),
+        type_error("arguments of `Ping` depend on private values; `reveal` them")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               event Ping(uint n);\
+               function f(private uint a) {\
+                 if a > 1 {\
+                   emit Ping(1);\
+                 };\
+               }\
+            }"
+        ),
+        type_error("`Ping` is emitted under a condition on private values")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               event Ping(uint n);\
+               function f(uint[2] xs) {\
+                 for x in xs {\
+                   emit Ping(x);\
+                 };\
+               }\
+            }"
+        ),
+        type_error("`Ping` is emitted in a loop")
+    );
+    assert_eq!(
+        error("contract Test { function f() { emit Ping(1); } }"),
+        type_error("unknown event `Ping`")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               event Ping(uint n);\
+               function f() {\
+                 emit Ping(1, 2);\
+               }\
+            }"
+        ),
+        type_error("`Ping` takes 1 arguments, found 2")
+    );
+    assert_eq!(
+        error("contract Test { event Ping(private uint n); }"),
+        type_error("event parameters are public")
+    );
+    assert_eq!(
+        error("contract Test { event Ping(uint[2] ns); }"),
+        type_error("event parameters must be value types, found uint[2]")
+    );
+    assert_eq!(
+        error("contract Test { event Ping(uint n); event Ping(bool b); }"),
+        type_error("event `Ping` is declared twice")
+    );
+}
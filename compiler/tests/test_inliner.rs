@@ -0,0 +1,142 @@
+mod common;
+
+use common::{compile, error, evaluate};
+use num_bigint::BigUint;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::encoding::to_words;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::interpret;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::symbol::Function;
+
+fn has_call(function: &Function) -> bool {
+    format!("{:?}", function.operations).contains("Call {")
+}
+
+// Runs `function`, whose parameters are a private and a public `uint`,
+// both as a circuit and in the interpreter.
+fn run(function: &Function, a: u32, b: u32) -> BigUint {
+    let args = [BigUint::from(a), BigUint::from(b)];
+    let options = CircuitOptions::default();
+    let execution = interpret(function, options, &args).unwrap();
+    let circuit = Circuit::from_function(function, options).unwrap();
+    let output = evaluate(&circuit, &to_words(&args[0], 8), &to_words(&args[1], 8));
+    assert_eq!(execution.output_words(), output);
+    execution.outputs[0].value.clone()
+}
+
+#[test]
+fn test_inliner_substitutes_arguments() {
+    let contracts = compile(
+        "contract Test {\
+           function square(uint x) returns (uint) {\
+             uint y = x * x;\
+             return y;\
+           }\
+           function f(private uint a, uint b) returns (uint) {\
+             uint c = square(a) + square(b + 1);\
+             return reveal(square(c) - c);\
+           }\
+        }",
+    );
+    let function = &contracts[0].functions[1];
+    assert!(!has_call(function));
+    // `square` keeps its own variables at every call site.
+    assert_eq!(
+        function.public_num,
+        contracts[0].functions[0].public_num * 3 + 2
+    );
+    let c = 3u32 * 3 + 5 * 5;
+    assert_eq!(run(function, 3, 4), BigUint::from(c * c - c));
+}
+
+#[test]
+fn test_inliner_early_returns() {
+    let contracts = compile(
+        "contract Test {\
+           function clamp(uint x, uint max) returns (uint) {\
+             if x > max {\
+               return max;\
+             };\
+             x = x + 1;\
+             return x;\
+           }\
+           function twice(uint x) returns (uint) {\
+             return clamp(x, 10) + clamp(x, 20);\
+           }\
+           function f(private uint a, uint b) returns (uint) {\
+             if reveal(a < b) {\
+               return reveal(twice(a));\
+             } else {\
+               return twice(b) * 2;\
+             };\
+           }\
+        }",
+    );
+    let function = &contracts[0].functions[2];
+    assert!(!has_call(function));
+    assert_eq!(run(function, 3, 5), BigUint::from(4u32 + 4));
+    assert_eq!(run(function, 15, 30), BigUint::from(10u32 + 16));
+    assert_eq!(run(function, 40, 25), BigUint::from((10u32 + 20) * 2));
+}
+
+#[test]
+fn test_inliner_rejects_bad_calls() {
+    let arity = error(
+        "contract Test {\
+           function g(uint x) returns (uint) {\
+             return x;\
+           }\
+           function f(uint a) returns (uint) {\
+             return g(a, a);\
+           }\
+        }",
+    );
+    assert_eq!(
+        arity,
+        RewriteErrorType::TypeError("`g` takes 1 arguments, found 2".to_string())
+    );
+    let unknown = error(
+        "contract Test {\
+           function f(uint a) returns (uint) {\
+             return h(a);\
+           }\
+        }",
+    );
+    assert_eq!(
+        unknown,
+        RewriteErrorType::TypeError("unknown function `h`".to_string())
+    );
+}
+
+#[test]
+fn test_inliner_rejects_recursion() {
+    let direct = error(
+        "contract Test {\
+           function f(uint a) returns (uint) {\
+             return f(a - 1);\
+           }\
+        }",
+    );
+    assert_eq!(
+        direct,
+        RewriteErrorType::TypeError("recursive call: f -> f".to_string())
+    );
+    let mutual = error(
+        "contract Test {\
+           function f(uint a) returns (uint) {\
+             return g(a);\
+           }\
+           function g(uint a) returns (uint) {\
+             return h(a) + 1;\
+           }\
+           function h(uint a) returns (uint) {\
+             return f(a);\
+           }\
+        }",
+    );
+    assert_eq!(
+        mutual,
+        RewriteErrorType::TypeError("recursive call: f -> g -> h -> f".to_string())
+    );
+}
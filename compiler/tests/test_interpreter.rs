@@ -0,0 +1,202 @@
+mod common;
+
+use common::compile_function;
+use num_bigint::BigUint;
+use zoker_compiler::circuit::{Circuit, Wire};
+use zoker_compiler::interpreter::{interpret, Step, Value};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::symbol::Function;
+use zoker_prover::ikos::IKosVariable;
+
+fn evaluate(circuit: &Circuit, function: &Function, args: &[BigUint]) -> Vec<u32> {
+    let (mut input, mut input_pub) = (vec![], vec![]);
+    for (param, arg) in function.params.iter().zip(args) {
+        let words = Value::new(param.symbol_type.clone(), arg.clone())
+            .words()
+            .into_iter()
+            .map(Wire::new_value);
+        if param.is_private {
+            input.extend(words);
+        } else {
+            input_pub.extend(words);
+        }
+    }
+    circuit
+        .evaluate(&input, &input_pub)
+        .unwrap()
+        .into_iter()
+        .map(|word| match word {
+            Wire::Const(value) => value,
+            Wire::Gate(..) => panic!("output depends on no input"),
+        })
+        .collect()
+}
+
+// Values around the edges of 256-bit arithmetic, mixed with random ones.
+fn samples() -> Vec<BigUint> {
+    let max = (BigUint::from(1u32) << 256) - 1u32;
+    let mut values = vec![
+        BigUint::from(0u32),
+        BigUint::from(1u32),
+        BigUint::from(7u32),
+        max.clone(),
+        BigUint::from(1u32) << 255,
+        &max >> 1,
+        &max - 6u32,
+    ];
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..5 {
+        let mut bytes = vec![];
+        for _ in 0..32 {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            bytes.push((state >> 56) as u8);
+        }
+        values.push(BigUint::from_bytes_be(&bytes) >> (state % 200) as usize);
+    }
+    values
+}
+
+fn assert_matches_circuit(source: &str, options: CircuitOptions) {
+    let function = compile_function(source);
+    let circuit = Circuit::from_function(&function, options).unwrap();
+    let samples = samples();
+    for (i, a) in samples.iter().enumerate() {
+        let b = &samples[(3 * i + 1) % samples.len()];
+        let args = [a.clone(), b.clone()];
+        let execution = interpret(&function, options, &args).unwrap();
+        assert_eq!(
+            execution.output_words(),
+            evaluate(&circuit, &function, &args),
+            "a = {}, b = {}",
+            a,
+            b
+        );
+    }
+}
+
+#[test]
+fn test_interpreter_unsigned_arithmetic() {
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             uint c = a * b + (a << 3);\
+             if c > b {\
+               c = c - b / (a + 1);\
+             } else {\
+               c = c % b;\
+             };\
+             return reveal(c + (a >> 100));\
+           }\
+        }",
+        CircuitOptions::default(),
+    );
+}
+
+#[test]
+fn test_interpreter_signed_arithmetic() {
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             if reveal(a < b) {\
+               return reveal(a / b - (a >> 7));\
+             };\
+             return reveal(a % b + -b);\
+           }\
+        }",
+        CircuitOptions::default(),
+    );
+}
+
+#[test]
+fn test_interpreter_checked_arithmetic() {
+    let options = CircuitOptions {
+        checked_arithmetic: true,
+        optimize: false,
+    };
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private uint a, uint b) returns (bool) {\
+             if reveal(a < b) {\
+               return reveal(b - a > 5);\
+             };\
+             return reveal(a * b == a + b);\
+           }\
+        }",
+        options,
+    );
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             return reveal(a * b - a);\
+           }\
+        }",
+        options,
+    );
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             return reveal(a / b + a % -b);\
+           }\
+        }",
+        options,
+    );
+    assert_matches_circuit(
+        "contract Test {\
+           function f(private uint a, uint b) returns (int) {\
+             return reveal(-a + -b);\
+           }\
+        }",
+        options,
+    );
+}
+
+#[test]
+fn test_interpreter_sha256() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (bytes32) {\
+             return sha256(a);\
+           }\
+        }",
+    );
+    let execution = interpret(&function, CircuitOptions::default(), &[BigUint::from(0u32)]);
+    // SHA-256 of 32 zero bytes.
+    let expected = BigUint::parse_bytes(
+        b"66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925",
+        16,
+    )
+    .unwrap();
+    assert_eq!(execution.unwrap().outputs[0].value, expected);
+}
+
+#[test]
+fn test_interpreter_trace() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (uint) {\
+             uint b = a + 1;\
+             if reveal(b > 3) {\
+               return reveal(b);\
+             };\
+             return 0;\
+           }\
+        }",
+    );
+    let execution = interpret(&function, CircuitOptions::default(), &[BigUint::from(5u32)]);
+    let trace = execution
+        .unwrap()
+        .trace
+        .iter()
+        .map(Step::to_string)
+        .collect::<Vec<String>>();
+    assert_eq!(trace, vec!["b = 6", "branch taken", "return 6"]);
+
+    let wrong = interpret(
+        &function,
+        CircuitOptions::default(),
+        &[BigUint::from(1u32) << 256],
+    );
+    assert!(wrong.is_err());
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::compile_function;
+use serde_json::Value;
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::{input_len, output_len, CircuitOptions};
+use zoker_compiler::symbol::Function;
+
+fn function() -> Function {
+    compile_function(
+        "contract Test {\
+           function f(private uint a, bool b, private address c) returns (int) {\
+             private uint d = a;\
+             return reveal(d);\
+           }\
+        }",
+    )
+}
+
+#[test]
+fn test_layout_counts_variables() {
+    let function = function();
+    // `a`, `c` and `d` are private, `b` is public.
+    assert_eq!((function.private_num, function.public_num), (3, 1));
+}
+
+#[test]
+fn test_layout_offsets() {
+    let function = function();
+    let layout = Layout::of(&function);
+    let offsets = layout
+        .params
+        .iter()
+        .map(|entry| {
+            (
+                entry.name.as_str(),
+                entry.is_private,
+                entry.offset,
+                entry.words,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        offsets,
+        vec![("a", true, 0, 8), ("b", false, 0, 1), ("c", true, 8, 5)]
+    );
+    assert_eq!((layout.returns[0].offset, layout.returns[0].words), (0, 8));
+}
+
+#[test]
+fn test_layout_manifest() {
+    let function = function();
+    let options = CircuitOptions {
+        checked_arithmetic: true,
+        optimize: false,
+    };
+    let json = manifest(&function, options);
+    assert_eq!(json["function"], "f");
+    let (private_len, public_len) = input_len(&function);
+    assert_eq!(json["input_words"], private_len);
+    assert_eq!(json["input_pub_words"], public_len);
+    assert_eq!(json["output_words"], output_len(&function, options));
+
+    let c = &json["params"][2];
+    assert_eq!(c["type"], "address");
+    assert_eq!(c["private"], true);
+    assert_eq!(c["offset"], 8);
+
+    assert_eq!(json["returns"].as_array().unwrap().len(), 1);
+    assert_eq!(json["valid"], Value::Null);
+    assert_eq!(json["ok"], 8);
+    // Members keep their order in the printed manifest.
+    assert!(json
+        .to_string()
+        .starts_with("{\"function\":\"f\",\"checked_arithmetic\":true,\"input_words\":"));
+}
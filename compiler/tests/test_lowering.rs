@@ -0,0 +1,422 @@
+mod common;
+
+use common::compile_function;
+use num_bigint::BigUint;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::lowering::{input_len, output_len, CircuitOptions};
+use zoker_compiler::prover::{prover_circuit, verifier_circuit};
+use zoker_compiler::symbol::Function;
+use zoker_parser::location::Location;
+use zoker_prover::zkboo::{ProvingProof, VerifyingProof, ZkBoo};
+
+fn to_words(value: &BigUint) -> Vec<u32> {
+    let mut bytes = value.to_bytes_be();
+    while bytes.len() < 32 {
+        bytes.insert(0, 0);
+    }
+    bytes
+        .chunks(4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn modulus() -> BigUint {
+    BigUint::from(1u32) << 256
+}
+
+fn prove_and_verify(function: &Function, input: Vec<u32>, input_pub: Vec<u32>) -> Vec<u32> {
+    let output = prove_and_verify_with(
+        function,
+        CircuitOptions::default(),
+        input.clone(),
+        input_pub.clone(),
+    );
+    let optimized = CircuitOptions {
+        optimize: true,
+        ..CircuitOptions::default()
+    };
+    assert_eq!(
+        prove_and_verify_with(function, optimized, input, input_pub),
+        output
+    );
+    output
+}
+
+fn prove_and_verify_with(
+    function: &Function,
+    options: CircuitOptions,
+    input: Vec<u32>,
+    input_pub: Vec<u32>,
+) -> Vec<u32> {
+    assert_eq!(input_len(function), (input.len(), input_pub.len()));
+    let circuit = Circuit::from_function(function, options).unwrap();
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+    let mut res = zk_boo
+        .prove(ProvingProof::new(
+            input.clone(),
+            input_pub.clone(),
+            output_len(function, options),
+            prover_circuit(&circuit),
+        ))
+        .unwrap();
+    let output = res.output.clone();
+    let challenge = ZkBoo::query_random_oracle(
+        res.input_len,
+        res.output_len,
+        &res.out_data,
+        &res.three_views,
+    );
+    let response = zk_boo.build_response(&res.views, &challenge);
+    let two_views = zk_boo.rebuild_proof(&mut res, &challenge);
+    let verified = zk_boo.verify(VerifyingProof::new(
+        input.len(),
+        input_pub,
+        output.clone(),
+        challenge,
+        two_views,
+        response,
+        verifier_circuit(&circuit),
+    ));
+    assert!(verified.unwrap());
+    output
+}
+
+#[test]
+fn test_lowering_add_carries_across_limbs() {
+    let function = compile_function(
+        "contract Test {\
+           function add(private uint a, uint b) returns (uint) {\
+             return reveal(a + b);\
+           }\
+        }",
+    );
+    let a = (BigUint::from(1u32) << 255) + BigUint::from(u32::MAX);
+    let b = (BigUint::from(1u32) << 255) + BigUint::from(1u32);
+    let output = prove_and_verify(&function, to_words(&a), to_words(&b));
+    assert_eq!(output, to_words(&((a + b) % modulus())));
+}
+
+#[test]
+fn test_lowering_sub_wraps() {
+    let function = compile_function(
+        "contract Test {\
+           function sub(private uint a, uint b) returns (uint) {\
+             return reveal(a - b);\
+           }\
+        }",
+    );
+    let a = BigUint::from(5u32);
+    let b = BigUint::from(1u64) << 40;
+    let output = prove_and_verify(&function, to_words(&a), to_words(&b));
+    assert_eq!(output, to_words(&((a + modulus() - b) % modulus())));
+}
+
+#[test]
+fn test_lowering_mul() {
+    let function = compile_function(
+        "contract Test {\
+           function mul(private uint a, uint b) returns (uint) {\
+             return reveal(a * b + 7);\
+           }\
+        }",
+    );
+    let a = BigUint::parse_bytes(b"f123456789abcdef0123456789abcdef0123456789", 16).unwrap();
+    let b = BigUint::parse_bytes(b"abcdef0123456789abcdef0123456789", 16).unwrap();
+    let output = prove_and_verify(&function, to_words(&a), to_words(&b));
+    let expected = (a * b + BigUint::from(7u32)) % modulus();
+    assert_eq!(output, to_words(&expected));
+}
+
+#[test]
+fn test_lowering_comparison_and_branch() {
+    let function = compile_function(
+        "contract Test {\
+           function min(private uint a, uint b) returns (uint) {\
+             if reveal(a < b) {\
+               return reveal(a);\
+             };\
+             return b;\
+           }\
+        }",
+    );
+    let small = BigUint::from(3u32) << 200;
+    let large = (BigUint::from(3u32) << 200) + BigUint::from(1u32);
+    let output = prove_and_verify(&function, to_words(&small), to_words(&large));
+    assert_eq!(output, to_words(&small));
+    let output = prove_and_verify(&function, to_words(&large), to_words(&small));
+    assert_eq!(output, to_words(&small));
+}
+
+#[test]
+fn test_lowering_equality() {
+    let function = compile_function(
+        "contract Test {\
+           function same(private uint a, uint b) returns (bool) {\
+             return reveal(a == b);\
+           }\
+        }",
+    );
+    let a = BigUint::from(1u32) << 100;
+    let output = prove_and_verify(&function, to_words(&a), to_words(&a));
+    assert_eq!(output, vec![1]);
+    let b = a.clone() + BigUint::from(1u32);
+    let output = prove_and_verify(&function, to_words(&a), to_words(&b));
+    assert_eq!(output, vec![0]);
+}
+
+fn signed_words(value: i128) -> Vec<u32> {
+    let value = if value < 0 {
+        modulus() - BigUint::from((-value) as u128)
+    } else {
+        BigUint::from(value as u128)
+    };
+    to_words(&value)
+}
+
+const SIGNED_CASES: [(i128, i128); 6] = [
+    (7, 2),
+    (-7, 2),
+    (7, -2),
+    (-7, -2),
+    (i128::MIN + 1, 3),
+    (-1, i128::MAX),
+];
+
+#[test]
+fn test_lowering_signed_comparison() {
+    let function = compile_function(
+        "contract Test {\
+           function lt(private int a, int b) returns (bool) {\
+             return reveal(a < b);\
+           }\
+        }",
+    );
+    for &(a, b) in SIGNED_CASES.iter() {
+        let output = prove_and_verify(&function, signed_words(a), signed_words(b));
+        assert_eq!(output, vec![(a < b) as u32]);
+    }
+}
+
+#[test]
+fn test_lowering_signed_division() {
+    let function = compile_function(
+        "contract Test {\
+           function div(private int a, int b) returns (int) {\
+             return reveal(a / b);\
+           }\
+        }",
+    );
+    for &(a, b) in SIGNED_CASES.iter() {
+        let output = prove_and_verify(&function, signed_words(a), signed_words(b));
+        assert_eq!(output, signed_words(a / b));
+    }
+}
+
+#[test]
+fn test_lowering_signed_remainder() {
+    let function = compile_function(
+        "contract Test {\
+           function rem(private int a, int b) returns (int) {\
+             return reveal(a % b);\
+           }\
+        }",
+    );
+    for &(a, b) in SIGNED_CASES.iter() {
+        let output = prove_and_verify(&function, signed_words(a), signed_words(b));
+        assert_eq!(output, signed_words(a % b));
+    }
+}
+
+#[test]
+fn test_lowering_arithmetic_shift() {
+    let function = compile_function(
+        "contract Test {\
+           function sar(private int a) returns (int) {\
+             return reveal((a >> 33) + (-a >> 1));\
+           }\
+        }",
+    );
+    for &a in [0i128, 5, -5, -(1 << 40) - 3, i128::MIN + 1].iter() {
+        let output = prove_and_verify(&function, signed_words(a), vec![]);
+        assert_eq!(output, signed_words((a >> 33) + (-a >> 1)));
+    }
+}
+
+#[test]
+fn test_lowering_unsigned_division() {
+    let function = compile_function(
+        "contract Test {\
+           function div(private uint a, uint b) returns (uint) {\
+             return reveal(a / b + a % b);\
+           }\
+        }",
+    );
+    let a = (BigUint::from(1u32) << 255) + BigUint::from(12345u32);
+    let b = (BigUint::from(1u32) << 254) + BigUint::from(7u32);
+    let output = prove_and_verify(&function, to_words(&a), to_words(&b));
+    assert_eq!(output, to_words(&(&a / &b + &a % &b)));
+}
+
+#[test]
+fn test_lowering_else_reuses_condition() {
+    let function = compile_function(
+        "contract Test {\
+           function clamp(private uint a) returns (uint) {\
+             if a < 10 {\
+               a = 20;\
+             } else {\
+               a = 0;\
+             };\
+             return reveal(a);\
+           }\
+        }",
+    );
+    let output = prove_and_verify(&function, to_words(&BigUint::from(3u32)), vec![]);
+    assert_eq!(output, to_words(&BigUint::from(20u32)));
+    let output = prove_and_verify(&function, to_words(&BigUint::from(30u32)), vec![]);
+    assert_eq!(output, to_words(&BigUint::from(0u32)));
+}
+
+const CHECKED: CircuitOptions = CircuitOptions {
+    checked_arithmetic: true,
+    optimize: false,
+};
+
+fn pow2(n: usize) -> BigUint {
+    BigUint::from(1u32) << n
+}
+
+#[test]
+fn test_lowering_checked_unsigned() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             return reveal(a * b - 1);\
+           }\
+        }",
+    );
+    let output = prove_and_verify_with(
+        &function,
+        CHECKED,
+        to_words(&pow2(200)),
+        to_words(&pow2(55)),
+    );
+    assert_eq!(
+        output[..8],
+        to_words(&(pow2(255) - BigUint::from(1u32)))[..]
+    );
+    assert_eq!(output[8], 1);
+    let output = prove_and_verify_with(
+        &function,
+        CHECKED,
+        to_words(&pow2(200)),
+        to_words(&pow2(56)),
+    );
+    assert_eq!(output[8], 0);
+    // a * 0 - 1 underflows.
+    let output = prove_and_verify_with(&function, CHECKED, to_words(&pow2(0)), vec![0; 8]);
+    assert_eq!(output[8], 0);
+}
+
+#[test]
+fn test_lowering_checked_signed() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             return reveal(a * b);\
+           }\
+        }",
+    );
+    let negative = modulus() - pow2(200);
+    let output =
+        prove_and_verify_with(&function, CHECKED, to_words(&negative), to_words(&pow2(55)));
+    assert_eq!(output[8], 1);
+    let output = prove_and_verify_with(
+        &function,
+        CHECKED,
+        to_words(&pow2(200)),
+        to_words(&pow2(55)),
+    );
+    assert_eq!(output[8], 0);
+
+    let function = compile_function(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             return reveal(a + b);\
+           }\
+        }",
+    );
+    let max = pow2(255) - BigUint::from(1u32);
+    let output = prove_and_verify_with(&function, CHECKED, to_words(&max), signed_words(-1));
+    assert_eq!(output[8], 1);
+    let output = prove_and_verify_with(&function, CHECKED, to_words(&max), signed_words(1));
+    assert_eq!(output[8], 0);
+}
+
+#[test]
+fn test_lowering_checked_division() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private int a, int b) returns (int) {\
+             return reveal(a / b + a % b);\
+           }\
+        }",
+    );
+    let min = to_words(&pow2(255));
+    let output = prove_and_verify_with(&function, CHECKED, min.clone(), signed_words(1));
+    assert_eq!(output[8], 1);
+    // Division by zero and `-2^255 / -1` fail like in Solidity.
+    let output = prove_and_verify_with(&function, CHECKED, signed_words(5), vec![0; 8]);
+    assert_eq!(output[8], 0);
+    let output = prove_and_verify_with(&function, CHECKED, min.clone(), signed_words(-1));
+    assert_eq!(output[8], 0);
+
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (int) {\
+             return reveal(-a);\
+           }\
+        }",
+    );
+    let output = prove_and_verify_with(&function, CHECKED, min.clone(), vec![]);
+    assert_eq!(output, [min, vec![1]].concat());
+    let output =
+        prove_and_verify_with(&function, CHECKED, to_words(&(pow2(255) + pow2(0))), vec![]);
+    assert_eq!(output[8], 0);
+}
+
+#[test]
+fn test_lowering_checked_branch_not_taken() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (uint) {\
+             if reveal(a > 0) {\
+               return reveal(a - 1);\
+             };\
+             return reveal(a);\
+           }\
+        }",
+    );
+    let output = prove_and_verify_with(&function, CHECKED, vec![0; 8], vec![]);
+    assert_eq!(output, [vec![0; 8], vec![1]].concat());
+    let output = prove_and_verify_with(&function, CHECKED, to_words(&pow2(3)), vec![]);
+    assert_eq!(output[8], 1);
+}
+
+#[test]
+fn test_lowering_parameter_errors() {
+    let function = compile_function(
+        "contract Test {
+  function f(uint a, string s) returns (uint) {
+    return a;
+  }
+}",
+    );
+    let err = Circuit::from_function(&function, CircuitOptions::default()).unwrap_err();
+    assert_eq!(
+        err.error,
+        RewriteErrorType::TypeError("parameter type has no circuit encoding".to_string())
+    );
+    assert_eq!(err.location, Location::new(1, 23));
+}
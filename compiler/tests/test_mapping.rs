@@ -0,0 +1,254 @@
+mod common;
+
+use common::{compile, error, run, uint};
+use serde_json::json;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::{execute, Step};
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+
+const TOKEN: &str = "contract Token {\
+       mapping(address => uint) balances;\
+       function transfer(address from, address to, uint amount) returns (bool) {\
+         uint balance = balances[from];\
+         if amount <= balance {\
+           balances[from] = balance - amount;\
+           credit(to, amount);\
+           return 1;\
+         };\
+         return 0;\
+       }\
+       function credit(address to, uint amount) {\
+         balances[to] = balances[to] + amount;\
+       }\
+    }";
+
+// The words of `value` as an address and as a uint.
+fn address(value: u32) -> Vec<u32> {
+    vec![0, 0, 0, 0, value]
+}
+
+#[test]
+fn test_mapping_transfer() {
+    let contracts = compile(TOKEN);
+    let transfer = &contracts[0].functions[0];
+    let accesses = transfer
+        .storage
+        .iter()
+        .map(|access| (access.mapping.name.as_str(), access.is_write))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        vec![
+            ("balances", false),
+            ("balances", true),
+            ("balances", false),
+            ("balances", true)
+        ]
+    );
+
+    let json = |from: u32, to: u32, amount: u32| {
+        format!(
+            "{{\"from\": {}, \"to\": {}, \"amount\": {}, \
+              \"storage\": {{\"balances\": {{\"1\": 10, \"2\": 5}}}}}}",
+            from, to, amount
+        )
+    };
+    let output = run(transfer, &json(1, 2, 3));
+    let mut expected = vec![1];
+    expected.extend([vec![1], address(1)].concat());
+    expected.extend([vec![1], address(1), uint(7)].concat());
+    expected.extend([vec![1], address(2)].concat());
+    expected.extend([vec![1], address(2), uint(8)].concat());
+    assert_eq!(output, expected);
+
+    // Sending to oneself reads back what was just written.
+    let output = run(transfer, &json(1, 1, 3));
+    assert_eq!(output[output.len() - 8..], uint(10)[..]);
+
+    // Accesses that do not run are all zero.
+    let output = run(transfer, &json(2, 1, 6));
+    let mut expected = vec![0];
+    expected.extend([vec![1], address(2)].concat());
+    expected.extend(vec![0; 14 + 6 + 14]);
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_mapping_witness() {
+    let contracts = compile(TOKEN);
+    let transfer = &contracts[0].functions[0];
+    let json = "{\"from\": 1, \"to\": 2, \"amount\": 3, \
+                \"storage\": {\"balances\": {\"0x2\": 5}, \"unused\": {}}}";
+    let witness = Witness::from_json(transfer, json).unwrap();
+    // Old values of the four accesses follow the public arguments; those
+    // of accesses that do not run are 0.
+    assert_eq!(witness.input_pub.len(), 5 + 5 + 8 + 4 * 8);
+    assert_eq!(witness.input_pub[18..], vec![0; 32][..]);
+    let execution = execute(
+        transfer,
+        CircuitOptions::default(),
+        &witness.args,
+        &witness.storage,
+    )
+    .unwrap();
+    assert_eq!(execution.outputs[0].value, 0u32.into());
+    assert!(!execution
+        .trace
+        .iter()
+        .any(|step| matches!(step, Step::Store { .. })));
+
+    let json = "{\"from\": 1, \"to\": 2, \"amount\": 3, \
+                \"storage\": {\"balances\": {\"0x1\": 4, \"0x2\": 5}}}";
+    let witness = Witness::from_json(transfer, json).unwrap();
+    assert_eq!(witness.input_pub[18..26], uint(4)[..]);
+    assert_eq!(witness.input_pub[34..42], uint(5)[..]);
+
+    let json = "{\"from\": 1, \"to\": 2, \"amount\": 3, \
+                \"storage\": {\"balances\": {\"1\": true}}}";
+    assert!(Witness::from_json(transfer, json).is_err());
+
+    let layout = Layout::of(transfer);
+    let entry = &layout.storage[1];
+    assert_eq!(entry.old.offset, 26);
+    assert_eq!(entry.active, 1 + 6);
+    assert_eq!(entry.key.offset, 8);
+    assert_eq!(entry.new.as_ref().unwrap().offset, 13);
+    let json = manifest(transfer, CircuitOptions::default());
+    assert_eq!(json["input_pub_words"], 50);
+    assert_eq!(json["storage"][1]["mapping"], "balances");
+    assert_eq!(
+        json["storage"][1]["old"],
+        json!({"name": "balances", "type": "uint256", "private": false, "offset": 26, "words": 8})
+    );
+    assert_eq!(json["storage"][1]["active"], 7);
+}
+
+#[test]
+fn test_mapping_verifier() {
+    let contracts = compile(TOKEN);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("mapping(address => uint256) public balances;"));
+    assert!(code.contains(") public returns (bool) {"));
+    assert!(code.contains("require(input_pub.length == 50);"));
+    assert!(code.contains("if (output[7] == 1) {"));
+    assert!(code.contains(
+        "require(balances[decode_address(output, 8)] == decode_uint256(input_pub, 26), \"stale storage read\");"
+    ));
+    assert!(code.contains("balances[decode_address(output, 8)] = decode_uint256(output, 13);"));
+    assert!(code.contains("return true;"));
+
+    let contracts = compile(
+        "contract Registry {\
+           mapping(bytes32 => bool) used;\
+           function check(private uint secret) returns (bool) {\
+             return used[sha256(secret)];\
+           }\
+        }",
+    );
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("mapping(bytes32 => bool) public used;"));
+    assert!(code.contains(") public view returns (bool) {"));
+    assert!(code.contains("used[decode_bytes32(output, 2)] == decode_bool(input_pub, 0)"));
+}
+
+#[test]
+fn test_mapping_hashed_key() {
+    let contracts = compile(
+        "contract Registry {\
+           mapping(bytes32 => uint) notes;\
+           function spend(private uint secret, uint amount) returns (uint) {\
+             notes[sha256(secret)] = amount;\
+             return notes[sha256(secret)];\
+           }\
+        }",
+    );
+    let spend = &contracts[0].functions[0];
+    let output = run(spend, "{\"secret\": 7, \"amount\": 9}");
+    assert_eq!(output[..8], uint(9)[..]);
+}
+
+#[test]
+fn test_mapping_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint k) returns (uint) {\
+                 return m[k];\
+               }\
+            }"
+        ),
+        type_error("key of `m` depends on private values; hash it with `sha256` or `reveal` it")
+    );
+    // Copies of private values are as private.
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint s) {\
+                 uint k = s;\
+                 m[k] = 1;\
+               }\
+            }"
+        ),
+        type_error("key of `m` depends on private values; hash it with `sha256` or `reveal` it")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint s) returns (uint) {\
+                 uint k = s;\
+                 return m[k];\
+               }\
+            }"
+        ),
+        type_error("key of `m` depends on private values; hash it with `sha256` or `reveal` it")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(uint[2] ks) {\
+                 for k in ks {\
+                   m[k] = 1;\
+                 };\
+               }\
+            }"
+        ),
+        type_error("`m` is accessed in a loop")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(uint k) returns (uint) {\
+                 return m;\
+               }\
+            }"
+        ),
+        type_error("mapping `m` is only accessed by key")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => mapping(uint => uint)) m;\
+            }"
+        ),
+        type_error("mapping keys and values must be value types, found mapping(uint => mapping(uint => uint))")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(uint k) {\
+                 mapping(uint => uint) m;\
+               }\
+            }"
+        ),
+        type_error("mappings can only be contract state")
+    );
+}
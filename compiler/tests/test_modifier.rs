@@ -0,0 +1,127 @@
+mod common;
+
+use common::{compile, error};
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::symbol::{Mutability, Visibility};
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_parser::location::Location;
+use zoker_parser::parser;
+
+#[test]
+fn test_proven_functions() {
+    let contracts = compile(
+        "contract Bank {\
+           uint total;\
+           function double(private uint a) internal pure returns (uint) {\
+             return a * 2;\
+           }\
+           function fee(uint a) public view returns (uint) {\
+             return a + total;\
+           }\
+           function pay(private uint a, uint b) external proven {\
+             total = total + fee(b) + reveal(double(a));\
+           }\
+           function check(uint b) view proven returns (uint) {\
+             return fee(b);\
+           }\
+           function legacy(uint b) returns (uint) {\
+             return b;\
+           }\
+        }",
+    );
+    // Helpers are inlined into the proven functions and dropped.
+    let functions = &contracts[0].functions;
+    let names = functions
+        .iter()
+        .map(|function| function.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["pay", "check", "legacy"]);
+    assert_eq!(functions[0].visibility, Visibility::External);
+    assert_eq!(functions[1].mutability, Mutability::View);
+    assert_eq!(functions[2].visibility, Visibility::Public);
+    assert!(functions.iter().all(|function| function.is_proven));
+
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(!code.contains("double"));
+    assert!(!code.contains("function fee("));
+    assert!(code.contains("function _circuit_pay("));
+    assert!(code.contains(") external returns (bool) {"));
+    assert!(code.contains(") public view returns (bool) {"));
+    assert!(code.contains(") public pure returns (bool) {"));
+}
+
+#[test]
+fn test_modifier_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    assert_eq!(
+        error(
+            "contract Test {\
+               uint total;\
+               function f(uint a) view {\
+                 total = a;\
+               }\
+            }"
+        ),
+        type_error("view function `f` writes `total`")
+    );
+    // Helpers are held to their mutability, wherever they are inlined.
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function get(uint k) internal pure returns (uint) {\
+                 return m[k];\
+               }\
+               function f(uint k) returns (uint) {\
+                 return get(k);\
+               }\
+            }"
+        ),
+        type_error("pure function `get` reads `m`")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               event Ping(uint n);\
+               function f(uint n) view proven {\
+                 emit Ping(n);\
+               }\
+            }"
+        ),
+        type_error("view function `f` emits `Ping`")
+    );
+    assert_eq!(
+        error("contract Test { function f() internal proven { } }"),
+        type_error("internal function `f` cannot be `proven`")
+    );
+    assert_eq!(
+        error("contract Test { function f() public external { } }"),
+        type_error("`f` has more than one visibility")
+    );
+    assert_eq!(
+        error("contract Test { function f() view pure { } }"),
+        type_error("`f` has more than one state mutability")
+    );
+    assert_eq!(
+        error("contract Test { function f() proven proven { } }"),
+        type_error("`f` is marked `proven` twice")
+    );
+
+    // The first offending operation is reported.
+    let program = parser::parse_program(
+        "contract Test {
+           uint total;
+           function f(uint a) view returns (uint) {
+             uint b = total;
+             total = a;
+             return b;
+           }
+        }",
+    )
+    .unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(err.error, type_error("view function `f` writes `total`"));
+    assert_eq!(err.location, Location::new(4, 21));
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::{compile_function, evaluate};
+use zoker_compiler::circuit::{Circuit, Gate};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::optimizer::{optimize, specialize};
+
+fn compile(source: &str) -> Circuit {
+    Circuit::from_function(&compile_function(source), CircuitOptions::default()).unwrap()
+}
+
+fn circuit(gates: Vec<Gate>, outputs: Vec<usize>) -> Circuit {
+    Circuit {
+        gates,
+        outputs,
+        input_len: 2,
+        input_pub_len: 0,
+    }
+}
+
+fn private(index: usize) -> Gate {
+    Gate::Input {
+        index,
+        is_private: true,
+    }
+}
+
+#[test]
+fn test_optimizer_algebraic_simplification() {
+    let source = circuit(
+        vec![
+            private(0),
+            private(1),
+            Gate::Const(0),
+            Gate::Xor(0, 0),
+            Gate::And(1, 2),
+            Gate::Add32(0, 2),
+            Gate::Xor(5, 3),
+            Gate::Not(6),
+            Gate::Not(7),
+        ],
+        vec![8, 4],
+    );
+    let (optimized, report) = optimize(&source);
+    assert_eq!(optimized.gates, vec![private(0), Gate::Const(0)]);
+    assert_eq!(optimized.outputs, vec![0, 1]);
+    assert_eq!(report.before.and, 1);
+    assert_eq!(report.before.add, 1);
+    assert_eq!(report.after.and, 0);
+    assert_eq!(report.after.add, 0);
+}
+
+#[test]
+fn test_optimizer_common_subexpressions() {
+    let source = circuit(
+        vec![
+            private(0),
+            private(1),
+            Gate::And(0, 1),
+            Gate::And(1, 0),
+            Gate::Add32(2, 3),
+        ],
+        vec![4],
+    );
+    let (optimized, report) = optimize(&source);
+    assert_eq!(
+        optimized.gates,
+        vec![private(0), private(1), Gate::And(0, 1), Gate::Add32(2, 2)]
+    );
+    assert_eq!(report.before.and, 2);
+    assert_eq!(report.after.and, 1);
+}
+
+#[test]
+fn test_optimizer_dead_gates() {
+    let source = circuit(
+        vec![private(0), private(1), Gate::And(0, 1), Gate::Rotr(1, 3)],
+        vec![3],
+    );
+    let (optimized, _) = optimize(&source);
+    assert_eq!(optimized.gates, vec![private(1), Gate::Rotr(0, 3)]);
+    assert_eq!(optimized.outputs, vec![1]);
+}
+
+#[test]
+fn test_optimizer_preserves_semantics() {
+    let source = compile(
+        "contract Test {\
+           function f(private uint a, int b) returns (uint) {\
+             uint c;\
+             if b < 0 {\
+               c = a * 3 - 1;\
+             } else {\
+               c = (a >> 7) + 10;\
+             };\
+             return reveal(c);\
+           }\
+        }",
+    );
+    let (optimized, report) = optimize(&source);
+    assert!(report.after.and < report.before.and);
+    assert!(report.after.add <= report.before.add);
+    let input = [0, 0, 0, 5, 6, 7, 8, 0xffff_fff9];
+    for &sign in [0u32, 0x8000_0000].iter() {
+        let input_pub = [sign, 0, 0, 0, 0, 0, 0, 3];
+        assert_eq!(
+            evaluate(&optimized, &input, &input_pub),
+            evaluate(&source, &input, &input_pub)
+        );
+    }
+}
+
+#[test]
+fn test_optimizer_specialize_public_inputs() {
+    let source = compile(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             return reveal(a * b);\
+           }\
+        }",
+    );
+    let input_pub = [0, 0, 0, 0, 0, 0, 0, 4];
+    let (optimized, report) = optimize(&specialize(&source, &input_pub));
+    // Multiplying by the constant 4 leaves a single partial product.
+    assert!(report.after.and * 10 < report.before.and);
+    let input = [1, 2, 3, 4, 5, 6, 7, 8];
+    assert_eq!(
+        evaluate(&optimized, &input, &input_pub),
+        evaluate(&source, &input, &input_pub)
+    );
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::{compile, error, run, uint};
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use serde_json::json;
+use zoker_compiler::bristol::BristolCircuit;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+
+const VAULT: &str = "contract Vault {\
+       private uint balance;\
+       function deposit(uint amount) {\
+         balance = balance + amount;\
+       }\
+       function check(uint limit) returns (bool) {\
+         return reveal(balance >= limit);\
+       }\
+    }";
+
+// Words of the commitment `sha256(value, salt)`.
+fn commitment(value: u32, salt: u32) -> Vec<u32> {
+    let mut hasher = Sha256::new();
+    for word in [uint(value), uint(salt)].concat() {
+        hasher.input(&word.to_be_bytes());
+    }
+    let mut digest = [0; 32];
+    hasher.result(&mut digest);
+    digest
+        .chunks(4)
+        .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[test]
+fn test_private_state_deposit() {
+    let contracts = compile(VAULT);
+    let deposit = &contracts[0].functions[0];
+    assert!(deposit.state[0].local.is_private);
+    let json = "{\"amount\": 5, \"storage\": {\"balance\": \
+                {\"value\": 10, \"salt\": \"0x2a\", \"new_salt\": 43}}}";
+    let output = run(deposit, json);
+    assert_eq!(output, [commitment(10, 42), commitment(15, 43)].concat());
+
+    // The opening is private and only the commitments are output.
+    let witness = Witness::from_json(deposit, json).unwrap();
+    assert_eq!(witness.input, [uint(10), uint(42), uint(43)].concat());
+    assert_eq!(witness.input_pub, uint(5));
+
+    let check = &contracts[0].functions[1];
+    let json = "{\"limit\": 3, \"storage\": {\"balance\": {\"value\": 4, \"salt\": 7}}}";
+    assert_eq!(run(check, json), [vec![1], commitment(4, 7)].concat());
+    let json = "{\"limit\": 3, \"storage\": {\"balance\": {\"salt\": 7, \"owner\": 1}}}";
+    assert!(Witness::from_json(check, json).is_err());
+}
+
+#[test]
+fn test_private_state_early_return() {
+    let contracts = compile(
+        "contract Test {\
+           private uint total;\
+           function f(uint s) returns (uint) {\
+             if s > 10 {\
+               return 0;\
+             };\
+             total = 1;\
+             return 0;\
+           }\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    let json = |s: u32| {
+        format!(
+            "{{\"s\": {}, \"storage\": {{\"total\": {{\"value\": 7, \"salt\": 2, \
+             \"new_salt\": 3}}}}}}",
+            s
+        )
+    };
+    let output = |value: u32| [uint(0), commitment(7, 2), commitment(value, 3)].concat();
+    assert_eq!(run(f, &json(5)), output(1));
+    // The new commitment opens to the old value after the `return` taken.
+    assert_eq!(run(f, &json(50)), output(7));
+}
+
+#[test]
+fn test_private_state_layout() {
+    let contracts = compile(VAULT);
+    let deposit = &contracts[0].functions[0];
+    let layout = Layout::of(deposit);
+    let state = &layout.state[0];
+    assert!(state.old.is_private);
+    assert_eq!(state.old.offset, 0);
+    assert!(state.new.is_none());
+    let commitment = state.commitment.as_ref().unwrap();
+    assert_eq!(commitment.salt.offset, 8);
+    assert_eq!(commitment.new_salt.as_ref().unwrap().offset, 16);
+    assert_eq!(commitment.old.offset, 0);
+    assert_eq!(commitment.new.as_ref().unwrap().offset, 8);
+    let json = manifest(deposit, CircuitOptions::default());
+    assert_eq!(json["input_words"], 24);
+    assert_eq!(json["output_words"], 16);
+    assert_eq!(
+        json["state"][0]["commitment"]["salt"],
+        json!({"name": "balance", "type": "bytes32", "private": true, "offset": 8, "words": 8})
+    );
+
+    let circuit = BristolCircuit::from_function(deposit, CircuitOptions::default()).unwrap();
+    assert_eq!(circuit.inputs, vec![256, 256, 256, 256]);
+    assert_eq!(circuit.outputs, vec![256, 256]);
+}
+
+#[test]
+fn test_private_state_verifier() {
+    let contracts = compile(VAULT);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    // The balance starts out as a commitment to 0, with a zero salt.
+    let initial = commitment(0, 0)
+        .iter()
+        .map(|word| format!("{:08x}", word))
+        .collect::<String>();
+    assert!(code.contains(&format!("bytes32 public balance = 0x{};", initial)));
+    assert!(code.contains("require(decode_bytes32(output, 0) == balance, \"stale storage read\");"));
+    assert!(code.contains("balance = decode_bytes32(output, 8);"));
+    assert!(code.contains("require(decode_bytes32(output, 1) == balance, \"stale storage read\");"));
+    assert!(code.contains(") public view returns (bool) {"));
+}
+
+#[test]
+fn test_private_state_errors() {
+    assert_eq!(
+        error("contract Test { private mapping(uint => uint) m; }"),
+        RewriteErrorType::UnsupportedError
+    );
+    assert_eq!(
+        error("contract Test { private string name; }"),
+        RewriteErrorType::TypeError(
+            "state variables must have a fixed size, found string".to_string()
+        )
+    );
+    // Structs with private fields may be private state.
+    compile(
+        "contract Test {\
+           struct Note {\
+             private uint value;\
+           }\
+           private struct Note note;\
+        }",
+    );
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::{compile, run_with, uint};
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::{execute, Step};
+use zoker_compiler::layout::manifest;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::prover::prove;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+use zoker_parser::location::Location;
+use zoker_prover::zkboo::ZkBoo;
+
+const ESCROW: &str = "contract Escrow {
+  function release(private uint secret, uint amount, uint limit) returns (uint) {
+    require(amount <= limit);
+    if amount > 5 {
+      assert(secret != 0);
+    };
+    return amount;
+  }
+}";
+
+fn json(secret: u32, amount: u32, limit: u32) -> String {
+    format!(
+        "{{\"secret\": {}, \"amount\": {}, \"limit\": {}}}",
+        secret, amount, limit
+    )
+}
+
+#[test]
+fn test_require_valid_word() {
+    let contracts = compile(ESCROW);
+    let release = &contracts[0].functions[0];
+    assert!(release.has_requirements());
+    let options = CircuitOptions::default();
+    assert_eq!(
+        run_with(release, options, &json(0, 3, 4)),
+        [uint(3), vec![1]].concat()
+    );
+    assert_eq!(
+        run_with(release, options, &json(0, 6, 4)),
+        [uint(6), vec![0]].concat()
+    );
+    // The `assert` only counts when its branch is taken.
+    assert_eq!(
+        run_with(release, options, &json(0, 6, 9)),
+        [uint(6), vec![0]].concat()
+    );
+    assert_eq!(
+        run_with(release, options, &json(1, 6, 9)),
+        [uint(6), vec![1]].concat()
+    );
+
+    let witness = Witness::from_json(release, &json(0, 6, 9)).unwrap();
+    let execution = execute(release, options, &witness.args, &witness.storage).unwrap();
+    let failed = execution.failed.unwrap();
+    assert!(failed.is_assert);
+    assert_eq!(failed.location, Location::new(4, 8));
+    assert_eq!(
+        execution.trace.last().unwrap().to_string(),
+        "return 6".to_string()
+    );
+    assert!(execution.trace.contains(&Step::Fail {
+        requirement: failed
+    }));
+
+    // Checked arithmetic puts `ok` after the validity word.
+    let checked = CircuitOptions {
+        checked_arithmetic: true,
+        ..CircuitOptions::default()
+    };
+    assert_eq!(
+        run_with(release, checked, &json(0, 6, 4)),
+        [uint(6), vec![0, 1]].concat()
+    );
+    let json = manifest(release, checked);
+    // The `valid` and `ok` words are not return values.
+    assert_eq!(json["returns"].as_array().unwrap().len(), 1);
+    assert_eq!(json["valid"], 8);
+    assert_eq!(json["ok"], 9);
+}
+
+#[test]
+fn test_require_after_return() {
+    let contracts = compile(
+        "contract Test {\
+           function check(uint a) {\
+             require(a > 2);\
+           }\
+           function f(uint x) returns (uint) {\
+             if x == 0 {\
+               return 0;\
+             };\
+             check(x);\
+             return x;\
+           }\
+           function g(uint x) returns (uint) {\
+             return x;\
+           }\
+        }",
+    );
+    let f = &contracts[0].functions[1];
+    let options = CircuitOptions::default();
+    // Requirements after a `return` taken do not run.
+    assert_eq!(
+        run_with(f, options, "{\"x\": 0}"),
+        [uint(0), vec![1]].concat()
+    );
+    assert_eq!(
+        run_with(f, options, "{\"x\": 2}"),
+        [uint(2), vec![0]].concat()
+    );
+    assert_eq!(
+        run_with(f, options, "{\"x\": 3}"),
+        [uint(3), vec![1]].concat()
+    );
+    // Functions without any have no validity word.
+    let g = &contracts[0].functions[2];
+    assert!(!g.has_requirements());
+    assert_eq!(run_with(g, options, "{\"x\": 3}"), uint(3));
+}
+
+#[test]
+fn test_require_verifier() {
+    let contracts = compile(ESCROW);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("require(output.length == 9);"));
+    assert!(code.contains("require(output[8] == 1, \"requirement failed\");"));
+
+    let checked = CircuitOptions {
+        checked_arithmetic: true,
+        ..CircuitOptions::default()
+    };
+    let code = rewrite_verifier(&contracts, checked).unwrap();
+    assert!(code.contains("require(output[8] == 1, \"requirement failed\");"));
+    assert!(code.contains("require(output[9] == 1, \"arithmetic overflow\");"));
+}
+
+#[test]
+fn test_require_prove() {
+    let contracts = compile(ESCROW);
+    let release = &contracts[0].functions[0];
+    let options = CircuitOptions::default();
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+
+    let witness = Witness::from_json(release, &json(1, 6, 9)).unwrap();
+    let proof = prove(&zk_boo, release, options, &witness).unwrap();
+    assert_eq!(proof.output, [uint(6), vec![1]].concat());
+
+    let witness = Witness::from_json(release, &json(1, 6, 4)).unwrap();
+    let err = match prove(&zk_boo, release, options, &witness) {
+        Ok(_) => panic!("proved a failed requirement"),
+        Err(err) => err,
+    };
+    assert_eq!(
+        err.error,
+        RewriteErrorType::RequirementError("`require` failed".to_string())
+    );
+    assert_eq!(err.location, Location::new(2, 6));
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use common::{compile, error, run, uint};
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::execute;
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::symbol::OperationType;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+use zoker_parser::location::Location;
+use zoker_parser::parser;
+
+#[test]
+fn test_reveal_output() {
+    let contracts = compile(
+        "contract Test {\
+           uint total;\
+           function f(private uint a, uint b) returns (bool) {\
+             total = total + reveal(a * 2);\
+             return reveal(a > b);\
+           }\
+           function g(private uint a, uint b) returns (private uint) {\
+             return a + b;\
+           }\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    match &f.operations[1].operation {
+        OperationType::Return { ret } => {
+            assert!(matches!(ret.operation, OperationType::Reveal { .. }))
+        }
+        _ => unreachable!(),
+    }
+    let json = "{\"a\": 4, \"b\": 3, \"storage\": {\"total\": 1}}";
+    assert_eq!(run(f, json), [vec![1], uint(9)].concat());
+
+    // A private return value stays an internal wire.
+    let g = &contracts[0].functions[1];
+    assert!(run(g, "{\"a\": 4, \"b\": 3}").is_empty());
+    let witness = Witness::from_json(g, "{\"a\": 4, \"b\": 3}").unwrap();
+    let execution = execute(
+        g,
+        CircuitOptions::default(),
+        &witness.args,
+        &witness.storage,
+    )
+    .unwrap();
+    assert_eq!(execution.outputs[0].to_string(), "7");
+    assert!(Layout::of(g).returns.is_empty());
+    let json = manifest(g, CircuitOptions::default());
+    assert_eq!(json["output_words"], 0);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("require(output.length == 0);"));
+}
+
+#[test]
+fn test_reveal_flow() {
+    // Private fields stay private in public structs, and values only
+    // become private once assigned from private ones, even in later
+    // iterations of a loop.
+    let contracts = compile(
+        "contract Test {\
+           struct Note {\
+             private uint value;\
+             uint tag;\
+           }\
+           function f(struct Note note, private uint[2] xs) returns (uint) {\
+             uint sum = 0;\
+             uint last = 0;\
+             for x in xs {\
+               sum = sum + reveal(x);\
+               last = x;\
+             };\
+             note.value = last;\
+             return sum + note.tag;\
+           }\
+        }",
+    );
+    let json = "{\"note\": {\"value\": 1, \"tag\": 2}, \"xs\": [3, 4]}";
+    assert_eq!(run(&contracts[0].functions[0], json), uint(9));
+}
+
+#[test]
+fn test_reveal_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    let leak = type_error("return value depends on private values; `reveal` it");
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint a) returns (uint) {\
+                 return a + 1;\
+               }\
+            }"
+        ),
+        leak
+    );
+    // Assignments under a private condition show the condition.
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint a) returns (uint) {\
+                 uint big = 0;\
+                 if a > 5 {\
+                   big = 1;\
+                 };\
+                 return big;\
+               }\
+            }"
+        ),
+        leak
+    );
+    // So do assignments in earlier iterations.
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint[2] xs) returns (uint) {\
+                 uint y = 0;\
+                 uint z = 0;\
+                 for x in xs {\
+                   z = y;\
+                   y = x;\
+                 };\
+                 return z;\
+               }\
+            }"
+        ),
+        leak
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Note {\
+                 private uint value;\
+                 uint tag;\
+               }\
+               function f(struct Note note) returns (uint) {\
+                 return note.value;\
+               }\
+            }"
+        ),
+        leak
+    );
+    // Whether an early `return` is taken shows its condition.
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(private uint s) returns (uint) {\
+                 if s > 10 {\
+                   return 1;\
+                 };\
+                 return 0;\
+               }\
+            }"
+        ),
+        leak
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               event E(uint a);\
+               function f(private uint s) returns (uint) {\
+                 if s > 10 {\
+                   return reveal(0);\
+                 };\
+                 emit E(1);\
+                 return 0;\
+               }\
+            }"
+        ),
+        leak
+    );
+    // So does whether what follows it runs.
+    assert_eq!(
+        error(
+            "contract Test {\
+               event E(uint a);\
+               function f(private uint s) returns (private uint) {\
+                 if s > 10 {\
+                   return 0;\
+                 };\
+                 emit E(1);\
+                 return 1;\
+               }\
+            }"
+        ),
+        type_error("`E` is emitted under a condition on private values")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               uint total;\
+               function f(private uint a) {\
+                 total = total + a;\
+               }\
+            }"
+        ),
+        type_error("`total` depends on private values; `reveal` it")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint a, uint k) {\
+                 m[k] = a;\
+               }\
+            }"
+        ),
+        type_error("value written to `m` depends on private values; `reveal` it")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint a) {\
+                 uint k = 0;\
+                 if a > 1 {\
+                   k = 1;\
+                 };\
+                 m[k] = 1;\
+               }\
+            }"
+        ),
+        type_error("key of `m` depends on private values; hash it with `sha256` or `reveal` it")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               mapping(uint => uint) m;\
+               function f(private uint a, uint k) {\
+                 if a > 5 {\
+                   m[k] = 1;\
+                 };\
+               }\
+            }"
+        ),
+        type_error("`m` is accessed under a condition on private values")
+    );
+    let program = parser::parse_program(
+        "contract Test {
+           function f(uint a, uint b) returns (uint) {
+             return reveal(a, b);
+           }
+        }",
+    )
+    .unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(err.error, type_error("`reveal` takes one value"));
+    assert_eq!(err.location, Location::new(2, 22));
+
+    // Leaks found after rewriting are reported where they happen.
+    let program = parser::parse_program(
+        "contract Test {
+           uint total;
+           function f(private uint a, uint b) {
+             uint c = b;
+             total = a + c;
+           }
+        }",
+    )
+    .unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(
+        err.error,
+        type_error("`total` depends on private values; `reveal` it")
+    );
+    assert_eq!(err.location, Location::new(4, 15));
+}
@@ -1,12 +1,14 @@
+use zoker_compiler::error::RewriteErrorType;
 use zoker_compiler::rewriter::rewrite_program;
-use zoker_compiler::symbol::SymbolType;
+use zoker_compiler::symbol::{OperationType, SymbolType};
+use zoker_parser::location::Location;
 use zoker_parser::parser;
 
 #[test]
 fn test_rewriting() {
     let source = "contract Test {\
            function add(private uint a, uint b) returns (uint) {\
-             return a + b + 1;\
+             return reveal(a + b + 1);\
            }\
         }";
     let res = parser::parse_program(source);
@@ -36,10 +38,10 @@ fn test_rewriting() {
 fn test_rewriting_multiple_function() {
     let source = "contract Test {\
            function add(private uint a, uint b) returns (uint) {\
-             return a + b + 1;\
+             return reveal(a + b + 1);\
            }\
            function add2(private uint a, uint b, uint c) returns (uint) {\
-             return add(add(a + b) + c);\
+             return add(add(a, b), c);\
            }\
         }";
     let res = parser::parse_program(source);
@@ -55,3 +57,92 @@ fn test_rewriting_multiple_function() {
     assert_eq!(contracts[0].functions[1].params[1].num, 0);
     assert_eq!(contracts[0].functions[1].params[2].num, 1);
 }
+
+#[test]
+fn test_rewriting_sha256_builtin() {
+    let source = "contract Test {\
+           function hash(private uint preimage) returns (bytes32) {\
+             return sha256(preimage);\
+           }\
+        }";
+    let program = parser::parse_program(source).unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let function = &contracts[0].functions[0];
+    assert_eq!(function.returns[0].symbol_type, SymbolType::Bytes32);
+    match &function.operations[0].operation {
+        OperationType::Return { ret } => match &ret.operation {
+            OperationType::Sha256 { args } => {
+                assert_eq!(args.len(), 1);
+                assert_eq!(args[0].as_symbol().unwrap().name, "preimage");
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_rewriting_shift() {
+    let source = "contract Test {\
+           function shift(uint a, uint b) returns (uint) {\
+             return (a << 3) + (b >> 1);\
+           }\
+        }";
+    let program = parser::parse_program(source).unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    match &contracts[0].functions[0].operations[0].operation {
+        OperationType::Return { ret } => match &ret.operation {
+            OperationType::Add { left, right } => {
+                assert!(matches!(left.operation, OperationType::LShift { .. }));
+                assert!(matches!(right.operation, OperationType::RShift { .. }));
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_rewriting_unknown_variable() {
+    let source = "contract Test {
+  function f(uint a) returns (uint) {
+    return a + y;
+  }
+}";
+    let program = parser::parse_program(source).unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(
+        err.error,
+        RewriteErrorType::TypeError("unknown variable `y`".to_string())
+    );
+    assert_eq!(err.location, Location::new(2, 17));
+}
+
+#[test]
+fn test_rewriting_multiple_returns() {
+    let source = "contract Test {
+  function f(uint a) returns (uint, uint) {
+    return a;
+  }
+}";
+    let program = parser::parse_program(source).unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(
+        err.error,
+        RewriteErrorType::TypeError("`f` returns more than one value".to_string())
+    );
+    assert_eq!(err.location, Location::new(1, 38));
+
+    let source = "contract Test {
+  function f(uint a) returns (uint) {
+    return (a, a);
+  }
+}";
+    let program = parser::parse_program(source).unwrap();
+    let err = rewrite_program(&program).unwrap_err();
+    assert_eq!(
+        err.error,
+        RewriteErrorType::TypeError("`return` takes one value".to_string())
+    );
+    assert_eq!(err.location, Location::new(2, 6));
+}
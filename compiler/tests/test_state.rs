@@ -0,0 +1,184 @@
+mod common;
+
+use common::{compile, error, run, uint};
+use serde_json::json;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+
+const COUNTER: &str = "contract Counter {\
+       uint storage total;\
+       bool open;\
+       function add(uint amount) returns (uint) {\
+         if open {\
+           total = total + amount;\
+         };\
+         return total;\
+       }\
+       function peek() returns (uint) {\
+         return total;\
+       }\
+    }";
+
+#[test]
+fn test_state_counter() {
+    let contracts = compile(COUNTER);
+    let add = &contracts[0].functions[0];
+    let state = add
+        .state
+        .iter()
+        .map(|state| (state.variable.name.as_str(), state.is_write))
+        .collect::<Vec<_>>();
+    assert_eq!(state, vec![("open", false), ("total", true)]);
+
+    let json = |open: bool| {
+        format!(
+            "{{\"amount\": 3, \"storage\": {{\"total\": 15, \"open\": {}}}}}",
+            open
+        )
+    };
+    assert_eq!(run(add, &json(true)), [uint(18), uint(18)].concat());
+    // Written state is output even if the write did not run.
+    assert_eq!(run(add, &json(false)), [uint(15), uint(15)].concat());
+    // Missing state is 0.
+    assert_eq!(run(add, "{\"amount\": 3}"), vec![0; 16]);
+
+    let peek = &contracts[0].functions[1];
+    assert_eq!(run(peek, "{\"storage\": {\"total\": 4}}"), uint(4));
+}
+
+#[test]
+fn test_state_early_return() {
+    let contracts = compile(
+        "contract Test {\
+           uint total;\
+           function f(uint s) returns (uint) {\
+             if s > 10 {\
+               return 0;\
+             };\
+             total = 1;\
+             return 0;\
+           }\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    let json = |s: u32| format!("{{\"s\": {}, \"storage\": {{\"total\": 7}}}}", s);
+    assert_eq!(run(f, &json(5)), [uint(0), uint(1)].concat());
+    // A write after the `return` taken does not run.
+    assert_eq!(run(f, &json(50)), [uint(0), uint(7)].concat());
+}
+
+#[test]
+fn test_state_declared_after() {
+    let contracts = compile(
+        "contract Test {\
+           constructor(uint a) {\
+             total = a;\
+           }\
+           function f() returns (uint) {\
+             return total;\
+           }\
+           uint total;\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    assert_eq!(run(f, "{\"storage\": {\"total\": 4}}"), uint(4));
+}
+
+#[test]
+fn test_state_witness() {
+    let contracts = compile(COUNTER);
+    let add = &contracts[0].functions[0];
+    let json = "{\"amount\": 3, \"storage\": {\"total\": 15, \"open\": true}}";
+    let witness = Witness::from_json(add, json).unwrap();
+    // State follows the public arguments in order of first use.
+    assert_eq!(witness.input_pub, [uint(3), vec![1], uint(15)].concat());
+    let json = "{\"amount\": 3, \"storage\": {\"total\": true}}";
+    assert!(Witness::from_json(add, json).is_err());
+
+    let layout = Layout::of(add);
+    assert_eq!(layout.state[0].old.offset, 8);
+    assert!(layout.state[0].new.is_none());
+    assert_eq!(layout.state[1].old.offset, 9);
+    assert_eq!(layout.state[1].new.as_ref().unwrap().offset, 8);
+    let json = manifest(add, CircuitOptions::default());
+    assert_eq!(json["input_pub_words"], 17);
+    assert_eq!(json["output_words"], 16);
+    let total = |offset| json!({"name": "total", "type": "uint256", "private": false, "offset": offset, "words": 8});
+    assert_eq!(
+        json["state"][1],
+        json!({"variable": "total", "old": total(9), "new": total(8)})
+    );
+}
+
+#[test]
+fn test_state_verifier() {
+    let contracts = compile(COUNTER);
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("uint256 public total;"));
+    assert!(code.contains("bool public open;"));
+    assert!(code.contains("require(input_pub[8] <= 1, \"bool out of range\");"));
+    assert!(code.contains("require(decode_bool(input_pub, 8) == open, \"stale storage read\");"));
+    assert!(
+        code.contains("require(decode_uint256(input_pub, 9) == total, \"stale storage read\");")
+    );
+    assert!(code.contains("total = decode_uint256(output, 8);"));
+    assert!(!code.contains("open = "));
+    assert!(code.contains(") public view returns (bool) {"));
+
+    let contracts = compile(
+        "contract Board {\
+           struct Slot {\
+             address owner;\
+             uint price;\
+           }\
+           struct Slot[2] slots;\
+           function buy(address owner) {\
+             slots[1].owner = owner;\
+           }\
+        }",
+    );
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code.contains("Slot[2] public slots;"));
+    assert!(code.contains(
+        "require(decode_uint256(input_pub, 10) == slots[0].price, \"stale storage read\");"
+    ));
+    assert!(code.contains("slots[1].owner = decode_address(output, 13);"));
+    assert!(code.contains(") public returns (bool) {"));
+}
+
+#[test]
+fn test_state_errors() {
+    let type_error = |message: &str| RewriteErrorType::TypeError(message.to_string());
+    assert_eq!(
+        error("contract Test { uint memory x; }"),
+        type_error("state variables live in storage")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f() {\
+                 uint storage x = 1;\
+               }\
+            }"
+        ),
+        type_error("only contract state lives in storage")
+    );
+    assert_eq!(
+        error("contract Test { string name; }"),
+        type_error("state variables must have a fixed size, found string")
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Note {\
+                 private uint value;\
+               }\
+               struct Note note;\
+            }"
+        ),
+        type_error("struct Note has private fields")
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::compile_function;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::prover::prover_circuit;
+use zoker_compiler::stats::{circuit_stats, function_stats, ProofParams};
+use zoker_prover::ikos::IKosView;
+use zoker_prover::zkboo::{ProvingProof, ZkBoo};
+
+fn view_bytes(view: &IKosView) -> usize {
+    view.rand_tape_seed.len() + 4 * (view.in_data.len() + view.out_data.len())
+}
+
+#[test]
+fn test_stats_match_proof() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a, uint b) returns (uint) {\
+             return reveal(a * b + a);\
+           }\
+        }",
+    );
+    let circuit = Circuit::from_function(&function, CircuitOptions::default()).unwrap();
+    let params = ProofParams::default();
+    let stats = circuit_stats(&circuit, &params);
+    assert_eq!((stats.input_words, stats.input_pub_words), (8, 8));
+    assert_eq!(stats.output_words, 8);
+    assert_eq!(stats.non_linear_gates(), stats.and_gates + stats.add_gates);
+    assert!(stats.and_gates > 0 && stats.add_gates > 0);
+
+    let zk_boo = ZkBoo::new(params.num_of_round, 3, 2, params.commit_length);
+    let mut proof = zk_boo
+        .prove(ProvingProof::new(
+            vec![3; 8],
+            vec![5; 8],
+            stats.output_words,
+            prover_circuit(&circuit),
+        ))
+        .unwrap();
+    for view in &proof.views {
+        assert_eq!(
+            view.out_data.len(),
+            stats.non_linear_gates() + stats.output_words
+        );
+    }
+    let largest = proof.views.iter().map(view_bytes).max().unwrap();
+    assert_eq!(largest, stats.view_bytes);
+
+    let challenge = ZkBoo::query_random_oracle(
+        proof.input_len,
+        proof.output_len,
+        &proof.out_data,
+        &proof.three_views,
+    );
+    let response = zk_boo.build_response(&proof.views, &challenge);
+    let two_views = zk_boo.rebuild_proof(&mut proof, &challenge);
+    let sent = response.iter().map(view_bytes).sum::<usize>()
+        + two_views.len()
+        + challenge.len()
+        + 4 * proof.output.len();
+    assert!(sent <= stats.proof_bytes);
+}
+
+#[test]
+fn test_stats_optimized_circuit_is_cheaper() {
+    let function = compile_function(
+        "contract Test {\
+           function f(private uint a) returns (uint) {\
+             return reveal(a * 3 + 4 * 5);\
+           }\
+        }",
+    );
+    let params = ProofParams::default();
+    let plain = function_stats(&function, CircuitOptions::default(), &params).unwrap();
+    let optimized = CircuitOptions {
+        optimize: true,
+        ..CircuitOptions::default()
+    };
+    let optimized = function_stats(&function, optimized, &params).unwrap();
+    assert!(optimized.non_linear_gates() <= plain.non_linear_gates());
+    assert!(optimized.verify_gas <= plain.verify_gas);
+    assert!(optimized.to_string().contains("non-linear gates"));
+}
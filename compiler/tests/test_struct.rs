@@ -0,0 +1,235 @@
+mod common;
+
+use common::{compile, compile_function, error, run, uint};
+use serde_json::json;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::error::RewriteErrorType;
+use zoker_compiler::interpreter::interpret;
+use zoker_compiler::layout::{manifest, Layout};
+use zoker_compiler::lowering::{input_len, output_len, CircuitOptions};
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_compiler::witness::Witness;
+use zoker_parser::parser;
+
+const BANK: &str = "contract Bank {\
+       struct Account {\
+         private uint balance;\
+         address owner;\
+         uint nonce;\
+       }\
+       function deposit(struct Account a, uint amount) returns (struct Account) {\
+         a.balance = a.balance + amount;\
+         a.nonce = a.nonce + 1;\
+         return a;\
+       }\
+    }";
+
+#[test]
+fn test_struct_fields() {
+    let function = compile_function(BANK);
+    let json = "{\"a\": {\"balance\": 5, \"owner\": \"0x1f\", \"nonce\": 2}, \"amount\": 7}";
+    let witness = Witness::from_json(&function, json).unwrap();
+    // The private balance is a private input even though `a` is public.
+    assert_eq!(witness.input, uint(5));
+    let mut input_pub = vec![0, 0, 0, 0, 0x1f];
+    input_pub.extend(uint(2));
+    input_pub.extend(uint(7));
+    assert_eq!(witness.input_pub, input_pub);
+    assert_eq!(input_len(&function), (8, 21));
+
+    // Only the public fields are output.
+    let mut output = vec![0, 0, 0, 0, 0x1f];
+    output.extend(uint(3));
+    assert_eq!(run(&function, json), output);
+    assert_eq!(output_len(&function, CircuitOptions::default()), 13);
+    let execution = interpret(&function, CircuitOptions::default(), &witness.args).unwrap();
+    assert_eq!(
+        execution.outputs[0].to_string(),
+        "{balance: 12, owner: 0x1f, nonce: 3}"
+    );
+
+    assert!(Witness::from_json(&function, "{\"a\": {\"balance\": 5}, \"amount\": 7}").is_err());
+    assert!(Witness::from_json(&function, "{\"a\": 5, \"amount\": 7}").is_err());
+}
+
+#[test]
+fn test_struct_nesting() {
+    let function = compile_function(
+        "contract Test {\
+           struct Point {\
+             uint x;\
+             uint y;\
+           }\
+           struct Segment {\
+             struct Point a;\
+             struct Point b;\
+           }\
+           function f(private struct Point[3] ps, uint i) returns (uint) {\
+             struct Segment s;\
+             s.a = ps[i];\
+             s.b.x = ps[2].y;\
+             if s.a.x > 5 {\
+               s.b.y = 1;\
+             };\
+             return reveal(s.a.x + s.b.x * 10 + s.b.y * 100);\
+           }\
+        }",
+    );
+    let ps = "[{\"x\": 1, \"y\": 2}, {\"x\": 6, \"y\": 4}, {\"x\": 3, \"y\": 5}]";
+    let expected = [1 + 50, 6 + 50 + 100, 3 + 50];
+    for (i, expected) in expected.iter().enumerate() {
+        let json = format!("{{\"ps\": {}, \"i\": {}}}", ps, i);
+        assert_eq!(run(&function, &json), uint(*expected));
+    }
+}
+
+#[test]
+fn test_struct_layout() {
+    let function = compile_function(BANK);
+    let layout = Layout::of(&function);
+    let params = layout
+        .params
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry.is_private, entry.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        params,
+        vec![
+            ("a.balance", true, 0),
+            ("a.owner", false, 0),
+            ("a.nonce", false, 5),
+            ("amount", false, 13),
+        ]
+    );
+    let returns = layout
+        .returns
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry.offset, entry.words))
+        .collect::<Vec<_>>();
+    assert_eq!(returns, vec![("owner", 0, 5), ("nonce", 5, 8)]);
+
+    let json = manifest(&function, CircuitOptions::default());
+    assert_eq!(
+        json["params"][0],
+        json!({"name": "a.balance", "type": "uint256", "private": true, "offset": 0, "words": 8})
+    );
+    assert_eq!(json["input_pub_words"], 21);
+    assert_eq!(json["output_words"], 13);
+}
+
+#[test]
+fn test_struct_verifier() {
+    let program = parser::parse_program(BANK).unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let code = rewrite_verifier(&contracts, CircuitOptions::default()).unwrap();
+    assert!(code
+        .contains("    struct Account {\n        address owner;\n        uint256 nonce;\n    }\n"));
+    assert!(code.contains(
+        "function decode_Account(uint32[] memory words, uint start) internal pure returns (Account memory value) {"
+    ));
+    assert!(code.contains("value.owner = decode_address(words, start + 0);"));
+    assert!(code.contains("value.nonce = decode_uint256(words, start + 5);"));
+    assert!(!code.contains("balance"));
+    assert!(code.contains("require(input_pub.length == 21);"));
+}
+
+#[test]
+fn test_struct_declared_after() {
+    let contracts = compile(
+        "contract Test {\
+           struct Pair a;\
+           function f(struct Pair p) returns (uint) {\
+             return p.left.x + a.right.x;\
+           }\
+           struct Pair {\
+             struct Point left;\
+             struct Point right;\
+           }\
+           struct Point {\
+             uint x;\
+           }\
+        }",
+    );
+    let f = &contracts[0].functions[0];
+    let json = "{\"p\": {\"left\": {\"x\": 2}, \"right\": {\"x\": 0}}, \
+                \"storage\": {\"a\": {\"left\": {\"x\": 0}, \"right\": {\"x\": 3}}}}";
+    assert_eq!(run(f, json), uint(5));
+}
+
+#[test]
+fn test_struct_errors() {
+    assert_eq!(
+        error(
+            "contract Test {\
+               function f(struct Point p) returns (uint) {\
+                 return 1;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("unknown struct `Point`".to_string())
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Point {\
+                 uint x;\
+               }\
+               function f(struct Point p) returns (uint) {\
+                 return p.z;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("struct Point has no field `z`".to_string())
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Point {\
+                 uint x;\
+                 bool x;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("field `x` is declared twice".to_string())
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Names {\
+                 string name;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError(
+            "struct fields must have a fixed size, found string".to_string()
+        )
+    );
+    assert_eq!(
+        error(
+            "contract Test {\
+               struct Point {\
+                 uint x = 1;\
+               }\
+            }"
+        ),
+        RewriteErrorType::TypeError("struct fields are a type and a name".to_string())
+    );
+
+    let function = compile_function(
+        "contract Test {\
+           struct Point {\
+             uint x;\
+           }\
+           function f(struct Point p) returns (uint) {\
+             return p + 1;\
+           }\
+        }",
+    );
+    assert_eq!(
+        Circuit::from_function(&function, CircuitOptions::default())
+            .unwrap_err()
+            .error,
+        RewriteErrorType::TypeError("arithmetic on a struct".to_string())
+    );
+}
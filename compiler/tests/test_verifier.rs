@@ -0,0 +1,78 @@
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::verifier::rewrite_verifier;
+use zoker_parser::parser;
+
+fn generate(source: &str) -> String {
+    let program = parser::parse_program(source).unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    rewrite_verifier(&contracts, CircuitOptions::default()).unwrap()
+}
+
+#[test]
+fn test_verifier_contract() {
+    let code = generate(
+        "contract Test {\
+           function add(private uint a, uint b) returns (uint) {\
+             return reveal(a + b);\
+           }\
+        }",
+    );
+    assert!(code.contains("import \"./zkboo.sol\";"));
+    assert!(code.contains("contract Test is ZKBoo {"));
+    assert!(code.contains(
+        "function _circuit_add(IKosVariable4V[] memory input, uint32[] memory input_pub)"
+    ));
+    assert!(code.contains("add_op(input[7], IKosVariable_new_value(input_pub[7]))"));
+    assert!(code.contains("require(input_pub.length == 8);"));
+    assert!(code.contains(
+        "return ZKBoo_verify(VerifyingProof(8, input_pub, output, challenge, two_views, response, _circuit_add));"
+    ));
+}
+
+#[test]
+fn test_verifier_signed_comparison() {
+    let code = generate(
+        "contract Test {\
+           function lt(private int a, int b) returns (bool) {\
+             return reveal(a < b);\
+           }\
+        }",
+    );
+    // The sign bits are flipped before the unsigned comparison.
+    assert!(code.contains("_xor(input[0], IKosVariable_new_value(2147483648))"));
+    assert!(code.contains("IKosVariable4V[] memory output = new IKosVariable4V[](1);"));
+}
+
+#[test]
+fn test_verifier_constant_gates_are_folded() {
+    let code = generate(
+        "contract Test {\
+           function one() returns (uint) {\
+             return 1 + 2;\
+           }\
+        }",
+    );
+    assert!(code.contains("IKosVariable4V[] memory w = new IKosVariable4V[](0);"));
+    assert!(code.contains("output[7] = IKosVariable_new_value(3);"));
+}
+
+#[test]
+fn test_verifier_checked_arithmetic() {
+    let program = parser::parse_program(
+        "contract Test {\
+           function add(private uint a, uint b) returns (uint) {\
+             return reveal(a + b);\
+           }\
+        }",
+    )
+    .unwrap();
+    let contracts = rewrite_program(&program).unwrap();
+    let options = CircuitOptions {
+        checked_arithmetic: true,
+        ..CircuitOptions::default()
+    };
+    let code = rewrite_verifier(&contracts, options).unwrap();
+    assert!(code.contains("require(output.length == 9);"));
+    assert!(code.contains("require(output[8] == 1, \"arithmetic overflow\");"));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::compile_function;
+use num_bigint::{BigInt, BigUint};
+use std::collections::HashMap;
+use zoker_compiler::circuit::Circuit;
+use zoker_compiler::interpreter::interpret;
+use zoker_compiler::lowering::{output_len, CircuitOptions};
+use zoker_compiler::prover::prover_circuit;
+use zoker_compiler::symbol::Function;
+use zoker_compiler::witness::{InputValue, Witness};
+use zoker_prover::zkboo::{ProvingProof, ZkBoo};
+
+fn function() -> Function {
+    compile_function(
+        "contract Test {\
+           function f(private int a, uint b, private bool c) returns (int) {\
+             if reveal(c) {\
+               return reveal(a - b);\
+             };\
+             return reveal(a);\
+           }\
+        }",
+    )
+}
+
+#[test]
+fn test_witness_from_json() {
+    let function = function();
+    let witness = Witness::from_json(&function, r#"{"a": -5, "b": "0x10", "c": true}"#).unwrap();
+    let minus_five = vec![
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX - 4,
+    ];
+    assert_eq!(witness.input, [minus_five, vec![1]].concat());
+    assert_eq!(witness.input_pub, vec![0, 0, 0, 0, 0, 0, 0, 16]);
+
+    let c = witness.layout.param("c").unwrap();
+    assert_eq!((c.is_private, c.offset, c.words), (true, 8, 1));
+    let b = witness.layout.param("b").unwrap();
+    assert_eq!((b.is_private, b.offset, b.words), (false, 0, 8));
+
+    let options = CircuitOptions::default();
+    let circuit = Circuit::from_function(&function, options).unwrap();
+    let proof = ZkBoo::new(2, 3, 2, 32)
+        .prove(ProvingProof::new(
+            witness.input.clone(),
+            witness.input_pub.clone(),
+            output_len(&function, options),
+            prover_circuit(&circuit),
+        ))
+        .unwrap();
+    let execution = interpret(&function, options, &witness.args).unwrap();
+    assert_eq!(proof.output, execution.output_words());
+    assert_eq!(execution.outputs[0].to_string(), "-21");
+}
+
+#[test]
+fn test_witness_large_numbers() {
+    // Numbers are read as written, so no precision is lost.
+    let json = r#"{"a": 1, "b": 115792089237316195423570985008687907853269984665640564039457584007913129639935, "c": false}"#;
+    let witness = Witness::from_json(&function(), json).unwrap();
+    assert_eq!(witness.input_pub, vec![u32::MAX; 8]);
+}
+
+#[test]
+fn test_witness_from_map() {
+    let function = function();
+    let mut values = HashMap::new();
+    values.insert("a".to_string(), InputValue::Number(BigInt::from(7)));
+    values.insert("b".to_string(), InputValue::Text("12".to_string()));
+    values.insert("c".to_string(), InputValue::Bool(false));
+    let witness = Witness::from_map(&function, &values).unwrap();
+    assert_eq!(
+        witness.args,
+        vec![
+            BigUint::from(7u32),
+            BigUint::from(12u32),
+            BigUint::from(0u32)
+        ]
+    );
+}
+
+#[test]
+fn test_witness_rejects_invalid_values() {
+    let function = function();
+    let invalid = [
+        r#"{"a": 1, "b": 2}"#,
+        r#"{"a": 1, "b": -2, "c": false}"#,
+        r#"{"a": 1, "b": 2, "c": 2}"#,
+        r#"{"a": 1, "b": 2, "c": false, "d": 3}"#,
+        r#"{"a": "0x8000000000000000000000000000000000000000000000000000000000000000", "b": 2, "c": 1}"#,
+        r#"{"a": 1, "b": true, "c": 1}"#,
+        r#"[1, 2, 3]"#,
+        r#"{"a": 1, "b": 2, "c": false"#,
+        r#"{"a": 1.5, "b": 2, "c": false}"#,
+    ];
+    for json in invalid.iter() {
+        assert!(Witness::from_json(&function, json).is_err(), "{}", json);
+    }
+}
@@ -23,13 +23,26 @@ pub enum StatementType {
     FunctionStatement {
         function_name: Box<Expression>,
         parameters: Box<Expression>,
+        specifiers: Vec<FunctionSpecifier>,
         statement: Box<Statement>,
         returns: Option<Box<Expression>>,
     },
+    ConstructorStatement {
+        parameters: Box<Expression>,
+        statement: Box<Statement>,
+    },
     ContractStatement {
         contract_name: Box<Expression>,
         members: Box<Statement>,
     },
+    StructStatement {
+        struct_name: Box<Expression>,
+        fields: Vec<Statement>,
+    },
+    EventStatement {
+        event_name: Box<Expression>,
+        parameters: Box<Expression>,
+    },
     InitializerStatement {
         variable_type: Type,
         is_private: bool,
@@ -48,6 +61,14 @@ pub enum StatementType {
     ReturnStatement {
         ret: Option<Box<Expression>>,
     },
+    RequireStatement {
+        condition: Box<Expression>,
+        is_assert: bool,
+    },
+    EmitStatement {
+        event_name: Box<Expression>,
+        arguments: Box<Expression>,
+    },
     Expression {
         expression: Box<Expression>,
     },
@@ -76,6 +97,14 @@ pub enum ExpressionType {
         function_name: Box<Expression>,
         arguments: Box<Expression>,
     },
+    IndexExpression {
+        array: Box<Expression>,
+        index: Box<Expression>,
+    },
+    MemberExpression {
+        expression: Box<Expression>,
+        member: Box<Expression>,
+    },
     IfExpression {
         condition: Box<Expression>,
         if_statement: Box<Statement>,
@@ -184,6 +213,19 @@ pub enum Specifier {
     Storage,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum FunctionSpecifier {
+    // Visibility
+    Public,
+    External,
+    Internal,
+    // State mutability
+    View,
+    Pure,
+    // Verified with a proof on chain
+    Proven,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Type {
     // Static size
@@ -196,7 +238,17 @@ pub enum Type {
     Bytes,
     String,
     Address,
+
+    // Dynamic size up to `max_len` bytes
+    BoundedBytes { max_len: usize },
+    BoundedString { max_len: usize },
+
+    // Fixed size array
+    Array { element: Box<Type>, size: usize },
+    // Contract storage
+    Mapping { key: Box<Type>, value: Box<Type> },
+    // Contract-level struct definition
+    Struct { name: String },
     // To be supported..
-    // Mapping,
     // Var,
 }
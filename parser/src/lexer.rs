@@ -33,16 +33,29 @@ fn get_keywords() -> HashMap<String, Tok> {
     keywords.insert(String::from("string"), Tok::String);
     keywords.insert(String::from("address"), Tok::Address);
     keywords.insert(String::from("function"), Tok::Function);
+    keywords.insert(String::from("constructor"), Tok::Constructor);
     keywords.insert(String::from("contract"), Tok::Contract);
-    keywords.insert(String::from("memory"), Tok::Contract);
-    keywords.insert(String::from("storage"), Tok::Contract);
+    keywords.insert(String::from("memory"), Tok::Memory);
+    keywords.insert(String::from("storage"), Tok::Storage);
     keywords.insert(String::from("if"), Tok::If);
     keywords.insert(String::from("else"), Tok::Else);
     keywords.insert(String::from("for"), Tok::For);
     keywords.insert(String::from("in"), Tok::In);
     keywords.insert(String::from("returns"), Tok::Returns);
     keywords.insert(String::from("return"), Tok::Return);
+    keywords.insert(String::from("require"), Tok::Require);
+    keywords.insert(String::from("assert"), Tok::Assert);
+    keywords.insert(String::from("event"), Tok::Event);
+    keywords.insert(String::from("emit"), Tok::Emit);
     keywords.insert(String::from("private"), Tok::Private);
+    keywords.insert(String::from("public"), Tok::Public);
+    keywords.insert(String::from("external"), Tok::External);
+    keywords.insert(String::from("internal"), Tok::Internal);
+    keywords.insert(String::from("view"), Tok::View);
+    keywords.insert(String::from("pure"), Tok::Pure);
+    keywords.insert(String::from("proven"), Tok::Proven);
+    keywords.insert(String::from("mapping"), Tok::Mapping);
+    keywords.insert(String::from("struct"), Tok::Struct);
 
     keywords
 }
@@ -176,6 +189,10 @@ where
                     token = Some(Tok::Comma);
                     break;
                 }
+                "." => {
+                    token = Some(Tok::Dot);
+                    break;
+                }
                 "{" => {
                     token = Some(Tok::LBrace);
                     break;
@@ -184,6 +201,14 @@ where
                     token = Some(Tok::RBrace);
                     break;
                 }
+                "[" => {
+                    token = Some(Tok::LBracket);
+                    break;
+                }
+                "]" => {
+                    token = Some(Tok::RBracket);
+                    break;
+                }
                 "(" => {
                     token = Some(Tok::LPar);
                     break;
@@ -244,6 +269,10 @@ where
                     token = Some(Tok::Eq);
                     break;
                 }
+                "=>" => {
+                    token = Some(Tok::Arrow);
+                    break;
+                }
                 "!=" => {
                     token = Some(Tok::NotEq);
                     break;
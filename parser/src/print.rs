@@ -57,16 +57,18 @@ pub fn stmt_to_str(node: &ast::StatementType) -> PrintAST {
         ast::StatementType::FunctionStatement {
             function_name: id,
             parameters: params,
+            specifiers,
             statement: stmt,
             returns: ret,
         } => {
-            let name = name_from_identifier(&id).unwrap();
+            let name = name_from_identifier(id).unwrap();
             let repr = String::from("[ Function Statement: ")
                 .add(name.as_str())
                 .add(" ] ");
             let parameters = expr_to_str(&params.node);
-            let statement = stmt_to_str(&stmt.node);
-            let mut children = vec![parameters, statement];
+            let mut children = vec![parameters];
+            children.extend(specifiers.iter().map(function_specifier_to_str));
+            children.push(stmt_to_str(&stmt.node));
             if let Some(returns) = ret {
                 let returns = expr_to_str(&returns.node);
                 children.push(returns);
@@ -84,11 +86,30 @@ pub fn stmt_to_str(node: &ast::StatementType) -> PrintAST {
             ast.add_children_margin();
             ast
         }
+        ast::StatementType::ConstructorStatement {
+            parameters: params,
+            statement: stmt,
+        } => {
+            let repr = String::from("[ Constructor Statement ] ");
+            let children = vec![expr_to_str(&params.node), stmt_to_str(&stmt.node)];
+            let children_size = children.iter().fold(0, |v, child| v + child.size);
+            let size = usize::max(repr.len(), children_size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children,
+            };
+            ast.add_children_margin();
+            ast
+        }
         ast::StatementType::ContractStatement {
             contract_name: name,
             members: stmts,
         } => {
-            let name = name_from_identifier(&name).unwrap();
+            let name = name_from_identifier(name).unwrap();
             let repr = String::from("[ Contract Statement: ")
                 .add(name.as_str())
                 .add(" ] ");
@@ -107,6 +128,31 @@ pub fn stmt_to_str(node: &ast::StatementType) -> PrintAST {
             ast.add_children_margin();
             ast
         }
+        ast::StatementType::StructStatement {
+            struct_name: name,
+            fields,
+        } => {
+            let name = name_from_identifier(name).unwrap();
+            let repr = String::from("[ Struct Statement: ")
+                .add(name.as_str())
+                .add(" ] ");
+            let children = fields
+                .iter()
+                .map(|field| stmt_to_str(&field.node))
+                .collect::<Vec<_>>();
+            let children_size = children.iter().fold(0, |v, child| v + child.size);
+            let size = usize::max(repr.len(), children_size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children,
+            };
+            ast.add_children_margin();
+            ast
+        }
         ast::StatementType::InitializerStatement {
             variable_type: var_type,
             data_location: loc,
@@ -115,7 +161,7 @@ pub fn stmt_to_str(node: &ast::StatementType) -> PrintAST {
             ..
         } => {
             let repr = String::from("[ Initializer Statement ] ");
-            let variable_type = type_to_str(&var_type);
+            let variable_type = type_to_str(var_type);
             let mut children = vec![variable_type];
             if let Some(location) = loc {
                 let data_location = specifier_to_str(location);
@@ -196,6 +242,70 @@ pub fn stmt_to_str(node: &ast::StatementType) -> PrintAST {
             let children_size = children.iter().fold(0, |v, child| v + child.size);
             let size = usize::max(repr.len(), children_size);
 
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children,
+            };
+            ast.add_children_margin();
+            ast
+        }
+        ast::StatementType::EventStatement {
+            event_name: name,
+            parameters: params,
+        } => {
+            let name = name_from_identifier(name).unwrap();
+            let repr = String::from("[ Event Statement: ")
+                .add(name.as_str())
+                .add(" ] ");
+            let children = vec![expr_to_str(&params.node)];
+            let size = usize::max(repr.len(), children[0].size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children,
+            };
+            ast.add_children_margin();
+            ast
+        }
+        StatementType::EmitStatement {
+            event_name: name,
+            arguments: args,
+        } => {
+            let name = name_from_identifier(name).unwrap();
+            let repr = String::from("[ EmitStatement: ")
+                .add(name.as_str())
+                .add(" ] ");
+            let children = vec![expr_to_str(&args.node)];
+            let size = usize::max(repr.len(), children[0].size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children,
+            };
+            ast.add_children_margin();
+            ast
+        }
+        StatementType::RequireStatement {
+            condition,
+            is_assert,
+        } => {
+            let repr = if *is_assert {
+                String::from("[ AssertStatement ] ")
+            } else {
+                String::from("[ RequireStatement ] ")
+            };
+            let children = vec![expr_to_str(&condition.node)];
+            let size = usize::max(repr.len(), children[0].size);
+
             let mut ast = PrintAST {
                 repr,
                 size,
@@ -297,6 +407,40 @@ pub fn expr_to_str(node: &ast::ExpressionType) -> PrintAST {
             ast.add_children_margin();
             ast
         }
+        ast::ExpressionType::IndexExpression { array, index } => {
+            let array = expr_to_str(&array.node);
+            let index = expr_to_str(&index.node);
+            let repr = String::from("[ Index Expression ] ");
+            let children_size = array.size + index.size;
+            let size = usize::max(repr.len(), children_size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![array, index],
+            };
+            ast.add_children_margin();
+            ast
+        }
+        ast::ExpressionType::MemberExpression { expression, member } => {
+            let expression = expr_to_str(&expression.node);
+            let member = expr_to_str(&member.node);
+            let repr = String::from("[ Member Expression ] ");
+            let children_size = expression.size + member.size;
+            let size = usize::max(repr.len(), children_size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![expression, member],
+            };
+            ast.add_children_margin();
+            ast
+        }
         ast::ExpressionType::ForEachExpression {
             iterator: iter,
             vector: vec,
@@ -370,7 +514,7 @@ pub fn expr_to_str(node: &ast::ExpressionType) -> PrintAST {
             operator: op,
             expression: expr,
         } => {
-            let operator = operator_to_str(&op);
+            let operator = operator_to_str(op);
             let expression = expr_to_str(&expr.node);
             let repr = String::from("[ UnaryExpression ] ");
             let children_size = operator.size + expression.size;
@@ -768,6 +912,25 @@ fn specifier_to_str(node: &ast::Specifier) -> PrintAST {
     }
 }
 
+fn function_specifier_to_str(node: &ast::FunctionSpecifier) -> PrintAST {
+    let name = match node {
+        ast::FunctionSpecifier::Public => "public",
+        ast::FunctionSpecifier::External => "external",
+        ast::FunctionSpecifier::Internal => "internal",
+        ast::FunctionSpecifier::View => "view",
+        ast::FunctionSpecifier::Pure => "pure",
+        ast::FunctionSpecifier::Proven => "proven",
+    };
+    let repr = format!("[ specifier : {} ] ", name);
+    PrintAST {
+        size: repr.len(),
+        repr,
+        left_margin: 0,
+        right_margin: 0,
+        children: vec![],
+    }
+}
+
 pub fn type_to_str(node: &ast::Type) -> PrintAST {
     match node {
         ast::Type::Uint256 => PrintAST {
@@ -819,6 +982,62 @@ pub fn type_to_str(node: &ast::Type) -> PrintAST {
             right_margin: 0,
             children: vec![],
         },
+        ast::Type::BoundedBytes { max_len } | ast::Type::BoundedString { max_len } => {
+            let name = match node {
+                ast::Type::BoundedBytes { .. } => "bytes",
+                _ => "string",
+            };
+            let repr = format!("[ type : {}<{}> ] ", name, max_len);
+            PrintAST {
+                size: repr.len(),
+                repr,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![],
+            }
+        }
+        ast::Type::Array { element, size } => {
+            let element = type_to_str(element);
+            let repr = format!("[ type : array[{}] ] ", size);
+            let size = usize::max(repr.len(), element.size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![element],
+            };
+            ast.add_children_margin();
+            ast
+        }
+        ast::Type::Struct { name } => {
+            let repr = format!("[ type : struct {} ] ", name);
+            PrintAST {
+                size: repr.len(),
+                repr,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![],
+            }
+        }
+        ast::Type::Mapping { key, value } => {
+            let key = type_to_str(key);
+            let value = type_to_str(value);
+            let repr = String::from("[ type : mapping ] ");
+            let children_size = key.size + value.size;
+            let size = usize::max(repr.len(), children_size);
+
+            let mut ast = PrintAST {
+                repr,
+                size,
+                left_margin: 0,
+                right_margin: 0,
+                children: vec![key, value],
+            };
+            ast.add_children_margin();
+            ast
+        }
     }
 }
 
@@ -58,9 +58,12 @@ pub enum Tok {
     Bytes,
     String,
     Address,
+    Mapping,
+    Struct,
 
     // Keyword
     Function,
+    Constructor,
     Contract,
     Memory,
     Storage,
@@ -70,16 +73,30 @@ pub enum Tok {
     In,
     Returns,
     Return,
+    Require,
+    Assert,
+    Event,
+    Emit,
     Private,
+    Public,
+    External,
+    Internal,
+    View,
+    Pure,
+    Proven,
     // Mark
     LPar,
     RPar,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
     Semi,
     Comma,
     Question,
     Colon,
+    Arrow,
+    Dot,
     // variable
     Num { number: BigUint },
     Identifier { name: String },
@@ -216,3 +216,51 @@ fn test_print_program_ast1() {
         "                                       [ Program ]                                         \n                               [ Contract Statement: Test ]                                \n                                   [ Member Statement ]                                    \n      [ Initializer Statement ]                    [ Initializer Statement ]               \n[ type : uint256 ] [ Identifier : i ] [ type : uint256 ] [ Identifier : a ] [ Number : 3 ] \n"
     );
 }
+
+#[test]
+fn test_print_shift_in_comparison() {
+    let num = parser::parse_expression("a < b << 2").unwrap();
+
+    let ast = print::expr_to_str(&num.node);
+    assert_eq!(ast.str(), "[ BinaryExpression ] ");
+    assert_eq!(ast.print_ast(), "                                  [ BinaryExpression ]                                    \n[ Identifier : a ] [ compare-op : < ]                [ BinaryExpression ]                 \n                                      [ Identifier : b ] [ shift-op : << ] [ Number : 2 ] \n");
+}
+
+#[test]
+fn test_print_array_ast() {
+    let stmt = parser::parse_statement("uint[4] xs").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(ast.print_ast(), "       [ Initializer Statement ]        \n[ type : array[4] ] [ Identifier : xs ] \n [ type : uint256 ]                     \n");
+
+    let stmt = parser::parse_statement("xs[i] = 1").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(ast.print_ast(), "                         [ AssignExpression ]                           \n         [ Index Expression ]          [ assign-op : = ] [ Number : 1 ] \n[ Identifier : xs ] [ Identifier : i ]                                  \n");
+}
+
+#[test]
+fn test_print_mapping_ast() {
+    let stmt = parser::parse_statement("mapping(address => uint) m").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(ast.print_ast(), "               [ Initializer Statement ]                 \n         [ type : mapping ]           [ Identifier : m ] \n[ type : address ] [ type : uint256 ]                    \n");
+}
+
+#[test]
+fn test_print_member_ast() {
+    let stmt = parser::parse_statement("a.owner = 1").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(ast.print_ast(), "                           [ AssignExpression ]                            \n          [ Member Expression ]           [ assign-op : = ] [ Number : 1 ] \n[ Identifier : a ] [ Identifier : owner ]                                  \n");
+}
+
+#[test]
+fn test_print_require_ast() {
+    let stmt = parser::parse_statement("require(a)").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(
+        ast.print_ast(),
+        "[ RequireStatement ] \n [ Identifier : a ]  \n"
+    );
+
+    let stmt = parser::parse_statement("assert(a)").unwrap();
+    let ast = print::stmt_to_str(&stmt.node);
+    assert_eq!(ast.str(), "[ AssertStatement ] ");
+}
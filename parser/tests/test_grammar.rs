@@ -1,4 +1,4 @@
-use zoker_parser::parser;
+use zoker_parser::{ast, parser};
 
 #[test]
 fn test_if_statement_parser() {
@@ -84,3 +84,166 @@ fn test_bigint_parser() {
     let bigint = parser::parse_expression("1_021_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000");
     assert!(bigint.is_ok());
 }
+
+#[test]
+fn test_shift_expression_parser() {
+    assert!(parser::parse_expression("a << 2").is_ok());
+    assert!(parser::parse_expression("a >> 2 >> 3").is_ok());
+    assert!(parser::parse_expression("a << 2 < b").is_ok());
+    assert!(parser::parse_expression("a < b << 2").is_ok());
+    assert!(parser::parse_expression("a + 1 >> b - 1 == c").is_ok());
+    assert!(parser::parse_expression("a << << 2").is_err());
+}
+
+#[test]
+fn test_array_parser() {
+    assert!(parser::parse_statement("private uint[4] xs").is_ok());
+    assert!(parser::parse_statement("bool[2][3] flags").is_ok());
+    assert!(parser::parse_expression("xs[i + 1]").is_ok());
+    assert!(parser::parse_expression("xs[0][1] + f(a)[2]").is_ok());
+    assert!(parser::parse_expression("xs[i] = xs[j] = 3").is_ok());
+    assert!(parser::parse_expression("for x in xs { x }").is_ok());
+    assert!(parser::parse_expression("xs[]").is_err());
+    assert!(parser::parse_statement("uint[a] xs").is_err());
+    assert!(parser::parse_statement("uint[4294967296] xs").is_err());
+}
+
+#[test]
+fn test_mapping_parser() {
+    assert!(parser::parse_program(
+        "contract Token {\
+           mapping(address => uint) balances;\
+           function balance(address a) returns (uint) {\
+             return balances[a];\
+           }\
+        }"
+    )
+    .is_ok());
+    assert!(parser::parse_statement("mapping(bytes32 => bool) spent").is_ok());
+    assert!(parser::parse_statement("mapping(uint => mapping(uint => uint)) m").is_ok());
+    assert!(parser::parse_statement("mapping(uint) m").is_err());
+    assert!(parser::parse_statement("mapping(uint => ) m").is_err());
+}
+
+#[test]
+fn test_struct_parser() {
+    assert!(parser::parse_program(
+        "contract Bank {\
+           struct Account {\
+             private uint balance;\
+             address owner;\
+           }\
+           function deposit(struct Account a, uint amount) returns (struct Account) {\
+             a.balance += amount;\
+             return a;\
+           }\
+        }"
+    )
+    .is_ok());
+    assert!(parser::parse_statement("struct Account[2] accounts").is_ok());
+    assert!(parser::parse_statement("a.owner == accounts[1].owner").is_ok());
+    assert!(parser::parse_statement("a.b.c = 1").is_ok());
+    assert!(parser::parse_statement("a.").is_err());
+    assert!(parser::parse_statement("a.1").is_err());
+}
+
+#[test]
+fn test_data_location_parser() {
+    let statement = parser::parse_statement("uint storage total = 1").unwrap();
+    match statement.node {
+        ast::StatementType::InitializerStatement { data_location, .. } => {
+            assert_eq!(data_location, Some(ast::Specifier::Storage))
+        }
+        _ => panic!("not an initializer"),
+    }
+    let statement = parser::parse_statement("uint[2] memory xs").unwrap();
+    match statement.node {
+        ast::StatementType::InitializerStatement { data_location, .. } => {
+            assert_eq!(data_location, Some(ast::Specifier::Memory))
+        }
+        _ => panic!("not an initializer"),
+    }
+    assert!(parser::parse_statement("storage x").is_err());
+}
+
+#[test]
+fn test_event_parser() {
+    assert!(parser::parse_program(
+        "contract Token {\
+           event Transfer(address to, uint amount);\
+           function transfer(address to, uint amount) {\
+             emit Transfer(to, amount);\
+           }\
+        }"
+    )
+    .is_ok());
+    let statement = parser::parse_statement("emit Transfer(to, 1)").unwrap();
+    assert!(matches!(
+        statement.node,
+        ast::StatementType::EmitStatement { .. }
+    ));
+    assert!(parser::parse_statement("emit Transfer").is_err());
+    assert!(parser::parse_program("contract Token { event Transfer; }").is_err());
+}
+
+#[test]
+fn test_function_specifier_parser() {
+    let program = parser::parse_program(
+        "contract Bank {\
+           function fee(uint a) internal pure returns (uint) {\
+             return a;\
+           }\
+           function pay(uint a) external view proven {\
+           }\
+        }",
+    )
+    .unwrap();
+    let ast::Program::GlobalStatements(statements) = program;
+    let members = match &statements[0].node {
+        ast::StatementType::ContractStatement { members, .. } => &members.node,
+        _ => panic!("not a contract"),
+    };
+    let specifiers = match members {
+        ast::StatementType::MemberStatement { statements } => statements
+            .iter()
+            .map(|statement| match &statement.node {
+                ast::StatementType::FunctionStatement { specifiers, .. } => specifiers,
+                _ => panic!("not a function"),
+            })
+            .collect::<Vec<_>>(),
+        _ => panic!("not members"),
+    };
+    assert_eq!(
+        specifiers[0],
+        &vec![
+            ast::FunctionSpecifier::Internal,
+            ast::FunctionSpecifier::Pure
+        ]
+    );
+    assert_eq!(
+        specifiers[1],
+        &vec![
+            ast::FunctionSpecifier::External,
+            ast::FunctionSpecifier::View,
+            ast::FunctionSpecifier::Proven
+        ]
+    );
+    assert!(
+        parser::parse_program("contract Bank { function f() returns (uint) view { } }").is_err()
+    );
+}
+
+#[test]
+fn test_constructor_parser() {
+    assert!(parser::parse_program(
+        "contract Token {\
+           uint total = 1;\
+           constructor(uint supply) {\
+             total = supply;\
+           }\
+        }"
+    )
+    .is_ok());
+    assert!(parser::parse_program("contract Token { constructor { } }").is_err());
+    assert!(parser::parse_program("contract Token { constructor() returns (uint) { } }").is_err());
+}
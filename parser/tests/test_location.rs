@@ -48,7 +48,7 @@ fn test_ast_location1() {
         assert_eq!(left.location, Location::new(0, 3));
         assert_eq!(right.location, Location::new(0, 9));
     } else {
-        assert!(false);
+        unreachable!();
     }
 }
 
@@ -68,6 +68,6 @@ fn test_ast_location2() {
         assert_eq!(if_statement.location, Location::new(0, 10));
         assert!(else_statement.is_none())
     } else {
-        assert!(false);
+        unreachable!();
     }
 }
@@ -1,4 +1,4 @@
-use crate::utils::convert_u32_to_u8;
+use crate::utils::{byte_of, convert_u32_to_u8, slice_bits};
 use crypto::digest::Digest;
 use crypto::sha2::Sha256;
 use hex::FromHex;
@@ -80,12 +80,33 @@ fn generate_randomness(key: &[u8]) -> Vec<u32> {
     randoms
 }
 
+/// Gates over a shared 32-bit word.
+///
+/// Implemented by both `IKosVariable4P` and `IKosVariable4V`, so gadgets
+/// built from these gates run unchanged in the prover and the verifier.
+pub trait IKosVariable: Clone {
+    fn new_value(value: u32) -> Self;
+    fn negate(self) -> Self;
+    fn xor(self, rhs: &Self) -> Self;
+    fn rshift(self, n: u32) -> Self;
+    fn lshift(self, n: u32) -> Self;
+    fn rotr(self, n: u32) -> Self;
+    fn rotl(self, n: u32) -> Self;
+    fn byte(self, n: u32) -> Self;
+    fn swap_bytes(self) -> Self;
+    fn bit_slice(self, lo: u32, len: u32) -> Self;
+    fn mask(self) -> Self;
+    fn bit_and(self, rhs: &Self) -> IKosResult<Self>;
+    fn add_op(self, rhs: &Self) -> IKosResult<Self>;
+    fn mux(self, a: &Self, b: &Self) -> IKosResult<Self>;
+}
+
 pub fn get_next_random_from_context(ctx: &mut IKosContext) -> IKosResult<u32> {
     if ctx.randomness.len() <= ctx.used_rand_ctr {
         ctx.randomness = generate_randomness(convert_u32_to_u8(ctx.randomness.as_ref()).as_ref());
         ctx.used_rand_ctr = 0;
     }
-    let rand = ctx.randomness[ctx.used_rand_ctr as usize];
+    let rand = ctx.randomness[ctx.used_rand_ctr];
     ctx.used_rand_ctr += 1;
     Ok(rand)
 }
@@ -107,6 +128,12 @@ impl IKosView {
     }
 }
 
+impl Default for IKosContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IKosContext {
     pub fn new() -> Self {
         let ikos_view = IKosView::new();
@@ -132,10 +159,9 @@ impl IKosContext {
     pub fn commit_ikos_context(&mut self) -> [u8; 32] {
         let mut sha = Sha256::new();
         let mut vec = self.ikos_view.rand_tape_seed.clone();
-        vec.extend(convert_u32_to_u8(&*self.ikos_view.out_data.clone()));
+        vec.extend(convert_u32_to_u8(&self.ikos_view.out_data));
         sha.input(&vec);
-        let res = <[u8; 32]>::from_hex(sha.result_str()).unwrap();
-        res
+        <[u8; 32]>::from_hex(sha.result_str()).unwrap()
     }
 }
 
@@ -163,6 +189,9 @@ impl IKosVariable4P {
     }
 
     pub fn xor(mut self, rhs: &IKosVariable4P) -> Self {
+        if self.is_empty_context() {
+            self.ctx = Rc::clone(&rhs.ctx);
+        }
         for i in 0..3 {
             self.value[i] ^= rhs.value[i];
         }
@@ -183,9 +212,46 @@ impl IKosVariable4P {
         self
     }
 
+    pub fn rotr(mut self, n: u32) -> Self {
+        for i in 0..3 {
+            self.value[i] = self.value[i].rotate_right(n);
+        }
+        self
+    }
+
+    pub fn rotl(mut self, n: u32) -> Self {
+        for i in 0..3 {
+            self.value[i] = self.value[i].rotate_left(n);
+        }
+        self
+    }
+
+    /// Byte `n` of the word, counted from the most significant byte.
+    pub fn byte(mut self, n: u32) -> Self {
+        for i in 0..3 {
+            self.value[i] = byte_of(self.value[i], n);
+        }
+        self
+    }
+
+    pub fn swap_bytes(mut self) -> Self {
+        for i in 0..3 {
+            self.value[i] = self.value[i].swap_bytes();
+        }
+        self
+    }
+
+    /// `len` bits starting at bit `lo`, moved down to bit 0.
+    pub fn bit_slice(mut self, lo: u32, len: u32) -> Self {
+        for i in 0..3 {
+            self.value[i] = slice_bits(self.value[i], lo, len);
+        }
+        self
+    }
+
     pub fn bit_and(mut self, rhs: &IKosVariable4P) -> Self {
-        let mut rand = vec![0; 3];
-        let mut out = vec![0; 3];
+        let mut rand = [0; 3];
+        let mut out = [0; 3];
 
         if self.is_empty_context() && rhs.is_empty_context() {
             for i in 0..3 {
@@ -226,16 +292,32 @@ impl IKosVariable4P {
         self
     }
 
+    /// Spreads the lowest bit of every share over the whole word,
+    /// so a boolean condition becomes an all-ones or all-zeros mask.
+    pub fn mask(mut self) -> Self {
+        for i in 0..3 {
+            self.value[i] = 0u32.wrapping_sub(self.value[i] & 1);
+        }
+        self
+    }
+
+    /// `self` is the condition: returns `a` if it is set and `b` otherwise.
+    /// Costs a single `bit_and`: `b ^ ((a ^ b) & mask)`.
+    pub fn mux(self, a: &IKosVariable4P, b: &IKosVariable4P) -> Self {
+        let mask = self.mask();
+        a.clone().xor(b).bit_and(&mask).xor(b)
+    }
+
     pub fn add_op(mut self, rhs: &IKosVariable4P) -> Self {
-        let mut a = vec![0; 3];
-        let mut b = vec![0; 3];
-        let mut rand = vec![0; 3];
-        let mut out = vec![0; 3];
+        let mut a = [0; 3];
+        let mut b = [0; 3];
+        let mut rand = [0; 3];
+        let mut out = [0; 3];
 
         if self.is_empty_context() && rhs.is_empty_context() {
             // constant calculation
             for i in 0..3 {
-                self.value[i] += rhs.value[i];
+                self.value[i] = self.value[i].wrapping_add(rhs.value[i]);
             }
             return self;
         }
@@ -297,6 +379,9 @@ impl IKosVariable4V {
     }
 
     pub fn xor(mut self, rhs: &IKosVariable4V) -> Self {
+        if self.is_empty_context() {
+            self.ctx = Rc::clone(&rhs.ctx);
+        }
         for i in 0..2 {
             self.value[i] ^= rhs.value[i];
         }
@@ -317,12 +402,47 @@ impl IKosVariable4V {
         self
     }
 
+    pub fn rotr(mut self, n: u32) -> Self {
+        for i in 0..2 {
+            self.value[i] = self.value[i].rotate_right(n);
+        }
+        self
+    }
+
+    pub fn rotl(mut self, n: u32) -> Self {
+        for i in 0..2 {
+            self.value[i] = self.value[i].rotate_left(n);
+        }
+        self
+    }
+
+    pub fn byte(mut self, n: u32) -> Self {
+        for i in 0..2 {
+            self.value[i] = byte_of(self.value[i], n);
+        }
+        self
+    }
+
+    pub fn swap_bytes(mut self) -> Self {
+        for i in 0..2 {
+            self.value[i] = self.value[i].swap_bytes();
+        }
+        self
+    }
+
+    pub fn bit_slice(mut self, lo: u32, len: u32) -> Self {
+        for i in 0..2 {
+            self.value[i] = slice_bits(self.value[i], lo, len);
+        }
+        self
+    }
+
     pub fn require_reconstruct(ctx: &[IKosContext]) -> bool {
         ctx[0].ikos_view.out_data.len() != ctx[1].ikos_view.out_data.len()
     }
 
     pub fn bit_and(mut self, rhs: &IKosVariable4V) -> IKosResult<Self> {
-        let mut rand = vec![0; 2];
+        let mut rand = [0; 2];
         if self.is_empty_context() && rhs.is_empty_context() {
             for i in 0..2 {
                 self.value[i] &= rhs.value[i];
@@ -369,14 +489,26 @@ impl IKosVariable4V {
         self
     }
 
+    pub fn mask(mut self) -> Self {
+        for i in 0..2 {
+            self.value[i] = 0u32.wrapping_sub(self.value[i] & 1);
+        }
+        self
+    }
+
+    pub fn mux(self, a: &IKosVariable4V, b: &IKosVariable4V) -> IKosResult<Self> {
+        let mask = self.mask();
+        Ok(a.clone().xor(b).bit_and(&mask)?.xor(b))
+    }
+
     pub fn add_op(mut self, rhs: &IKosVariable4V) -> IKosResult<Self> {
-        let mut a = vec![0; 2];
-        let mut b = vec![0; 2];
-        let mut rand = vec![0; 2];
-        let mut out = vec![0; 2];
+        let mut a = [0; 2];
+        let mut b = [0; 2];
+        let mut rand = [0; 2];
+        let mut out = [0; 2];
         if self.is_empty_context() && rhs.is_empty_context() {
             for i in 0..2 {
-                self.value[i] += rhs.value[i];
+                self.value[i] = self.value[i].wrapping_add(rhs.value[i]);
             }
             return Ok(self);
         }
@@ -426,3 +558,119 @@ impl IKosVariable4V {
         Ok(self)
     }
 }
+
+impl IKosVariable for IKosVariable4P {
+    fn new_value(value: u32) -> Self {
+        IKosVariable4P::new_value(value)
+    }
+
+    fn negate(self) -> Self {
+        IKosVariable4P::negate(self)
+    }
+
+    fn xor(self, rhs: &Self) -> Self {
+        IKosVariable4P::xor(self, rhs)
+    }
+
+    fn rshift(self, n: u32) -> Self {
+        IKosVariable4P::rshift(self, n)
+    }
+
+    fn lshift(self, n: u32) -> Self {
+        IKosVariable4P::lshift(self, n)
+    }
+
+    fn rotr(self, n: u32) -> Self {
+        IKosVariable4P::rotr(self, n)
+    }
+
+    fn rotl(self, n: u32) -> Self {
+        IKosVariable4P::rotl(self, n)
+    }
+
+    fn byte(self, n: u32) -> Self {
+        IKosVariable4P::byte(self, n)
+    }
+
+    fn swap_bytes(self) -> Self {
+        IKosVariable4P::swap_bytes(self)
+    }
+
+    fn bit_slice(self, lo: u32, len: u32) -> Self {
+        IKosVariable4P::bit_slice(self, lo, len)
+    }
+
+    fn mask(self) -> Self {
+        IKosVariable4P::mask(self)
+    }
+
+    fn bit_and(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(IKosVariable4P::bit_and(self, rhs))
+    }
+
+    fn add_op(self, rhs: &Self) -> IKosResult<Self> {
+        Ok(IKosVariable4P::add_op(self, rhs))
+    }
+
+    fn mux(self, a: &Self, b: &Self) -> IKosResult<Self> {
+        Ok(IKosVariable4P::mux(self, a, b))
+    }
+}
+
+impl IKosVariable for IKosVariable4V {
+    fn new_value(value: u32) -> Self {
+        IKosVariable4V::new_value(value)
+    }
+
+    fn negate(self) -> Self {
+        IKosVariable4V::negate(self)
+    }
+
+    fn xor(self, rhs: &Self) -> Self {
+        IKosVariable4V::xor(self, rhs)
+    }
+
+    fn rshift(self, n: u32) -> Self {
+        IKosVariable4V::rshift(self, n)
+    }
+
+    fn lshift(self, n: u32) -> Self {
+        IKosVariable4V::lshift(self, n)
+    }
+
+    fn rotr(self, n: u32) -> Self {
+        IKosVariable4V::rotr(self, n)
+    }
+
+    fn rotl(self, n: u32) -> Self {
+        IKosVariable4V::rotl(self, n)
+    }
+
+    fn byte(self, n: u32) -> Self {
+        IKosVariable4V::byte(self, n)
+    }
+
+    fn swap_bytes(self) -> Self {
+        IKosVariable4V::swap_bytes(self)
+    }
+
+    fn bit_slice(self, lo: u32, len: u32) -> Self {
+        IKosVariable4V::bit_slice(self, lo, len)
+    }
+
+    fn mask(self) -> Self {
+        IKosVariable4V::mask(self)
+    }
+
+    fn bit_and(self, rhs: &Self) -> IKosResult<Self> {
+        IKosVariable4V::bit_and(self, rhs)
+    }
+
+    fn add_op(self, rhs: &Self) -> IKosResult<Self> {
+        IKosVariable4V::add_op(self, rhs)
+    }
+
+    fn mux(self, a: &Self, b: &Self) -> IKosResult<Self> {
+        IKosVariable4V::mux(self, a, b)
+    }
+}
@@ -1,4 +1,6 @@
 pub mod ikos;
+pub mod sha256;
+pub mod uint256;
 pub mod utils;
 pub mod vector;
 pub mod zkboo;
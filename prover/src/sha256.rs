@@ -0,0 +1,179 @@
+use crate::ikos::{IKosResult, IKosVariable};
+
+const SHA256_H: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const SHA256_K: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+fn big_sigma0<T: IKosVariable>(x: &T) -> T {
+    x.clone()
+        .rotr(2)
+        .xor(&x.clone().rotr(13))
+        .xor(&x.clone().rotr(22))
+}
+
+fn big_sigma1<T: IKosVariable>(x: &T) -> T {
+    x.clone()
+        .rotr(6)
+        .xor(&x.clone().rotr(11))
+        .xor(&x.clone().rotr(25))
+}
+
+fn small_sigma0<T: IKosVariable>(x: &T) -> T {
+    x.clone()
+        .rotr(7)
+        .xor(&x.clone().rotr(18))
+        .xor(&x.clone().rshift(3))
+}
+
+fn small_sigma1<T: IKosVariable>(x: &T) -> T {
+    x.clone()
+        .rotr(17)
+        .xor(&x.clone().rotr(19))
+        .xor(&x.clone().rshift(10))
+}
+
+// (e & f) ^ (!e & g) with a single `bit_and`.
+fn choose<T: IKosVariable>(e: &T, f: &T, g: &T) -> IKosResult<T> {
+    Ok(f.clone().xor(g).bit_and(e)?.xor(g))
+}
+
+// (a & b) ^ (a & c) ^ (b & c) with a single `bit_and`.
+fn majority<T: IKosVariable>(a: &T, b: &T, c: &T) -> IKosResult<T> {
+    Ok(a.clone().xor(b).bit_and(&a.clone().xor(c))?.xor(a))
+}
+
+/// Initial hash value `H(0)` as constant words.
+pub fn sha256_initial_state<T: IKosVariable>() -> Vec<T> {
+    SHA256_H.iter().map(|&h| T::new_value(h)).collect()
+}
+
+/// SHA-256 compression function over 8 state words and a 16 word block.
+pub fn sha256_compress<T: IKosVariable>(state: &[T], block: &[T]) -> IKosResult<Vec<T>> {
+    let mut w = block.to_vec();
+    for i in 16..64 {
+        let word = small_sigma1(&w[i - 2])
+            .add_op(&w[i - 7])?
+            .add_op(&small_sigma0(&w[i - 15]))?
+            .add_op(&w[i - 16])?;
+        w.push(word);
+    }
+
+    let mut v = state.to_vec();
+    for (i, word) in w.iter().enumerate() {
+        let t1 = v[7]
+            .clone()
+            .add_op(&big_sigma1(&v[4]))?
+            .add_op(&choose(&v[4], &v[5], &v[6])?)?
+            .add_op(&T::new_value(SHA256_K[i]))?
+            .add_op(word)?;
+        let t2 = big_sigma0(&v[0]).add_op(&majority(&v[0], &v[1], &v[2])?)?;
+        v[7] = v[6].clone();
+        v[6] = v[5].clone();
+        v[5] = v[4].clone();
+        v[4] = v[3].clone().add_op(&t1)?;
+        v[3] = v[2].clone();
+        v[2] = v[1].clone();
+        v[1] = v[0].clone();
+        v[0] = t1.add_op(&t2)?;
+    }
+
+    let mut res = vec![];
+    for (h, x) in state.iter().zip(v.iter()) {
+        res.push(h.clone().add_op(x)?);
+    }
+    Ok(res)
+}
+
+/// SHA-256 digest of a message given as big-endian 32-bit words.
+///
+/// The message length is public, so padding only adds constant words.
+pub fn sha256<T: IKosVariable>(message: &[T]) -> IKosResult<Vec<T>> {
+    let mut words = message.to_vec();
+    words.push(T::new_value(0x8000_0000));
+    while words.len() % 16 != 14 {
+        words.push(T::new_value(0));
+    }
+    let bit_len = message.len() as u64 * 32;
+    words.push(T::new_value((bit_len >> 32) as u32));
+    words.push(T::new_value(bit_len as u32));
+
+    let mut state = sha256_initial_state();
+    for block in words.chunks(16) {
+        state = sha256_compress(&state, block)?;
+    }
+    Ok(state)
+}
@@ -0,0 +1,332 @@
+use crate::ikos::{IKosResult, IKosVariable};
+
+/// Number of 32-bit limbs of a 256-bit integer.
+///
+/// Limbs are kept little-endian: `limbs[0]` holds the least significant word.
+pub const LIMBS: usize = 8;
+
+/// Bits of a 256-bit integer.
+pub const BITS: usize = LIMBS * 32;
+
+pub fn constant<T: IKosVariable>(limbs: &[u32]) -> Vec<T> {
+    limbs.iter().map(|&limb| T::new_value(limb)).collect()
+}
+
+pub fn zero<T: IKosVariable>() -> Vec<T> {
+    constant(&[0; LIMBS])
+}
+
+// Carry out of bit 31 of `a + b`, given their 32-bit sum.
+fn carry_out<T: IKosVariable>(a: &T, b: &T, sum: &T) -> IKosResult<T> {
+    let carry_in = a.clone().xor(b).xor(sum);
+    let carry = a
+        .clone()
+        .xor(&carry_in)
+        .bit_and(&b.clone().xor(&carry_in))?
+        .xor(&carry_in);
+    Ok(carry.rshift(31))
+}
+
+/// `a + b (+ carry)` on a single limb, returning the sum and the carry
+/// into the next limb. Unlike `add_op`, the carry out of bit 31 is kept.
+pub fn add_limb<T: IKosVariable>(a: &T, b: &T, carry: Option<&T>) -> IKosResult<(T, T)> {
+    let partial = a.clone().add_op(b)?;
+    let first = carry_out(a, b, &partial)?;
+    match carry {
+        Some(carry) => {
+            let sum = partial.clone().add_op(carry)?;
+            let second = carry_out(&partial, carry, &sum)?;
+            // Both carries can never be set at once.
+            Ok((sum, first.xor(&second)))
+        }
+        None => Ok((partial, first)),
+    }
+}
+
+fn add_from<T: IKosVariable>(
+    a: &[T],
+    b: &[T],
+    start: usize,
+    carry: Option<T>,
+) -> IKosResult<(Vec<T>, T)> {
+    let mut res = a[..start].to_vec();
+    let mut carry = carry;
+    for i in start..a.len() {
+        let (sum, next) = add_limb(&a[i], &b[i], carry.as_ref())?;
+        res.push(sum);
+        carry = Some(next);
+    }
+    Ok((res, carry.unwrap_or_else(|| T::new_value(0))))
+}
+
+/// `a + b` modulo 2^256 and the carry out of the top limb.
+pub fn add<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, T)> {
+    add_from(a, b, 0, None)
+}
+
+/// `a - b` modulo 2^256 and the borrow out of the top limb.
+pub fn sub<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, T)> {
+    let not_b = b
+        .iter()
+        .map(|limb| limb.clone().negate())
+        .collect::<Vec<T>>();
+    let (diff, carry) = add_from(a, &not_b, 0, Some(T::new_value(1)))?;
+    Ok((diff, carry.xor(&T::new_value(1))))
+}
+
+/// Two's complement negation.
+pub fn neg<T: IKosVariable>(a: &[T]) -> IKosResult<Vec<T>> {
+    Ok(sub(&zero(), a)?.0)
+}
+
+/// `a * b` modulo 2^256, by shift-and-add over the bits of `b`.
+pub fn mul<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<Vec<T>> {
+    let mut acc = zero();
+    for j in 0..BITS {
+        let start = j / 32;
+        let bit = b[start].clone().bit_slice((j % 32) as u32, 1).mask();
+        let mut partial = shl(a, j);
+        for limb in partial.iter_mut().skip(start) {
+            *limb = limb.clone().bit_and(&bit)?;
+        }
+        acc = add_from(&acc, &partial, start, None)?.0;
+    }
+    Ok(acc)
+}
+
+/// Unsigned `a < b`, as a single bit word.
+pub fn lt<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<T> {
+    Ok(sub(a, b)?.1)
+}
+
+fn or<T: IKosVariable>(a: &T, b: &T) -> IKosResult<T> {
+    Ok(a.clone().xor(b).xor(&a.clone().bit_and(b)?))
+}
+
+/// `a == 0`, as a single bit word.
+pub fn is_zero<T: IKosVariable>(a: &[T]) -> IKosResult<T> {
+    let mut acc = a[0].clone();
+    for limb in &a[1..] {
+        acc = or(&acc, limb)?;
+    }
+    for &n in &[16, 8, 4, 2, 1] {
+        acc = or(&acc, &acc.clone().rshift(n))?;
+    }
+    Ok(acc.bit_slice(0, 1).xor(&T::new_value(1)))
+}
+
+/// `a == b`, as a single bit word.
+pub fn eq<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<T> {
+    let diff = a
+        .iter()
+        .zip(b.iter())
+        .map(|(x, y)| x.clone().xor(y))
+        .collect::<Vec<T>>();
+    is_zero(&diff)
+}
+
+/// Logical shift left by a constant number of bits.
+pub fn shl<T: IKosVariable>(a: &[T], n: usize) -> Vec<T> {
+    let (words, bits) = (n / 32, (n % 32) as u32);
+    (0..a.len())
+        .map(|i| {
+            if i < words {
+                return T::new_value(0);
+            }
+            let limb = a[i - words].clone().lshift(bits);
+            if bits == 0 || i == words {
+                limb
+            } else {
+                limb.xor(&a[i - words - 1].clone().rshift(32 - bits))
+            }
+        })
+        .collect()
+}
+
+/// Logical shift right by a constant number of bits.
+pub fn shr<T: IKosVariable>(a: &[T], n: usize) -> Vec<T> {
+    let (words, bits) = (n / 32, (n % 32) as u32);
+    (0..a.len())
+        .map(|i| {
+            if i + words >= a.len() {
+                return T::new_value(0);
+            }
+            let limb = a[i + words].clone().rshift(bits);
+            if bits == 0 || i + words + 1 == a.len() {
+                limb
+            } else {
+                limb.xor(&a[i + words + 1].clone().lshift(32 - bits))
+            }
+        })
+        .collect()
+}
+
+/// Limb-wise `mux`: `a` when the bit `cond` is set, `b` otherwise.
+pub fn mux<T: IKosVariable>(cond: &T, a: &[T], b: &[T]) -> IKosResult<Vec<T>> {
+    let mut res = vec![];
+    for (x, y) in a.iter().zip(b.iter()) {
+        res.push(cond.clone().mux(x, y)?);
+    }
+    Ok(res)
+}
+
+/// Unsigned `a / b` and `a % b`, by restoring division.
+///
+/// Division by zero yields an all-ones quotient and `a` as the remainder;
+/// callers that need Solidity's revert have to check `b` themselves.
+pub fn divrem<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, Vec<T>)> {
+    let mut quotient: Vec<T> = zero();
+    let mut rem: Vec<T> = zero();
+    for j in (0..BITS).rev() {
+        let top = rem[LIMBS - 1].clone().rshift(31);
+        let mut shifted = shl(&rem, 1);
+        shifted[0] = shifted[0]
+            .clone()
+            .xor(&a[j / 32].clone().bit_slice((j % 32) as u32, 1));
+        let (diff, borrow) = sub(&shifted, b)?;
+        // The shifted remainder has 257 bits; a set top bit always fits `b`.
+        let fits = or(&top, &borrow.xor(&T::new_value(1)))?;
+        rem = mux(&fits, &diff, &shifted)?;
+        quotient[j / 32] = quotient[j / 32].clone().xor(&fits.lshift((j % 32) as u32));
+    }
+    Ok((quotient, rem))
+}
+
+/// Sign bit of a two's complement integer, as a single bit word.
+pub fn sign<T: IKosVariable>(a: &[T]) -> T {
+    a[LIMBS - 1].clone().rshift(31)
+}
+
+/// `-a` when the bit `cond` is set, `a` otherwise.
+pub fn negate_if<T: IKosVariable>(a: &[T], cond: &T) -> IKosResult<Vec<T>> {
+    let mask = cond.clone().mask();
+    let flipped = a
+        .iter()
+        .map(|limb| limb.clone().xor(&mask))
+        .collect::<Vec<T>>();
+    Ok(add_from(&flipped, &zero(), 0, Some(cond.clone()))?.0)
+}
+
+/// Signed `a < b`, as a single bit word.
+pub fn slt<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<T> {
+    let flip = |x: &[T]| {
+        let mut x = x.to_vec();
+        x[LIMBS - 1] = x[LIMBS - 1].clone().xor(&T::new_value(0x8000_0000));
+        x
+    };
+    lt(&flip(a), &flip(b))
+}
+
+/// Extends the sign bit of the low `bits` bits over the whole integer.
+pub fn sign_extend<T: IKosVariable>(a: &[T], bits: usize) -> Vec<T> {
+    let (index, pos) = ((bits - 1) / 32, ((bits - 1) % 32) as u32);
+    let mask = a[index].clone().bit_slice(pos, 1).mask();
+    let mut res = a.to_vec();
+    if pos < 31 {
+        res[index] = a[index]
+            .clone()
+            .bit_slice(0, pos + 1)
+            .xor(&mask.clone().lshift(pos + 1));
+    }
+    for limb in res.iter_mut().skip(index + 1) {
+        *limb = mask.clone();
+    }
+    res
+}
+
+/// Arithmetic shift right by a constant number of bits.
+pub fn sar<T: IKosVariable>(a: &[T], n: usize) -> Vec<T> {
+    if n >= BITS {
+        return sign_extend(&shr(a, BITS - 1), 1);
+    }
+    if n == 0 {
+        return a.to_vec();
+    }
+    sign_extend(&shr(a, n), BITS - n)
+}
+
+/// Signed `a / b` and `a % b`, rounding toward zero like Solidity.
+///
+/// The remainder takes the sign of `a`.
+pub fn sdivrem<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, Vec<T>)> {
+    let (sign_a, sign_b) = (sign(a), sign(b));
+    let (quotient, rem) = divrem(&negate_if(a, &sign_a)?, &negate_if(b, &sign_b)?)?;
+    Ok((
+        negate_if(&quotient, &sign_a.clone().xor(&sign_b))?,
+        negate_if(&rem, &sign_a)?,
+    ))
+}
+
+fn or_limbs<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<Vec<T>> {
+    let mut res = vec![];
+    for (x, y) in a.iter().zip(b.iter()) {
+        res.push(or(x, y)?);
+    }
+    Ok(res)
+}
+
+/// `a * b` modulo 2^256 and whether the full product does not fit.
+pub fn mul_overflow<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, T)> {
+    // Bit `k` of `smear` is set when `a` has any bit set at `k` or above.
+    let mut smear = a.to_vec();
+    let mut n = 1;
+    while n < BITS {
+        smear = or_limbs(&smear, &shr(&smear, n))?;
+        n *= 2;
+    }
+    let mut acc = zero();
+    let mut overflow = T::new_value(0);
+    for j in 0..BITS {
+        let start = j / 32;
+        let bit = b[start].clone().bit_slice((j % 32) as u32, 1);
+        let mask = bit.clone().mask();
+        let mut partial = shl(a, j);
+        for limb in partial.iter_mut().skip(start) {
+            *limb = limb.clone().bit_and(&mask)?;
+        }
+        let (sum, carry) = add_from(&acc, &partial, start, None)?;
+        acc = sum;
+        overflow = or(&overflow, &carry)?;
+        if j > 0 {
+            // The top `j` bits of `a` are shifted out of `a << j`.
+            let k = BITS - j;
+            let lost = smear[k / 32].clone().bit_slice((k % 32) as u32, 1);
+            overflow = or(&overflow, &bit.bit_and(&lost)?)?;
+        }
+    }
+    Ok((acc, overflow))
+}
+
+/// Whether the signed sum `a + b` overflowed, given its wrapped `sum`.
+pub fn signed_add_overflow<T: IKosVariable>(a: &[T], b: &[T], sum: &[T]) -> IKosResult<T> {
+    let top = LIMBS - 1;
+    let flag = sum[top]
+        .clone()
+        .xor(&a[top])
+        .bit_and(&sum[top].clone().xor(&b[top]))?;
+    Ok(flag.rshift(31))
+}
+
+/// Whether the signed difference `a - b` overflowed, given its wrapped `diff`.
+pub fn signed_sub_overflow<T: IKosVariable>(a: &[T], b: &[T], diff: &[T]) -> IKosResult<T> {
+    let top = LIMBS - 1;
+    let flag = a[top]
+        .clone()
+        .xor(&b[top])
+        .bit_and(&a[top].clone().xor(&diff[top]))?;
+    Ok(flag.rshift(31))
+}
+
+/// Signed `a * b` modulo 2^256 and whether it overflowed `int256`.
+pub fn signed_mul_overflow<T: IKosVariable>(a: &[T], b: &[T]) -> IKosResult<(Vec<T>, T)> {
+    let (sign_a, sign_b) = (sign(a), sign(b));
+    let (product, overflow) = mul_overflow(&negate_if(a, &sign_a)?, &negate_if(b, &sign_b)?)?;
+    let negative = sign_a.xor(&sign_b);
+    let mut min = [0; LIMBS];
+    min[LIMBS - 1] = 0x8000_0000;
+    // Magnitudes up to 2^255 fit when negative, below it otherwise.
+    let is_min = eq(&product, &constant(&min))?;
+    let allowed = negative.clone().bit_and(&is_min)?;
+    let too_large = sign(&product).bit_and(&allowed.xor(&T::new_value(1)))?;
+    Ok((negate_if(&product, &negative)?, or(&overflow, &too_large)?))
+}
@@ -18,3 +18,33 @@ pub fn convert_u32_to_u8(vec: &[u32]) -> Vec<u8> {
     }
     res
 }
+
+/// Panics unless bits `lo..lo + len` all lie within a 32-bit word.
+pub fn check_bit_range(lo: u32, len: u32) {
+    assert!(
+        lo <= 32 && len <= 32 - lo,
+        "bits {}..{} are out of a 32-bit word",
+        lo,
+        lo as u64 + len as u64
+    );
+}
+
+/// `len` bits of `x` starting at bit `lo`, moved down to bit 0.
+///
+/// Panics if they do not all lie within the word.
+pub fn slice_bits(x: u32, lo: u32, len: u32) -> u32 {
+    check_bit_range(lo, len);
+    match len {
+        0 => 0,
+        32 => x,
+        _ => (x >> lo) & ((1 << len) - 1),
+    }
+}
+
+/// Byte `n` of `x`, counting from the most significant one.
+///
+/// Panics unless `n` is below 4.
+pub fn byte_of(x: u32, n: u32) -> u32 {
+    assert!(n < 4, "byte {} is out of a 32-bit word", n);
+    slice_bits(x, 24 - 8 * n, 8)
+}
@@ -11,7 +11,7 @@ impl _3DVector {
     pub fn new(depth: usize, row: usize, col: usize) -> Self {
         let area: usize = row * col;
         _3DVector {
-            data: vec![0; depth as usize * area as usize],
+            data: vec![0; depth * area],
             row,
             col,
             area,
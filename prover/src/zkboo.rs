@@ -22,8 +22,8 @@ fn find_shares(input: u32, ctx: Rc<RefCell<Vec<IKosContext>>>) -> IKosResult<IKo
     Ok(IKosVariable4P::new_share(shares, ctx))
 }
 
-type Circuit4P = fn(&[IKosVariable4P], &[u32]) -> Vec<IKosVariable4P>;
-type Circuit4V = fn(&[IKosVariable4V], &[u32]) -> IKosResult<Vec<IKosVariable4V>>;
+pub type Circuit4P = Box<dyn Fn(&[IKosVariable4P], &[u32]) -> IKosResult<Vec<IKosVariable4P>>>;
+pub type Circuit4V = Box<dyn Fn(&[IKosVariable4V], &[u32]) -> IKosResult<Vec<IKosVariable4V>>>;
 
 pub struct ZkBoo {
     num_of_round: usize,
@@ -91,7 +91,8 @@ impl ZkBoo {
                 contexts[2].ikos_view.in_data.push(ikos_input[i].value[2]);
             }
             // Circuit 실행
-            let ikos_output: Vec<IKosVariable4P> = proof.run_circuit(&ikos_input, &proof.input_pub);
+            let ikos_output: Vec<IKosVariable4P> =
+                proof.run_circuit(&ikos_input, &proof.input_pub)?;
 
             // ikos output 저장
             for (party, context) in (*ctx).borrow_mut().iter_mut().enumerate() {
@@ -123,7 +124,7 @@ impl ZkBoo {
         }
         Ok(Proof::new(
             proof.input.len(),
-            proof.input_pub.len(),
+            proof.output_len,
             vec_view.data,
             out,
             three_views,
@@ -188,7 +189,7 @@ impl ZkBoo {
             // rut circuit
             let ikos_out = proof.run_circuit(&ikos_input, &proof.input_pub)?;
 
-            let required = IKosVariable4V::require_reconstruct(&**(*ctx).borrow());
+            let required = IKosVariable4V::require_reconstruct(&(*ctx).borrow());
             let mut contexts = (*ctx).borrow_mut();
             for (branch, context) in contexts
                 .iter_mut()
@@ -204,8 +205,8 @@ impl ZkBoo {
                         }
                     } else {
                         context.ikos_view.out_data.push(ikos.value[branch]);
-                        context.out_view_ctr += 1;
                     }
+                    context.out_view_ctr += 1;
                 }
             }
 
@@ -330,7 +331,7 @@ impl ZkBoo {
         let mut sha = Sha256::new();
         let mut vec = convert_usize_to_u8(input_len);
         vec.extend(convert_usize_to_u8(output_len));
-        vec.extend(convert_u32_to_u8(&out_data));
+        vec.extend(convert_u32_to_u8(out_data));
         vec.extend(three_views);
         sha.input(vec.as_ref());
         let res = sha.result_str();
@@ -341,7 +342,7 @@ impl ZkBoo {
         let mut res = vec![];
         let mut val = 0;
         for &com in commit.iter().take(4) {
-            val = val as usize * 16 + com as usize;
+            val = val * 16 + com as usize;
         }
 
         for _ in 0..self.num_of_round {
@@ -413,35 +414,40 @@ impl Proof {
 }
 
 impl ProvingProof {
-    pub fn new(
-        input: Vec<u32>,
-        input_pub: Vec<u32>,
-        output_len: usize,
-        circuit: Circuit4P,
-    ) -> Self {
+    pub fn new<F>(input: Vec<u32>, input_pub: Vec<u32>, output_len: usize, circuit: F) -> Self
+    where
+        F: Fn(&[IKosVariable4P], &[u32]) -> IKosResult<Vec<IKosVariable4P>> + 'static,
+    {
         ProvingProof {
             input,
             input_pub,
             output_len,
-            circuit,
+            circuit: Box::new(circuit),
         }
     }
 
-    fn run_circuit(&self, ikos_input: &[IKosVariable4P], input_pub: &[u32]) -> Vec<IKosVariable4P> {
+    fn run_circuit(
+        &self,
+        ikos_input: &[IKosVariable4P],
+        input_pub: &[u32],
+    ) -> IKosResult<Vec<IKosVariable4P>> {
         (self.circuit)(ikos_input, input_pub)
     }
 }
 
 impl VerifyingProof {
-    pub fn new(
+    pub fn new<F>(
         input_len: usize,
         input_pub: Vec<u32>,
         output: Vec<u32>,
         challenge: [u8; 32],
         two_views: Vec<u8>,
         response: Vec<IKosView>,
-        circuit: Circuit4V,
-    ) -> Self {
+        circuit: F,
+    ) -> Self
+    where
+        F: Fn(&[IKosVariable4V], &[u32]) -> IKosResult<Vec<IKosVariable4V>> + 'static,
+    {
         VerifyingProof {
             input_len,
             input_pub,
@@ -449,7 +455,7 @@ impl VerifyingProof {
             challenge,
             two_views,
             response,
-            circuit,
+            circuit: Box::new(circuit),
         }
     }
 
@@ -139,6 +139,9 @@ contract ZKBoo {
         for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
             val[i] = self.value[i] ^ rhs.value[i];
         }
+        if (is_empty_context(self)) {
+            return IKosVariable4V(val, rhs.ctx, rhs.is_full);
+        }
         return IKosVariable4V(val, self.ctx, self.is_full);
     }
 
@@ -150,6 +153,14 @@ contract ZKBoo {
         return IKosVariable4V(val, self.ctx, self.is_full);
     }
 
+    function mask(IKosVariable4V memory self) internal pure returns (IKosVariable4V memory) {
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            val[i] = uint32(0) - (self.value[i] & 1);
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
     function rshift(IKosVariable4V memory self, uint32 n) internal pure returns (IKosVariable4V memory) {
         uint32[OZKB_PUBLIC_BRANCHES] memory val;
         for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
@@ -166,6 +177,50 @@ contract ZKBoo {
         return IKosVariable4V(val, self.ctx, self.is_full);
     }
 
+    function rotr(IKosVariable4V memory self, uint32 n) internal pure returns (IKosVariable4V memory) {
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            val[i] = (self.value[i] >> n) | (self.value[i] << (32 - n));
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
+    function rotl(IKosVariable4V memory self, uint32 n) internal pure returns (IKosVariable4V memory) {
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            val[i] = (self.value[i] << n) | (self.value[i] >> (32 - n));
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
+    function _byte(IKosVariable4V memory self, uint32 n) internal pure returns (IKosVariable4V memory) {
+        require(n < 4, "byte out of range");
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            val[i] = (self.value[i] >> (24 - 8 * n)) & 0xff;
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
+    function swap_bytes(IKosVariable4V memory self) internal pure returns (IKosVariable4V memory) {
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            uint32 x = self.value[i];
+            val[i] = (x >> 24) | ((x >> 8) & 0xff00) | ((x << 8) & 0xff0000) | (x << 24);
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
+    function bit_slice(IKosVariable4V memory self, uint32 lo, uint32 len) internal pure returns (IKosVariable4V memory) {
+        require(lo <= 32 && len <= 32 - lo, "bit slice out of range");
+        uint32[OZKB_PUBLIC_BRANCHES] memory val;
+        uint32 m = len == 0 ? 0 : uint32(0xffffffff) >> (32 - len);
+        for (uint i = 0; i < OZKB_PUBLIC_BRANCHES; ++i) {
+            val[i] = (self.value[i] >> lo) & m;
+        }
+        return IKosVariable4V(val, self.ctx, self.is_full);
+    }
+
     function bit_and(IKosVariable4V memory self, IKosVariable4V memory rhs) internal pure returns (IKosVariable4V memory) {
         uint32[OZKB_PUBLIC_BRANCHES] memory val;
         if (is_empty_context(self) && is_empty_context(rhs)) {
@@ -201,6 +256,14 @@ contract ZKBoo {
         return IKosVariable4V(val, self.ctx, self.is_full);
     }
 
+    function mux(
+        IKosVariable4V memory cond,
+        IKosVariable4V memory a,
+        IKosVariable4V memory b
+    ) internal pure returns (IKosVariable4V memory) {
+        return _xor(bit_and(_xor(a, b), mask(cond)), b);
+    }
+
     function get_bit(uint32 x, uint i) internal pure returns (uint32) {
         return (x >> i) & 1;
     }
@@ -308,6 +371,7 @@ contract ZKBoo {
                     if (ikos_output[j].value[branch] != ctx[branch].ikos_view.out_data[ctx[branch].out_view_ctr]) {
                         revert();
                     }
+                    ctx[branch].out_view_ctr += 1;
                 }
             }
 
@@ -440,4 +504,97 @@ contract ZKBoo {
 
         return tempUint;
     }
+
+    // Canonical word encodings of values, see `encoding.rs`.
+
+    function encode_uint256(uint256 value) internal pure returns (uint32[] memory words) {
+        words = new uint32[](8);
+        for (uint i = 0; i < 8; i++) {
+            words[7 - i] = uint32(value >> (i * 32));
+        }
+    }
+
+    function encode_int256(int256 value) internal pure returns (uint32[] memory) {
+        return encode_uint256(uint256(value));
+    }
+
+    function encode_bytes32(bytes32 value) internal pure returns (uint32[] memory) {
+        return encode_uint256(uint256(value));
+    }
+
+    function encode_address(address value) internal pure returns (uint32[] memory words) {
+        words = new uint32[](5);
+        uint160 v = uint160(value);
+        for (uint i = 0; i < 5; i++) {
+            words[4 - i] = uint32(v >> (i * 32));
+        }
+    }
+
+    function encode_bool(bool value) internal pure returns (uint32[] memory words) {
+        words = new uint32[](1);
+        words[0] = value ? 1 : 0;
+    }
+
+    function encode_bytes(bytes memory value) internal pure returns (uint32[] memory words) {
+        uint padded = (value.length + 31) / 32 * 32;
+        words = new uint32[](1 + padded / 4);
+        words[0] = uint32(value.length);
+        for (uint i = 0; i < value.length; i++) {
+            words[1 + i / 4] |= uint32(uint8(value[i])) << (24 - (i % 4) * 8);
+        }
+    }
+
+    function encode_string(string memory value) internal pure returns (uint32[] memory) {
+        return encode_bytes(bytes(value));
+    }
+
+    function decode_uint256(uint32[] memory words, uint start) internal pure returns (uint256 value) {
+        require(words.length >= start + 8, "decode_outOfBounds");
+        for (uint i = 0; i < 8; i++) {
+            value = (value << 32) | words[start + i];
+        }
+    }
+
+    function decode_int256(uint32[] memory words, uint start) internal pure returns (int256) {
+        return int256(decode_uint256(words, start));
+    }
+
+    function decode_bytes32(uint32[] memory words, uint start) internal pure returns (bytes32) {
+        return bytes32(decode_uint256(words, start));
+    }
+
+    function decode_address(uint32[] memory words, uint start) internal pure returns (address) {
+        require(words.length >= start + 5, "decode_outOfBounds");
+        uint160 value;
+        for (uint i = 0; i < 5; i++) {
+            value = (value << 32) | words[start + i];
+        }
+        return address(value);
+    }
+
+    function decode_bool(uint32[] memory words, uint start) internal pure returns (bool) {
+        require(words.length > start, "decode_outOfBounds");
+        require(words[start] <= 1, "bool out of range");
+        return words[start] == 1;
+    }
+
+    function decode_bytes(uint32[] memory words, uint start) internal pure returns (bytes memory value) {
+        require(words.length > start, "decode_outOfBounds");
+        uint len = words[start];
+        uint count = (len + 31) / 32 * 8;
+        require(words.length >= start + 1 + count, "decode_outOfBounds");
+        value = new bytes(len);
+        for (uint i = 0; i < count * 4; i++) {
+            uint8 b = uint8(words[start + 1 + i / 4] >> (24 - (i % 4) * 8));
+            if (i < len) {
+                value[i] = byte(b);
+            } else {
+                require(b == 0, "padding is not zero");
+            }
+        }
+    }
+
+    function decode_string(uint32[] memory words, uint start) internal pure returns (string memory) {
+        return string(decode_bytes(words, start));
+    }
 }
@@ -8,12 +8,12 @@ fn test_ikos_for_prover() {
     a.clone().bit_and(&b).bit_and(&a);
 }
 
-fn circuit(input: &[IKosVariable4P], input_pub: &[u32]) -> Vec<IKosVariable4P> {
+fn circuit(input: &[IKosVariable4P], input_pub: &[u32]) -> IKosResult<Vec<IKosVariable4P>> {
     let mut out = input[0].clone();
     for _ in 0..input_pub[0] {
         out = out.add_op(&input[1].clone());
     }
-    vec![out]
+    Ok(vec![out])
 }
 
 fn circuit_verifying(
@@ -106,13 +106,13 @@ fn test_proving_verifying() {
         print!("{:02x?}", ch);
     }
     print!("\",[");
-    for i in 0..3 {
+    for view in response.iter().take(3) {
         print!("[\"0x");
-        for &ch in &response[i].rand_tape_seed.clone() {
+        for &ch in &view.rand_tape_seed.clone() {
             print!("{:02x?}", ch);
         }
         print!("\",");
-        print!("{:?},{:?}],", response[i].in_data, response[i].out_data);
+        print!("{:?},{:?}],", view.in_data, view.out_data);
     }
     print!("[\"0x");
     for &ch in &response[3].rand_tape_seed.clone() {
@@ -133,12 +133,15 @@ fn test_proving_verifying() {
     assert!(res.unwrap());
 }
 
-fn circuit_for_loop(input: &[IKosVariable4P], input_pub: &[u32]) -> Vec<IKosVariable4P> {
+fn circuit_for_loop(
+    input: &[IKosVariable4P],
+    input_pub: &[u32],
+) -> IKosResult<Vec<IKosVariable4P>> {
     let mut out = IKosVariable4P::new_value(0);
     for _ in 0..input_pub[0] {
         out = out.add_op(&input[0].clone());
     }
-    vec![out]
+    Ok(vec![out])
 }
 
 fn circuit_for_loop_verifying(
@@ -198,3 +201,160 @@ fn test_proving_verifying_loop() {
 [41, 201, 14, 72, 81, 230, 17, 254, 217, 34, 102, 131, 18, 125, 161, 54, 242, 158, 81, 178, 143, 176, 238, 49, 100, 21, 192, 181, 173, 46, 99, 203, 161, 107, 248, 127, 79, 161, 117, 116, 11, 236, 208, 134, 92, 193, 35, 72, 103, 57, 211, 90, 74, 243, 109, 212, 218, 156, 133, 14, 58, 128, 14, 24, 188, 146, 8, 125, 95, 206, 83, 6, 194, 109, 206, 116, 77, 54, 162, 156, 234, 22, 202, 73, 80, 216, 17, 237, 220, 177, 5, 150, 236, 108, 139, 23, 223, 172, 158, 75, 65, 111, 59, 250, 177, 137, 10, 88, 107, 56, 120, 69, 168, 97, 160, 127, 89, 127, 203, 192, 39, 255, 63, 216, 123, 113, 103, 194, 117, 123, 63, 165, 143, 229, 123, 128, 101, 47, 56, 181, 254, 237, 42, 107, 50, 235, 64, 10, 197, 59, 118, 4, 63, 105, 64, 142, 220, 170, 132, 34, 234, 73, 111, 51, 108, 89, 103, 84, 132, 126, 86, 250, 254, 120, 79, 133, 244, 202, 235, 126, 126, 21, 226, 152, 44, 62, 50, 171, 217, 127, 77, 82]
 [240, 97, 186, 20, 53, 0, 68, 107, 78, 239, 162, 210, 133, 247, 120, 132, 225, 34, 55, 64, 86, 17, 11, 183, 124, 132, 193, 249, 78, 27, 37, 77]
 */
+
+fn circuit_mux(input: &[IKosVariable4P], input_pub: &[u32]) -> IKosResult<Vec<IKosVariable4P>> {
+    let constant = IKosVariable4P::new_value(input_pub[0]);
+    Ok(vec![
+        input[0].clone().mux(&input[1], &input[2]),
+        input[0].clone().mux(&constant, &input[2]),
+        input[0].clone().mux(&input[1], &constant),
+    ])
+}
+
+fn circuit_mux_verifying(
+    input: &[IKosVariable4V],
+    input_pub: &[u32],
+) -> IKosResult<Vec<IKosVariable4V>> {
+    let constant = IKosVariable4V::new_value(input_pub[0]);
+    Ok(vec![
+        input[0].clone().mux(&input[1], &input[2])?,
+        input[0].clone().mux(&constant, &input[2])?,
+        input[0].clone().mux(&input[1], &constant)?,
+    ])
+}
+
+#[test]
+fn test_proving_verifying_mux() {
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+    for &cond in &[0, 1] {
+        let input = vec![cond, 0xdead_beef, 0x1234_5678];
+        let in_pub = vec![7];
+        let out = if cond == 1 {
+            vec![0xdead_beef, 7, 0xdead_beef]
+        } else {
+            vec![0x1234_5678, 0x1234_5678, 7]
+        };
+        let mut res = zk_boo
+            .prove(ProvingProof::new(
+                input.clone(),
+                in_pub.clone(),
+                out.len(),
+                circuit_mux,
+            ))
+            .unwrap();
+        assert_eq!(res.output, out);
+        let challenge = ZkBoo::query_random_oracle(
+            res.input_len,
+            res.output_len,
+            &res.out_data,
+            &res.three_views,
+        );
+        let response = zk_boo.build_response(&res.views, &challenge);
+        let two_views = zk_boo.rebuild_proof(&mut res, &challenge);
+        let res = zk_boo.verify(VerifyingProof::new(
+            input.len(),
+            in_pub,
+            out,
+            challenge,
+            two_views,
+            response,
+            circuit_mux_verifying,
+        ));
+        assert!(res.unwrap());
+    }
+}
+
+fn circuit_byte_gates(
+    input: &[IKosVariable4P],
+    _input_pub: &[u32],
+) -> IKosResult<Vec<IKosVariable4P>> {
+    Ok(vec![
+        input[0].clone().rotl(8),
+        input[0].clone().rotr(4),
+        input[0].clone().byte(0),
+        input[0].clone().byte(3),
+        input[0].clone().swap_bytes(),
+        input[0].clone().bit_slice(4, 12),
+        input[0].clone().bit_slice(0, 32),
+        input[0].clone().bit_slice(32, 0),
+    ])
+}
+
+fn circuit_byte_gates_verifying(
+    input: &[IKosVariable4V],
+    _input_pub: &[u32],
+) -> IKosResult<Vec<IKosVariable4V>> {
+    Ok(vec![
+        input[0].clone().rotl(8),
+        input[0].clone().rotr(4),
+        input[0].clone().byte(0),
+        input[0].clone().byte(3),
+        input[0].clone().swap_bytes(),
+        input[0].clone().bit_slice(4, 12),
+        input[0].clone().bit_slice(0, 32),
+        input[0].clone().bit_slice(32, 0),
+    ])
+}
+
+#[test]
+fn test_proving_verifying_byte_gates() {
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+    let input = vec![0x1234_5678];
+    let out = vec![
+        0x3456_7812,
+        0x8123_4567,
+        0x12,
+        0x78,
+        0x7856_3412,
+        0x567,
+        0x1234_5678,
+        0,
+    ];
+    let mut res = zk_boo
+        .prove(ProvingProof::new(
+            input.clone(),
+            vec![],
+            out.len(),
+            circuit_byte_gates,
+        ))
+        .unwrap();
+    assert_eq!(res.output, out);
+    let challenge = ZkBoo::query_random_oracle(
+        res.input_len,
+        res.output_len,
+        &res.out_data,
+        &res.three_views,
+    );
+    let response = zk_boo.build_response(&res.views, &challenge);
+    let two_views = zk_boo.rebuild_proof(&mut res, &challenge);
+    let res = zk_boo.verify(VerifyingProof::new(
+        input.len(),
+        vec![],
+        out,
+        challenge,
+        two_views,
+        response,
+        circuit_byte_gates_verifying,
+    ));
+    assert!(res.unwrap());
+}
+
+#[test]
+#[should_panic(expected = "bits 28..36 are out of a 32-bit word")]
+fn test_bit_slice_out_of_range() {
+    ZkBoo::new(2, 3, 2, 32)
+        .prove(ProvingProof::new(vec![1], vec![], 1, |input, _| {
+            Ok(vec![input[0].clone().bit_slice(28, 8)])
+        }))
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "byte 4 is out of a 32-bit word")]
+fn test_byte_out_of_range() {
+    ZkBoo::new(2, 3, 2, 32)
+        .prove(ProvingProof::new(vec![1], vec![], 1, |input, _| {
+            Ok(vec![input[0].clone().byte(4)])
+        }))
+        .unwrap();
+}
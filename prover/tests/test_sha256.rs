@@ -0,0 +1,113 @@
+use zoker_prover::ikos::{IKosResult, IKosVariable4P, IKosVariable4V};
+use zoker_prover::sha256::sha256;
+use zoker_prover::zkboo::{ProvingProof, VerifyingProof, ZkBoo};
+
+fn circuit(input: &[IKosVariable4P], _input_pub: &[u32]) -> IKosResult<Vec<IKosVariable4P>> {
+    sha256(input)
+}
+
+fn circuit_verifying(
+    input: &[IKosVariable4V],
+    _input_pub: &[u32],
+) -> IKosResult<Vec<IKosVariable4V>> {
+    sha256(input)
+}
+
+fn prove_and_verify(input: Vec<u32>, digest: Vec<u32>) {
+    let zk_boo = ZkBoo::new(2, 3, 2, 32);
+    let mut res = zk_boo
+        .prove(ProvingProof::new(input.clone(), vec![], 8, circuit))
+        .unwrap();
+    assert_eq!(res.output, digest);
+    let challenge = ZkBoo::query_random_oracle(
+        res.input_len,
+        res.output_len,
+        &res.out_data,
+        &res.three_views,
+    );
+    let response = zk_boo.build_response(&res.views, &challenge);
+    let two_views = zk_boo.rebuild_proof(&mut res, &challenge);
+    let res = zk_boo.verify(VerifyingProof::new(
+        input.len(),
+        vec![],
+        digest,
+        challenge,
+        two_views,
+        response,
+        circuit_verifying,
+    ));
+    assert!(res.unwrap());
+}
+
+#[test]
+fn test_sha256_constant_message() {
+    let digest = sha256(&[]).unwrap();
+    let digest = digest
+        .iter()
+        .map(|word: &IKosVariable4P| word.value[0])
+        .collect::<Vec<u32>>();
+    assert_eq!(
+        digest,
+        vec![
+            0xe3b0_c442,
+            0x98fc_1c14,
+            0x9afb_f4c8,
+            0x996f_b924,
+            0x27ae_41e4,
+            0x649b_934c,
+            0xa495_991b,
+            0x7852_b855
+        ]
+    );
+}
+
+#[test]
+fn test_sha256_single_block() {
+    // "abcd"
+    prove_and_verify(
+        vec![0x6162_6364],
+        vec![
+            0x88d4_266f,
+            0xd4e6_338d,
+            0x13b8_45fc,
+            0xf289_579d,
+            0x209c_8978,
+            0x23b9_217d,
+            0xa3e1_6193,
+            0x6f03_1589,
+        ],
+    );
+}
+
+#[test]
+fn test_sha256_two_blocks() {
+    // "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+    prove_and_verify(
+        vec![
+            0x6162_6364,
+            0x6263_6465,
+            0x6364_6566,
+            0x6465_6667,
+            0x6566_6768,
+            0x6667_6869,
+            0x6768_696a,
+            0x6869_6a6b,
+            0x696a_6b6c,
+            0x6a6b_6c6d,
+            0x6b6c_6d6e,
+            0x6c6d_6e6f,
+            0x6d6e_6f70,
+            0x6e6f_7071,
+        ],
+        vec![
+            0x248d_6a61,
+            0xd206_38b8,
+            0xe5c0_2693,
+            0x0c3e_6039,
+            0xa33c_e459,
+            0x64ff_2167,
+            0xf6ec_edd4,
+            0x19db_06c1,
+        ],
+    );
+}
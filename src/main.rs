@@ -1,4 +1,125 @@
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::process;
+use zoker_compiler::bristol::BristolCircuit;
+use zoker_compiler::layout::manifest;
+use zoker_compiler::lowering::CircuitOptions;
+use zoker_compiler::rewriter::rewrite_program;
+use zoker_compiler::stats::{function_stats, ProofParams};
+use zoker_compiler::symbol::Contract;
+use zoker_parser::parser;
+
+const USAGE: &str = "usage:
+  zoker stats <file> [--checked] [--optimize] [--rounds <n>]
+  zoker bristol <file> <contract>.<function> [--checked] [--optimize]
+  zoker manifest <file> [--checked]";
+
+struct Args {
+    positional: Vec<String>,
+    options: CircuitOptions,
+    params: ProofParams,
+}
+
 fn main() {
-    // TODO: CLI Program should be written.
-    println!("Zoker for using zero-knowledge in blockchain")
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let result = match args.first().map(String::as_str) {
+        Some("stats") => parse_args(&args[1..]).and_then(stats),
+        Some("bristol") => parse_args(&args[1..]).and_then(bristol),
+        Some("manifest") => parse_args(&args[1..]).and_then(manifests),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args {
+        positional: vec![],
+        options: CircuitOptions::default(),
+        params: ProofParams::default(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--checked" => parsed.options.checked_arithmetic = true,
+            "--optimize" => parsed.options.optimize = true,
+            "--rounds" => {
+                parsed.params.num_of_round = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| USAGE.to_string())?;
+            }
+            _ if !arg.starts_with("--") => parsed.positional.push(arg.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn load(path: &str) -> Result<Vec<Contract>, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let program = parser::parse_program(&source).map_err(|err| format!("{}: {}", path, err))?;
+    rewrite_program(&program).map_err(|err| format!("{}: {:?}", path, err))
+}
+
+/// Prints the cost report of every function of a source file.
+fn stats(args: Args) -> Result<(), String> {
+    let path = match args.positional.as_slice() {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    for contract in &load(path)? {
+        for function in &contract.functions {
+            let stats = function_stats(function, args.options, &args.params)
+                .map_err(|err| format!("{}.{}: {:?}", contract.name, function.name, err))?;
+            println!("{}.{}", contract.name, function.name);
+            for line in stats.to_string().lines() {
+                println!("  {}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints one function as a Bristol Fashion circuit.
+fn bristol(args: Args) -> Result<(), String> {
+    let (path, name) = match args.positional.as_slice() {
+        [path, name] => (path, name),
+        _ => return Err(USAGE.to_string()),
+    };
+    for contract in &load(path)? {
+        for function in &contract.functions {
+            if format!("{}.{}", contract.name, function.name) == *name {
+                let circuit = BristolCircuit::from_function(function, args.options)
+                    .map_err(|err| format!("{}: {:?}", name, err))?;
+                print!("{}", circuit);
+                return Ok(());
+            }
+        }
+    }
+    Err(format!("{}: no function {}", path, name))
+}
+
+/// Prints the word layout of every function of a source file as JSON.
+fn manifests(args: Args) -> Result<(), String> {
+    let path = match args.positional.as_slice() {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    let contracts = load(path)?
+        .iter()
+        .map(|contract| {
+            let functions = contract
+                .functions
+                .iter()
+                .map(|function| manifest(function, args.options))
+                .collect::<Vec<_>>();
+            json!({"contract": contract.name, "functions": functions})
+        })
+        .collect::<Vec<_>>();
+    println!("{}", json!(contracts));
+    Ok(())
 }